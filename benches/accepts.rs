@@ -0,0 +1,35 @@
+//! 比较01字母表上`DenseDFA::accepts`的快速路径和通用路径。
+//!
+//! 运行`cargo bench --bench accepts`。两个DFA识别同一个语言，
+//! 其中一个的字母表多了一个用不到的字符`2`，所以只能走通用路径。
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+fn bench_accepts(c: &mut Criterion) {
+    let binary = wasm_fa::re_to_dfa("(0|1)*1(0|1)(0|1)").unwrap();
+    let generic = binary.with_extended_alphabet(b"2");
+    let input: String = (0..1_000_000u32)
+        .map(|i| if i.count_ones() % 3 == 1 { '1' } else { '0' })
+        .collect();
+    assert_eq!(binary.accepts(&input), generic.accepts(&input));
+
+    let mut group = c.benchmark_group("accepts");
+    group.sample_size(20);
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_with_input(
+        BenchmarkId::new("binary", input.len()),
+        &input,
+        |b, input| b.iter(|| black_box(binary.accepts(black_box(input)))),
+    );
+    group.bench_with_input(
+        BenchmarkId::new("generic", input.len()),
+        &input,
+        |b, input| b.iter(|| black_box(generic.accepts(black_box(input)))),
+    );
+    group.finish();
+}
+
+criterion_group!(benches, bench_accepts);
+criterion_main!(benches);
@@ -0,0 +1,75 @@
+//! `DenseDFA::minimize`在大DFA上的耗时。
+//!
+//! 运行`cargo bench --bench minimize`。被测的DFA识别“二进制数能被m整除”，
+//! 但是每个余数都有两份完全等价的状态，所以极小化要把2m个状态合并成m个。
+//! 可区分状态表只保存 n(n-1)/2 个状态对，之前的二维数组是 n(n-1) 个，
+//! 开始时会打印两种存储方式的状态对个数。
+//!
+//! `compare`组在一个约500个状态的DFA上比较填表法`minimize`和Hopcroft算法`minimize_hopcroft`。
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use wasm_fa::dfa::{CompletedDfa, DenseDFA};
+
+/// 构造一个2m个状态的冗余DFA，m为奇数时极小DFA有m个状态，再加上陷阱状态。
+fn redundant_mod(m: u128) -> DenseDFA {
+    let mut table = vec![vec![0, 0]];
+    for k in 0..2 * m {
+        let (residue, copy) = (k % m, k / m);
+        let row = (0..2)
+            .map(|bit| {
+                let next = (2 * residue + bit) % m;
+                // 读到1时换到另一份，两份状态交替使用
+                next + m * (copy ^ bit) + 1
+            })
+            .collect();
+        table.push(row);
+    }
+    DenseDFA::from_table(b"01".to_vec(), &table, 1, &[1, m + 1])
+}
+
+fn bench_minimize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("minimize");
+    group.sample_size(10);
+    for m in [101, 501, 1501] {
+        let dfa = redundant_mod(m);
+        let n = dfa.number_of_states() as u64;
+        println!(
+            "{} states: {} pairs in the triangular table, {} in the old square table",
+            n,
+            n * (n - 1) / 2,
+            n * (n - 1)
+        );
+        assert_eq!(
+            dfa.minimize().number_of_states(),
+            m + 1,
+            "the two copies of each residue should be merged"
+        );
+        group.bench_with_input(BenchmarkId::from_parameter(n), &dfa, |b, dfa| {
+            b.iter(|| black_box(dfa.minimize()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_compare(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compare");
+    group.sample_size(10);
+    let dfa = redundant_mod(251);
+    assert_eq!(
+        dfa.minimize_hopcroft().number_of_states(),
+        dfa.minimize().number_of_states()
+    );
+    let n = dfa.number_of_states();
+    group.bench_with_input(BenchmarkId::new("table_filling", n), &dfa, |b, dfa| {
+        b.iter(|| black_box(dfa.minimize()))
+    });
+    group.bench_with_input(BenchmarkId::new("hopcroft", n), &dfa, |b, dfa| {
+        b.iter(|| black_box(dfa.minimize_hopcroft()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_minimize, bench_compare);
+criterion_main!(benches);
@@ -0,0 +1,98 @@
+use std::fmt;
+
+use crate::dfa::{DenseDFA, ProductError};
+use crate::nfa::Builder;
+use crate::ConversionError;
+
+/// 包装了一个`DenseDFA`，用链式调用组合语言。
+///
+/// 每个方法都消耗`self`并返回新的`Automaton`，积构造可能超过状态数上限，所以`union`等返回`Result`：
+///
+/// ```text
+/// let dfa = Automaton::from_regex("0*")?
+///     .union(Automaton::from_regex("1*")?)?
+///     .star()
+///     .minimize()
+///     .to_dfa();
+/// ```
+#[derive(Clone)]
+pub struct Automaton(DenseDFA);
+
+impl Automaton {
+    /// 从正则表达式构造。
+    ///
+    /// 用`NFA::determinize`确定化，所以字母表不限于{0,1}，也没有128个NFA状态的限制。
+    pub fn from_regex(re: &str) -> Result<Automaton, ConversionError> {
+        let nfa = Builder::new()
+            .build_nfa_from_re(re)
+            .map_err(ConversionError::Syntax)?;
+        Ok(Automaton(nfa.determinize()).minimize())
+    }
+
+    /// 并集。
+    pub fn union(self, other: Automaton) -> Result<Automaton, ProductError> {
+        self.0.union(&other.0).map(Automaton)
+    }
+
+    /// 交集。
+    pub fn intersect(self, other: Automaton) -> Result<Automaton, ProductError> {
+        self.0.intersect(&other.0).map(Automaton)
+    }
+
+    /// 差集：被`self`接受、但是不被`other`接受的字符串。
+    pub fn difference(self, other: Automaton) -> Result<Automaton, ProductError> {
+        self.0.difference(&other.0).map(Automaton)
+    }
+
+    /// 连接。
+    pub fn concat(self, other: Automaton) -> Automaton {
+        Automaton(self.0.concat(&other.0))
+    }
+
+    /// Kleene闭包。
+    pub fn star(self) -> Automaton {
+        Automaton(self.0.star())
+    }
+
+    /// 在自己的字母表上求补集。
+    pub fn complement(self) -> Automaton {
+        Automaton(self.0.complement())
+    }
+
+    /// 极小化。已经是极小的时候原样返回。
+    pub fn minimize(self) -> Automaton {
+        Automaton(self.0.minimize())
+    }
+
+    /// 判断是否接受`input`。
+    pub fn accepts(&self, input: &str) -> bool {
+        self.0.accepts(input)
+    }
+
+    /// 判断两个自动机是否接受相同的语言。
+    pub fn equivalent(&self, other: &Automaton) -> bool {
+        self.0.equivalent(&other.0)
+    }
+
+    /// 借用内部的DFA。
+    pub fn as_dfa(&self) -> &DenseDFA {
+        &self.0
+    }
+
+    /// 取出内部的DFA。
+    pub fn to_dfa(self) -> DenseDFA {
+        self.0
+    }
+}
+
+impl From<DenseDFA> for Automaton {
+    fn from(dfa: DenseDFA) -> Self {
+        Automaton(dfa)
+    }
+}
+
+impl fmt::Display for Automaton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
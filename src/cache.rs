@@ -0,0 +1,119 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use crate::dfa::DenseDFA;
+
+/// 带缓存的`re_to_dfa`，可以在多个线程之间共享。
+///
+/// 以正则表达式字符串为键缓存转换结果，命中时返回DFA的克隆。缓存有容量上限，
+/// 满了之后淘汰最久没有被用到的结果（LRU），所以长时间运行的服务不会无限占用内存。
+/// 容量为0时不缓存任何结果。
+pub struct CachingConverter {
+    capacity: usize,
+    inner: Mutex<Lru>,
+}
+
+/// 用一个递增的时间戳记录每个键最后一次被用到的时间，`order`按时间戳排序，第一个就是最久没用的。
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<String, (DenseDFA, u64)>,
+    order: BTreeMap<u64, String>,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl Lru {
+    fn touch(&mut self, re: &str) -> Option<DenseDFA> {
+        self.clock += 1;
+        let clock = self.clock;
+        let (dfa, stamp) = self.entries.get_mut(re)?;
+        let old = std::mem::replace(stamp, clock);
+        let dfa = dfa.clone();
+        self.order.remove(&old);
+        self.order.insert(clock, re.to_string());
+        Some(dfa)
+    }
+
+    fn insert(&mut self, re: &str, dfa: DenseDFA, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        // 别的线程可能已经插入了同一个键
+        if let Some((_, stamp)) = self.entries.remove(re) {
+            self.order.remove(&stamp);
+        }
+        while self.entries.len() >= capacity {
+            let (_, oldest) = self
+                .order
+                .pop_first()
+                .expect("order and entries have the same size");
+            self.entries.remove(&oldest);
+        }
+        self.clock += 1;
+        self.entries.insert(re.to_string(), (dfa, self.clock));
+        self.order.insert(self.clock, re.to_string());
+    }
+}
+
+impl CachingConverter {
+    /// 创建一个最多缓存`capacity`个结果的转换器。
+    pub fn new(capacity: usize) -> Self {
+        CachingConverter {
+            capacity,
+            inner: Mutex::new(Lru::default()),
+        }
+    }
+
+    /// 和`re_to_dfa`相同，但是先查缓存。
+    ///
+    /// 转换时不持有锁，所以多个线程可以同时转换不同的正则表达式。
+    /// 两个线程同时转换同一个没有缓存的正则表达式时，两边都会算一遍，结果只保留一份。
+    /// 转换失败的结果不会被缓存。
+    pub fn convert(&self, re: &str) -> Result<DenseDFA, String> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            if let Some(dfa) = inner.touch(re) {
+                inner.hits += 1;
+                return Ok(dfa);
+            }
+            inner.misses += 1;
+        }
+        let dfa = crate::re_to_dfa(re)?;
+        self.inner
+            .lock()
+            .unwrap()
+            .insert(re, dfa.clone(), self.capacity);
+        Ok(dfa)
+    }
+
+    /// 缓存的容量。
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// 现在缓存了多少个结果。
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// 缓存是否为空。
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 命中缓存的次数。
+    pub fn hits(&self) -> u64 {
+        self.inner.lock().unwrap().hits
+    }
+
+    /// 没有命中缓存、需要重新转换的次数。
+    pub fn misses(&self) -> u64 {
+        self.inner.lock().unwrap().misses
+    }
+
+    /// 清空缓存，命中和未命中的计数也清零。
+    pub fn clear(&self) {
+        *self.inner.lock().unwrap() = Lru::default();
+    }
+}
@@ -1,13 +1,33 @@
 use crate::nfa::NFA;
 use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet, VecDeque};
 use std::fmt;
 
+// 边集形式的DFA还没写完，暂时没人用。
+#[allow(dead_code)]
 mod edge;
 
 /// DFA的极小化相关的方法。
 pub mod minimize;
 
+/// DFA所表示的语言的性质分析相关的方法。
+pub mod analysis;
+
+// 把DFA生成为rust源代码。
+mod codegen;
+
+// DFA上的运算，例如反转、求交集。
+mod operation;
+
+// 紧凑的JSON格式，给网页前端自己绘制自动机用。
+mod json;
+
+/// 用状态消去法把DFA转换为正则表达式。
+pub mod state_elimination;
+
+/// 在DFA的转移上附加输出字符得到的简单转换器。
+pub mod transducer;
+
 /// 传入一个集合的索引的子集，返回一个无符号数来*表示*这个子集。
 /// 从NFA构造DFA的过程特别需要这个宏。
 ///
@@ -39,6 +59,7 @@ type StateId = u128;
 /// 在DFA中，则用HashMap储存所有的状态。
 ///
 /// 与之相对的“稠密”DFA，是指用一个数组储存所有的状态转移函数，而不抽象出State结构体。
+#[allow(dead_code)]
 trait SparseDFA {
     type State: State;
     type Error;
@@ -52,6 +73,9 @@ trait SparseDFA {
 }
 
 /// 已经构造完成的DFA，可以读取状态转移函数、字母表、开始状态等信息。
+///
+/// 识别字符串、转换为正则文法、求最短的被接受字符串等常用操作都是这个trait的默认方法，
+/// 所以可以写出同时适用于`DFA01`和`DenseDFA`的代码，例如接受`&dyn CompletedDfa<Alphabet = Vec<u8>>`的函数。
 pub trait CompletedDfa {
     type Alphabet: Alphabet;
 
@@ -60,6 +84,20 @@ pub trait CompletedDfa {
     fn accept_states(&self) -> &HashSet<StateId>;
     fn number_of_states(&self) -> StateId;
 
+    /// 所有状态的id，从小到大排列。
+    ///
+    /// 默认实现是 0..状态数，适用于状态id连续的DFA。`DFA01`的状态id是NFA状态子集的编码，不连续，需要自己实现。
+    fn state_ids(&self) -> Vec<StateId> {
+        (0..self.number_of_states()).collect()
+    }
+
+    /// 所有接受状态，从小到大排列。
+    ///
+    /// `accept_states`返回的是HashSet，迭代顺序不确定，需要稳定输出的地方（表格、状态图、测试）都应该用这个方法。
+    fn accept_states_sorted(&self) -> Vec<StateId> {
+        self.accept_states().iter().cloned().sorted().collect()
+    }
+
     /// 将这个DFA转换为Graphviz的dot语言，用于绘制状态转移图。
     fn to_dot(&self) -> String;
 
@@ -67,45 +105,248 @@ pub trait CompletedDfa {
     /// 也就是说，这个函数会返回从状态from经过输入input到达的状态。
     fn delta(&self, from: StateId, input: u8) -> StateId;
 
-    fn to_fmt_output(&self) -> String {
-        let mut output = String::from("\t0\t1\n");
-        let start_state = self.start_state();
-        let accept_states = self.accept_states();
+    /// 陷阱状态的id。陷阱状态不是接受状态，并且所有转移都指向它自己。
+    ///
+    /// 默认是0号状态：`DenseDFA`的0号状态是陷阱状态，`DFA01`的0号状态是NFA状态的空集，也是陷阱状态。
+    /// 没有陷阱状态的DFA应该返回None。
+    fn trap_state(&self) -> Option<StateId> {
+        Some(0)
+    }
 
-        for i in 1..self.number_of_states() {
-            if accept_states.contains(&i) {
-                output.push('*');
+    /// 判断这个DFA是否接受输入的字符串。输入中有字母表之外的字符时返回false。
+    ///
+    /// 只用到了`start_state`、`delta`、`accept_states`、`alphabet`和`trap_state`，进入陷阱状态之后直接返回false。
+    fn accepts(&self, input: &str) -> bool {
+        let alphabet: Vec<u8> = self.alphabet().to_iter().collect();
+        let trap = self.trap_state();
+        let mut state = self.start_state();
+        for byte in input.bytes() {
+            if !alphabet.contains(&byte) || Some(state) == trap {
+                return false;
             }
-            if i == start_state {
-                output.push_str(&format!("#q{}\t", i));
-            } else {
-                output.push_str(&format!("q{}\t", i));
+            state = self.delta(state, byte);
+        }
+        self.accept_states().contains(&state)
+    }
+
+    /// 返回这个DFA接受的最短字符串。长度相同时，按字母表顺序取最小的那个。
+    ///
+    /// 如果DFA不接受任何字符串，返回None。
+    fn shortest_accepted(&self) -> Option<Vec<u8>> {
+        // 记录每个状态的前驱状态和进入它时读的字符，用于最后还原字符串。
+        let mut previous: HashMap<StateId, Option<(StateId, u8)>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        previous.insert(self.start_state(), None);
+        queue.push_back(self.start_state());
+
+        while let Some(state) = queue.pop_front() {
+            if self.accept_states().contains(&state) {
+                let mut word = Vec::new();
+                let mut state = state;
+                while let Some((prev, input)) = previous[&state] {
+                    word.push(input);
+                    state = prev;
+                }
+                word.reverse();
+                return Some(word);
+            }
+            for input in self.alphabet().to_iter() {
+                let next = self.delta(state, input);
+                if let Entry::Vacant(entry) = previous.entry(next) {
+                    entry.insert(Some((state, input)));
+                    queue.push_back(next);
+                }
             }
+        }
+        None
+    }
 
-            macro_rules! state_or_none {
-                ($state:expr) => {
-                    if $state == 0 {
-                        "N".to_string()
-                    } else {
-                        format!("q{}", $state)
-                    }
-                };
+    /// 判断这个DFA是否接受空串，也就是开始状态是否是接受状态。
+    fn accepts_empty_string(&self) -> bool {
+        self.accept_states().contains(&self.start_state())
+    }
+
+    /// 将这个DFA转换为正则文法。
+    ///
+    /// 如果DFA接受空串，开始符号多一个空产生式`S -> ε`。
+    fn to_rg(&self) -> String {
+        completed_to_rg(self)
+    }
+
+    /// 以表格的形式输出这个DFA，使用默认的标记：`*`表示接受状态，`#`表示开始状态，`N`表示陷阱状态。
+    fn to_fmt_output(&self) -> String {
+        self.to_fmt_output_with(&FmtOptions::default())
+    }
+
+    /// 以表格的形式输出这个DFA，标记字符由`options`指定。
+    ///
+    /// 每个字符一列，列按字符从小到大排列，所以字母表是{0,1}时表头总是`\t0\t1`。
+    fn to_fmt_output_with(&self, options: &FmtOptions) -> String {
+        completed_to_fmt_output(self, options)
+    }
+}
+
+/// `CompletedDfa::to_rg`的默认实现。单独写成函数，这样覆盖了这个方法的实现也能调用它。
+fn completed_to_rg<D: CompletedDfa + ?Sized>(dfa: &D) -> String {
+    // 所有转移都指向陷阱状态的状态，不会产生有用的候选式。
+    let is_no_way_out = |state: StateId| {
+        dfa.alphabet()
+            .to_iter()
+            .all(|input| dfa.delta(state, input) == 0)
+    };
+
+    let mut rg = String::new();
+    if dfa.accepts_empty_string() {
+        rg.push_str(&format!("S -> q{} | ε\n", dfa.start_state()));
+    } else {
+        rg.push_str(&format!("S -> q{}\n", dfa.start_state()));
+    }
+    for from in dfa.state_ids().into_iter().filter(|id| *id != 0) {
+        // 这个变量代表产生式的右部，也就是候选式。
+        let mut candidate = String::new();
+        for input in dfa.alphabet().to_iter() {
+            let to = dfa.delta(from, input);
+            if dfa.accept_states().contains(&to) {
+                candidate.push_str(&format!(" {} |", input as char));
             }
-            let state0_str = state_or_none!(self.delta(i, b'0'));
-            let state1_str = state_or_none!(self.delta(i, b'1'));
+            if to == 0 || is_no_way_out(to) {
+                continue;
+            }
+            candidate.push_str(&format!(" {}q{} |", input as char, to));
+        }
+        if candidate.pop().is_some() {
+            rg.push_str(&format!("q{} ->{}\n", from, candidate));
+        }
+    }
+    rg
+}
+
+/// `CompletedDfa::to_fmt_output_with`的默认实现。
+fn completed_to_fmt_output<D: CompletedDfa + ?Sized>(dfa: &D, options: &FmtOptions) -> String {
+    let symbols: Vec<u8> = dfa.alphabet().to_iter().sorted().collect();
+    let mut output = String::new();
+    for input in symbols.iter() {
+        output.push_str(&format!("\t{}", *input as char));
+    }
+    output.push('\n');
+    let start_state = dfa.start_state();
+    let accept_states = dfa.accept_states();
+
+    for i in dfa.state_ids().into_iter().filter(|id| *id != 0) {
+        if accept_states.contains(&i) {
+            output.push(options.accept);
+        }
+        if i == start_state {
+            output.push_str(&format!("{}q{}\t", options.start, i));
+        } else {
+            output.push_str(&format!("q{}\t", i));
+        }
+
+        macro_rules! state_or_none {
+            ($state:expr) => {
+                if $state == 0 {
+                    options.none.to_string()
+                } else {
+                    format!("q{}", $state)
+                }
+            };
+        }
+        for input in symbols.iter() {
+            output.push_str(&format!("{}\t", state_or_none!(dfa.delta(i, *input))));
+        }
+
+        output.push('\n');
+    }
+    output
+}
 
-            output.push_str(&format!("{}\t{}\t", state0_str, state1_str));
+/// `to_fmt_output_with`使用的标记字符。
+#[derive(Debug, Clone, Copy)]
+pub struct FmtOptions<'a> {
+    /// 写在接受状态前面的字符。
+    pub accept: char,
+    /// 写在开始状态前面的字符。
+    pub start: char,
+    /// 转移到陷阱状态时，表格里写的内容。
+    pub none: &'a str,
+}
+
+impl Default for FmtOptions<'_> {
+    fn default() -> Self {
+        FmtOptions {
+            accept: '*',
+            start: '#',
+            none: "N",
+        }
+    }
+}
+
+/// `to_dot_opts`使用的选项。默认不显示陷阱状态，状态用id作为标签。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DotOptions<'a> {
+    /// 显示陷阱状态和指向它的边。
+    pub show_trap: bool,
+    /// 把`DFA01`的状态显示成它对应的NFA状态子集，例如`{0,2}`，陷阱状态显示为`∅`。
+    /// `DFA01`的状态id就是子集的编码，直接显示id很难看出是哪些状态。对`DenseDFA`没有作用。
+    pub subset_labels: bool,
+    /// 使用Graphviz的HTML-like标签（`label=<...>`）：第一行是状态id，第二行用小一号的字显示`details`中的说明。
+    /// `DFA01`打开了`subset_labels`时，没有说明的状态第二行显示它的子集。
+    pub html_labels: bool,
+    /// `html_labels`打开时显示在状态id下面的说明，例如幂集构造中的子集或者等价类。
+    /// 其中的`<`、`>`、`&`、`"`会被转义，所以可以放任意文字。
+    pub details: Option<&'a HashMap<StateId, String>>,
+    /// 按到开始状态的BFS距离分层，每层输出一个`{ rank=same; ... }`，让同一层的状态排在同一列。
+    /// 有限语言的trim自动机是一个DAG，分层之后比Graphviz默认的布局清楚很多。对`DFA01`没有作用。
+    pub rank_by_distance: bool,
+}
+
+impl DotOptions<'_> {
+    /// 按照选项生成一个状态的节点声明。`detail`是没有用户说明时第二行的默认内容。
+    fn node(&self, state: StateId, detail: Option<String>) -> String {
+        if !self.html_labels {
+            return format!("{};\n", state);
+        }
+        let detail = self
+            .details
+            .and_then(|details| details.get(&state).cloned())
+            .or(detail);
+        match detail {
+            Some(detail) => format!(
+                "{} [label = <{}<BR/><FONT POINT-SIZE=\"10\">{}</FONT>>];\n",
+                state,
+                state,
+                escape_html(&detail)
+            ),
+            None => format!("{} [label = <{}>];\n", state, state),
+        }
+    }
+}
 
-            output.push('\n');
+/// 转义HTML-like标签和XML中的特殊字符，避免生成不合法的dot或GraphML。
+///
+/// XML 1.0不允许0x20以下的控制字符，连`&#1;`这样的字符引用也不行，所以把它们写成`\x01`这样的文字。
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+            _ => escaped.push(c),
         }
-        output
     }
+    escaped
 }
 
 /// DFA的字母表，可以获取大小，可以转换为迭代器。
 pub trait Alphabet {
     type Iter: Iterator<Item = u8>;
     fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
     fn to_iter(&self) -> Self::Iter;
 }
 
@@ -115,7 +356,7 @@ impl Alphabet for (u8, u8) {
         2
     }
     fn to_iter(&self) -> Self::Iter {
-        (self.0..=self.1).into_iter()
+        self.0..=self.1
     }
 }
 
@@ -129,11 +370,148 @@ impl Alphabet for Vec<u8> {
     }
 }
 
+/// 字母表不满足要求时返回的错误。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlphabetError {
+    /// 这个字符不在DFA的字母表中。
+    UnknownSymbol(u8),
+    /// 参与运算的两个DFA的字母表不一样。
+    Mismatch { left: Vec<u8>, right: Vec<u8> },
+}
+
+impl fmt::Display for AlphabetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlphabetError::UnknownSymbol(input) => {
+                write!(f, "symbol '{}' is not in the alphabet", *input as char)
+            }
+            AlphabetError::Mismatch { left, right } => write!(
+                f,
+                "alphabets do not match: {:?} and {:?}",
+                String::from_utf8_lossy(left),
+                String::from_utf8_lossy(right)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AlphabetError {}
+
+/// 积构造默认的状态数上限。对正常的作业和演示来说足够大，又能避免wasm实例内存耗尽。
+pub const DEFAULT_PRODUCT_LIMIT: usize = 1 << 20;
+
+/// 从NFA构造DFA时默认的状态数上限，理由和`DEFAULT_PRODUCT_LIMIT`相同。
+pub const DEFAULT_DETERMINIZE_LIMIT: usize = 1 << 20;
+
+/// 积运算（交、并等）的错误。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProductError {
+    /// 两个DFA的字母表不一致。
+    ///
+    /// 只有`product_strict`、带`_strict`后缀的运算和`is_equivalent`会返回这个错误；
+    /// `intersect`、`union`等运算会把字母表自动扩展到并集，不会返回它。
+    Alphabet(AlphabetError),
+    /// 可达的状态对的数量超过了上限，`reached`是超过上限时已经构造出的状态数。
+    ProductTooLarge { reached: usize },
+    /// `intersect_many`没有给出任何DFA。没有字母表就构造不出“接受所有字符串”的DFA。
+    NoOperands,
+}
+
+impl From<AlphabetError> for ProductError {
+    fn from(error: AlphabetError) -> Self {
+        ProductError::Alphabet(error)
+    }
+}
+
+impl fmt::Display for ProductError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProductError::Alphabet(error) => write!(f, "{}", error),
+            ProductError::ProductTooLarge { reached } => {
+                write!(
+                    f,
+                    "product automaton is too large: reached {} states",
+                    reached
+                )
+            }
+            ProductError::NoOperands => write!(f, "no DFA to intersect"),
+        }
+    }
+}
+
+impl std::error::Error for ProductError {}
+
+/// 修改`DenseDFA`（`mark_accept`、`add_transition_checked`等）时的错误。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    /// 状态id超出了范围，`number_of_states`是DFA的状态数。
+    StateOutOfRange {
+        id: StateId,
+        number_of_states: StateId,
+    },
+    /// 这个字符不在DFA的字母表中。
+    UnknownSymbol(u8),
+    /// 完全DFA的0号状态是陷阱状态，不能变成接受状态，也不能有别的转移。
+    TrapState,
+    /// 这个转移已经有定义了，而且指向别的状态。覆盖它需要先决定怎么处理原来的转移，所以直接报错。
+    CellOccupied {
+        from: StateId,
+        input: u8,
+        existing: StateId,
+    },
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::StateOutOfRange {
+                id,
+                number_of_states,
+            } => write!(
+                f,
+                "state id {} is out of range (0..{})",
+                id, number_of_states
+            ),
+            EditError::UnknownSymbol(input) => {
+                write!(f, "symbol '{}' is not in the alphabet", *input as char)
+            }
+            EditError::TrapState => write!(f, "state 0 is the trap state"),
+            EditError::CellOccupied {
+                from,
+                input,
+                existing,
+            } => write!(
+                f,
+                "transition from {} on '{}' is already defined (to {})",
+                from, *input as char, existing
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+impl DenseDFA {
+    /// 检查两个DFA的字母表是否相同（不考虑字符的顺序）。
+    fn check_same_alphabet(&self, other: &DenseDFA) -> Result<(), AlphabetError> {
+        let mut left = self.alphabet.clone();
+        let mut right = other.alphabet.clone();
+        left.sort_unstable();
+        right.sort_unstable();
+        if left == right {
+            Ok(())
+        } else {
+            Err(AlphabetError::Mismatch { left, right })
+        }
+    }
+}
+
 /// 稀疏DFA。
-/// 01的意思是这个DFA的字母表只有0和1，适用于大作业给的测试用例。
+/// 01的意思是这个DFA最初只支持字母表{0,1}，适用于大作业给的测试用例。
+/// 现在字母表可以是任意字符，但是状态仍然用NFA状态子集的u128编码表示，所以NFA最多只能有128个状态。
 pub struct DFA01 {
     states: HashMap<StateId, State01>,
-    alphabet: (u8, u8),
+    alphabet: Vec<u8>,
     start_state: Option<StateId>,
     accept_states: HashSet<StateId>,
 }
@@ -157,6 +535,78 @@ impl DFA01 {
         self.to_dot()
     }
 
+    /// 按照给定的选项输出dot。
+    ///
+    /// 和`to_dot`相比，开始状态前面多了一个指向它的箭头，
+    /// 同一对状态之间的两条边合并成一条，标签是`0,1`。
+    pub fn to_dot_opts(&self, options: &DotOptions) -> String {
+        let shown = |state: StateId| options.show_trap || state != 0;
+        let node = |state: StateId| {
+            if options.html_labels {
+                let subset = Some(Self::subset_label(state)).filter(|_| options.subset_labels);
+                options.node(state, subset)
+            } else if options.subset_labels {
+                format!("{} [label = \"{}\"];\n", state, Self::subset_label(state))
+            } else {
+                format!("{};\n", state)
+            }
+        };
+
+        let mut dot = String::new();
+        dot.push_str("digraph DFA {\n");
+        dot.push_str("rankdir=LR;\n");
+        dot.push_str("node [shape = doublecircle];\n");
+        for state_id in self.accept_states_sorted() {
+            dot.push_str(&node(state_id));
+        }
+        dot.push_str("node [shape = circle];\n");
+        for state_id in self.state_ids() {
+            if shown(state_id) && !self.accept_states.contains(&state_id) {
+                dot.push_str(&node(state_id));
+            }
+        }
+        dot.push_str("start [shape = point];\n");
+        dot.push_str(&format!("start -> {};\n", self.start_state()));
+        for (id, state) in self.states_with_id_iter() {
+            if !shown(*id) {
+                continue;
+            }
+            // 到达同一个状态的边合并成一条，标签按字母表顺序用逗号连接
+            let mut edges: Vec<(StateId, Vec<String>)> = Vec::new();
+            for input in self.alphabet.iter() {
+                let to = state.to(*input);
+                if !shown(to) {
+                    continue;
+                }
+                match edges.iter_mut().find(|(target, _)| *target == to) {
+                    Some((_, labels)) => labels.push((*input as char).to_string()),
+                    None => edges.push((to, vec![(*input as char).to_string()])),
+                }
+            }
+            for (to, labels) in edges {
+                dot.push_str(&format!(
+                    "{} -> {} [label = \"{}\"];\n",
+                    id,
+                    to,
+                    labels.join(",")
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// 把状态id解码成它对应的NFA状态子集，例如`0b101`是`{0,2}`，空集是`∅`。
+    fn subset_label(state: StateId) -> String {
+        if state == 0 {
+            return "∅".to_string();
+        }
+        let members = (0..StateId::BITS)
+            .filter(|bit| state & (1 << bit) != 0)
+            .join(",");
+        format!("{{{}}}", members)
+    }
+
     fn search_unreachable_states(&mut self) -> HashSet<StateId> {
         let mut reachable_states = HashSet::new();
         let mut stack = Vec::new();
@@ -168,11 +618,10 @@ impl DFA01 {
         while let Some(state_id) = stack.pop() {
             reachable_states.insert(state_id);
             let state = self.get_state_by_id(state_id);
-            if !reachable_states.contains(&state.zero_to) {
-                stack.push(state.zero_to);
-            }
-            if !reachable_states.contains(&state.one_to) {
-                stack.push(state.one_to);
+            for to in state.to.values() {
+                if !reachable_states.contains(to) {
+                    stack.push(*to);
+                }
             }
         }
 
@@ -182,21 +631,24 @@ impl DFA01 {
 }
 
 impl DFA01 {
-    /// 从NFA构造DFA。
+    /// 从NFA构造DFA，状态id是NFA状态子集的位掩码，所以可以用`DotOptions::subset_labels`画出每个状态对应的子集。
+    ///
+    /// 位掩码是u128，NFA超过128个状态时panic。一般的确定化请用`NFA::determinize`，
+    /// `re_to_dfa`和`DenseDFA::build_from_nfa`也都用它，没有这个限制。
     pub fn build_dfa_from_nfa(nfa: &NFA) -> Self {
-        let nfa_state_set_len = nfa.get_states_iter().len();
+        let nfa_state_set_len = nfa.number_of_states();
         if nfa_state_set_len > 128 {
             panic!("too many states");
         }
 
-        let alphabet = nfa.alphabet();
-        if (alphabet.len() == 2 && alphabet.contains(&b'0') && alphabet.contains(&b'1'))
-            || (alphabet.len() == 1 && (alphabet.contains(&b'0') || alphabet.contains(&b'1')))
-        {
-            // 检查这个NFA的字母表是否只有0和1。
+        // NFA的字母表是{0,1}的子集时，DFA的字母表仍然是{0,1}，和以前的行为一致；
+        // 否则就用NFA自己的字母表，从小到大排列。
+        let alphabet = nfa.sorted_alphabet();
+        let alphabet = if alphabet.iter().all(|input| b"01".contains(input)) {
+            vec![b'0', b'1']
         } else {
-            panic!("alphabet is not ['0','1']");
-        }
+            alphabet
+        };
 
         trait ToDfaStateID {
             /// 将NFA状态ID转换为DFA的状态ID。
@@ -218,9 +670,10 @@ impl DFA01 {
         impl_to_dfa_state_id!(u32, usize, u8);
 
         let mut dfa = Self::init_empty();
+        dfa.alphabet = alphabet;
         let mut stack = Vec::new();
 
-        dfa.set_start_state(nfa.start_state.unwrap().to_dfa_state_id());
+        dfa.set_start_state(nfa.start_state().unwrap().to_dfa_state_id());
 
         // 准备好一个HashSet，用来判断一个DFA状态是否直接来自NFA，也就是只包含单个NFA状态的DFA状态。
         // 例如，如果原NFA的状态集合是{0,1,2}，那么DFA中的状态[0]、[1]、[2]都是直接来自NFA的。
@@ -267,28 +720,18 @@ impl DFA01 {
             //  00010]
             // 拆开的每一个数都代表一个DFA状态的id。
 
-            let (zero_to, one_to) = subset
-                .iter()
-                .map(|id| {
-                    let state = dfa.get_state_by_id(*id);
-                    (state.zero_to, state.one_to)
-                })
-                .reduce(|(zero_to1, one_to1), (zero_to2, one_to2)| {
-                    (zero_to1 | zero_to2, one_to1 | one_to2)
-                })
-                .unwrap_or((0, 0));
-            // 上面的|是按位或。
-            // 因为DFA的状态id是一个NFA状态的集合的编码，将两个DFA的状态id按位或，就相当于求并集。
-
-            let state = dfa.get_state_by_id(state_id);
-            state.one_to = one_to;
-            state.zero_to = zero_to;
+            for input in dfa.alphabet.clone() {
+                let to = subset
+                    .iter()
+                    .map(|id| dfa.get_state_by_id(*id).to(input))
+                    .fold(0, |to1, to2| to1 | to2);
+                // 上面的|是按位或。
+                // 因为DFA的状态id是一个NFA状态的集合的编码，将两个DFA的状态id按位或，就相当于求并集。
 
-            if !dfa.states.keys().contains(&one_to) {
-                stack.push(one_to);
-            }
-            if !dfa.states.keys().contains(&zero_to) {
-                stack.push(zero_to);
+                dfa.get_state_by_id(state_id).add_transition(input, to);
+                if !dfa.states.contains_key(&to) {
+                    stack.push(to);
+                }
             }
         }
 
@@ -296,9 +739,12 @@ impl DFA01 {
         for state_id in dfa.search_unreachable_states() {
             dfa.states.remove(&state_id);
         }
+        // 空子集（0号状态）就是陷阱状态。即使没有转移到达它，也要保留它，
+        // 这样转换成稠密DFA之后，0号状态一定是陷阱状态。
+        dfa.get_state_by_id(0);
         // 标记接受状态
         for id in dfa.states.keys() {
-            for accept in nfa.accept_states.iter() {
+            for accept in nfa.accept_states().iter() {
                 if *id & accept.to_dfa_state_id() != 0 {
                     dfa.accept_states.insert(*id);
                 }
@@ -316,7 +762,7 @@ impl SparseDFA for DFA01 {
     fn init_empty() -> Self {
         Self {
             states: HashMap::new(),
-            alphabet: (b'0', b'1'),
+            alphabet: vec![b'0', b'1'],
             start_state: None,
             accept_states: HashSet::new(),
         }
@@ -351,8 +797,8 @@ impl SparseDFA for DFA01 {
 }
 
 impl CompletedDfa for DFA01 {
-    /// 由于这个DFA的字母表只有0和1，所以直接用一个有两个元素的元组来表示字母表。
-    type Alphabet = (u8, u8);
+    /// 和`DenseDFA`一样用Vec表示字母表，这样两种DFA可以用同一种trait object来表示。
+    type Alphabet = Vec<u8>;
     fn alphabet(&self) -> &Self::Alphabet {
         &self.alphabet
     }
@@ -369,21 +815,28 @@ impl CompletedDfa for DFA01 {
         self.states.len() as StateId
     }
 
+    fn state_ids(&self) -> Vec<StateId> {
+        self.states.keys().cloned().sorted().collect()
+    }
+
     fn to_dot(&self) -> String {
         let mut dot = String::new();
         dot.push_str("digraph DFA {\n");
         dot.push_str("rankdir=LR;\n");
         dot.push_str("node [shape = doublecircle];\n");
-        for state_id in &self.accept_states {
+        for state_id in self.accept_states_sorted() {
             dot.push_str(&format!("{};\n", state_id));
         }
         dot.push_str("node [shape = circle];\n");
         for (id, state) in self.states_with_id_iter() {
-            if state.zero_to != 0 {
-                dot.push_str(&format!("{} -> {} [label = \"0\"];\n", id, state.zero_to));
-            }
-            if state.one_to != 0 {
-                dot.push_str(&format!("{} -> {} [label = \"1\"];\n", id, state.one_to));
+            for input in self.alphabet.iter() {
+                let to = state.to(*input);
+                if to != 0 {
+                    dot.push_str(&format!(
+                        "{} -> {} [label = \"{}\"];\n",
+                        id, to, *input as char
+                    ));
+                }
             }
         }
         dot.push_str("}\n");
@@ -392,14 +845,14 @@ impl CompletedDfa for DFA01 {
 
     fn delta(&self, from: StateId, input: u8) -> StateId {
         let state = self.states.get(&from).expect("No such a state");
-        match input {
-            b'0' => state.zero_to,
-            b'1' => state.one_to,
-            _ => panic!("invalid input"),
+        if !self.alphabet.contains(&input) {
+            panic!("invalid input");
         }
+        state.to(input)
     }
 }
 
+#[allow(dead_code)]
 trait State {
     type StateId;
     type Transitions;
@@ -407,36 +860,37 @@ trait State {
 }
 
 /// 用于表示`DFA01`这个结构体的状态。
+///
+/// 虽然叫State01，但是字母表不限于{0,1}：每个字符上的转移存在一个有序的表里，没有的转移就是到0号陷阱状态。
 pub struct State01 {
-    zero_to: StateId,
-    one_to: StateId,
+    to: BTreeMap<u8, StateId>,
 }
 
 impl State01 {
     fn new() -> Self {
         Self {
-            zero_to: 0,
-            one_to: 0,
+            to: BTreeMap::new(),
         }
     }
+
+    /// 读入`input`之后到达的状态，没有这个转移时是0号陷阱状态。
+    fn to(&self, input: u8) -> StateId {
+        self.to.get(&input).cloned().unwrap_or(0)
+    }
 }
 
 impl State01 {
     fn add_transition(&mut self, input: u8, to: StateId) {
-        match input {
-            b'0' => self.zero_to = to,
-            b'1' => self.one_to = to,
-            _ => panic!("invalid input"),
-        }
+        self.to.insert(input, to);
     }
 }
 
 impl State for State01 {
     type StateId = StateId;
-    type Transitions = (StateId, StateId);
+    type Transitions = Vec<(u8, StateId)>;
 
     fn transitions(&self) -> Self::Transitions {
-        (self.zero_to, self.one_to)
+        self.to.iter().map(|(input, to)| (*input, *to)).collect()
     }
 }
 
@@ -447,12 +901,17 @@ impl State for State01 {
 /// 一份 `in_transitions` 以到达状态为索引，称为“入表”。
 ///
 /// 本来感觉多储存一份入表可以方便之后使用DFA构造正则表达式，但实际上好像没什么帮助。暂时没有删除。
+/// 现在反向查询前驱的`symbols_reaching`用到了入表。
+#[derive(Clone)]
 pub struct DenseDFA {
     alphabet: Vec<u8>,
     out_transitions: Transisions<StateId>,
     in_transitions: Transisions<Vec<StateId>>,
     start_state: Option<StateId>,
     accept_states: HashSet<StateId>,
+    /// 部分DFA中每个转移是否有定义，下标和`out_transitions.trans`相同。
+    /// None表示完全DFA：这个crate的约定是没有定义的转移都指向0号陷阱状态。
+    defined: Option<Vec<bool>>,
 }
 
 impl DenseDFA {
@@ -469,15 +928,106 @@ impl DenseDFA {
         self.in_transitions.trans[to_index].push(from);
     }
 
+    #[allow(dead_code)]
     fn set_start_state(&mut self, id: StateId) {
         self.start_state = Some(id);
     }
 
+    #[allow(dead_code)]
     fn set_accept_state(&mut self, id: StateId) {
         self.accept_states.insert(id);
     }
 }
 
+/// 修改DFA的方法，都会检查参数是否合法
+impl DenseDFA {
+    fn check_state(&self, id: StateId) -> Result<(), EditError> {
+        if id < self.number_of_states() {
+            Ok(())
+        } else {
+            Err(EditError::StateOutOfRange {
+                id,
+                number_of_states: self.number_of_states(),
+            })
+        }
+    }
+
+    /// 把一个状态标记为接受状态。已经是接受状态时什么也不做。
+    ///
+    /// 完全DFA的0号状态是陷阱状态，标记它会返回`TrapState`；部分DFA的0号状态是普通状态，可以标记。
+    pub fn mark_accept(&mut self, id: StateId) -> Result<(), EditError> {
+        self.check_state(id)?;
+        if id == 0 && !self.is_partial() {
+            return Err(EditError::TrapState);
+        }
+        self.accept_states.insert(id);
+        Ok(())
+    }
+
+    /// 把一个状态改成非接受状态。本来就不是接受状态时什么也不做。
+    pub fn unmark_accept(&mut self, id: StateId) -> Result<(), EditError> {
+        self.check_state(id)?;
+        self.accept_states.remove(&id);
+        Ok(())
+    }
+
+    /// 修改开始状态。
+    pub fn set_start(&mut self, id: StateId) -> Result<(), EditError> {
+        self.check_state(id)?;
+        self.start_state = Some(id);
+        Ok(())
+    }
+
+    /// 添加一个转移 δ(from, input) = to。
+    ///
+    /// 完全DFA中指向0号陷阱状态的转移、部分DFA中没有定义的转移都可以设置；
+    /// 已经指向别的状态的转移不会被覆盖，返回`CellOccupied`。设置成和原来相同的状态不算错误。
+    /// 完全DFA的陷阱状态不能有指向别的状态的转移。
+    pub fn add_transition_checked(
+        &mut self,
+        from: StateId,
+        input: u8,
+        to: StateId,
+    ) -> Result<(), EditError> {
+        self.check_state(from)?;
+        self.check_state(to)?;
+        let index = self
+            .alphabet
+            .iter()
+            .position(|symbol| *symbol == input)
+            .ok_or(EditError::UnknownSymbol(input))?;
+        let cell = ((from as usize) << self.out_transitions.stride_as_power_of_2) + index;
+        let existing = self.out_transitions.trans[cell];
+        let occupied = match &self.defined {
+            Some(defined) => defined[cell],
+            None => existing != 0,
+        };
+        if occupied {
+            return if existing == to {
+                Ok(())
+            } else {
+                Err(EditError::CellOccupied {
+                    from,
+                    input,
+                    existing,
+                })
+            };
+        }
+        if from == 0 && to != 0 && !self.is_partial() {
+            return Err(EditError::TrapState);
+        }
+
+        // 原来的转移指向0号状态，先把它从0号状态的入表中删掉
+        let old_in = (existing as usize) * self.in_transitions.stride() + index;
+        self.in_transitions.trans[old_in].retain(|state| *state != from);
+        self.add_transition(from, input, to);
+        if let Some(defined) = &mut self.defined {
+            defined[cell] = true;
+        }
+        Ok(())
+    }
+}
+
 impl CompletedDfa for DenseDFA {
     /// 使用一个Vec来表示字母表。不用HashSet的原因是需要字母表是有序的。
     type Alphabet = Vec<u8>;
@@ -491,23 +1041,11 @@ impl CompletedDfa for DenseDFA {
         dot.push_str("digraph DFA {\n");
         dot.push_str("rankdir=LR;\n");
         dot.push_str("node [shape = doublecircle];\n");
-        for state_id in &self.accept_states {
+        for state_id in self.accept_states_sorted() {
             dot.push_str(&format!("{};\n", state_id));
         }
         dot.push_str("node [shape = circle];\n");
-        let stride2 = self.out_transitions.stride_as_power_of_2;
-        for (index, to) in self.out_transitions.trans.iter().enumerate() {
-            let from = index >> stride2;
-            // 如果想显示陷阱状态，就把下面这个if注释掉。
-            if *to == 0 || from == 0 {
-                continue;
-            }
-            let input = self.alphabet[index & ((1 << stride2) - 1)];
-            dot.push_str(&format!(
-                "{} -> {} [label = \"{}\"];\n",
-                from, to, input as char
-            ));
-        }
+        self.push_dot_edges(&mut dot, false);
         dot.push_str("}\n");
         dot
     }
@@ -535,6 +1073,33 @@ impl CompletedDfa for DenseDFA {
     fn accept_states(&self) -> &HashSet<StateId> {
         &self.accept_states
     }
+
+    /// 部分DFA的0号状态是普通状态，没有陷阱状态。
+    fn trap_state(&self) -> Option<StateId> {
+        if self.is_partial() {
+            None
+        } else {
+            Some(0)
+        }
+    }
+
+    // 下面两个方法转发给更快的固有方法。
+    fn accepts(&self, input: &str) -> bool {
+        DenseDFA::accepts(self, input)
+    }
+
+    fn shortest_accepted(&self) -> Option<Vec<u8>> {
+        DenseDFA::shortest_accepted(self)
+    }
+
+    // 默认实现把0号状态当作陷阱状态，部分DFA先补全。
+    fn to_rg(&self) -> String {
+        completed_to_rg(&self.complete_partial())
+    }
+
+    fn to_fmt_output_with(&self, options: &FmtOptions) -> String {
+        completed_to_fmt_output(&self.complete_partial(), options)
+    }
 }
 
 #[derive(Clone)]
@@ -592,7 +1157,7 @@ impl DfaConfig {
     fn new_from_01(dfa: &DFA01) -> Self {
         DfaConfig {
             number_of_states: dfa.states.len(),
-            alphabet: vec![dfa.alphabet.0, dfa.alphabet.1],
+            alphabet: dfa.alphabet.clone(),
             start_state_id: dfa.start_state.unwrap(),
             accept_states: dfa.accept_states.clone(),
             id_map: dfa
@@ -605,10 +1170,29 @@ impl DfaConfig {
 
     /// 将原来的不可区分状态合并为一个状态，返回一个新的DFA配置。
     /// 具体方法是，有几组不可区分状态，就新添加几个状态。然后把每一组的状态都映射到新的状态上。
+    ///
+    /// 接受状态和非接受状态总是可区分的，所以一组不可区分状态要么全是接受状态，要么全不是。
+    /// 如果不是这样，说明计算不可区分状态时出了错，合并之后开始状态和接受状态的映射也不可信，直接panic。
     fn new_for_minimize(dfa: &DenseDFA, indistin: &minimize::IndistinGroups) -> Self {
+        for group in indistin.iter() {
+            let accepting = group
+                .iter()
+                .filter(|state| dfa.accept_states.contains(state))
+                .count();
+            assert!(
+                accepting == 0 || accepting == group.len(),
+                "minimize: group {:?} mixes accepting and non-accepting states",
+                group.iter().sorted().collect::<Vec<_>>()
+            );
+        }
         let id_map = indistin.remap(dfa.number_of_states());
-        dbg!(&id_map);
-        dbg!(&dfa.accept_states);
+        for group in indistin.iter() {
+            assert!(
+                group.iter().map(|state| id_map[state]).all_equal(),
+                "minimize: group {:?} is not merged into a single state",
+                group.iter().sorted().collect::<Vec<_>>()
+            );
+        }
         DfaConfig {
             number_of_states: dfa.number_of_states() as usize - indistin.num_of_indistin_states()
                 + indistin.num_of_groups(),
@@ -637,24 +1221,19 @@ impl DenseDFA {
             accept_states: config
                 .accept_states
                 .iter()
-                .map(|id| config.id_map[&id])
+                .map(|id| config.id_map[id])
                 .collect(),
+            defined: None,
         }
     }
 
     /// delta 的意思是状态转移函数。
+    #[allow(dead_code)]
     fn delta_by_tran_index(&self, index: usize) -> StateId {
         // 如果index超出了范围，会panic。
         self.out_transitions.trans[index]
     }
 
-    fn is_no_way_out(&self, state: StateId) -> bool {
-        self.out_transitions.trans[(state << self.out_transitions.stride_as_power_of_2) as usize
-            ..((state + 1) << self.out_transitions.stride_as_power_of_2) as usize]
-            .iter()
-            .all(|&to| to == 0)
-    }
-
     fn alphabet_index_of(&self, input: u8) -> usize {
         self.alphabet
             .to_iter()
@@ -662,6 +1241,7 @@ impl DenseDFA {
             .expect("invalid input")
     }
 
+    #[allow(dead_code)]
     fn clear_accept_states(&mut self) {
         self.accept_states.clear();
     }
@@ -672,58 +1252,448 @@ impl DenseDFA {
         let mut dense_dfa = Self::init_with_config(&config);
 
         for (new_id, state) in sparse_dfa.states_iter().enumerate() {
-            dense_dfa.add_transition(new_id as StateId, b'0', config.id_map[&state.zero_to]);
-            dense_dfa.add_transition(new_id as StateId, b'1', config.id_map[&state.one_to]);
+            for input in sparse_dfa.alphabet.iter() {
+                dense_dfa.add_transition(
+                    new_id as StateId,
+                    *input,
+                    config.id_map[&state.to(*input)],
+                );
+            }
         }
         dense_dfa
     }
 
-    pub fn test_print_in_transitions(&self) {
-        let stride2 = self.in_transitions.stride_as_power_of_2;
-        for (index, froms) in self.in_transitions.trans.iter().enumerate() {
-            let state_id = index >> stride2;
-            let input = self.alphabet[index & ((1 << stride2) - 1)];
-            for from in froms {
-                println!("{} <- {} ({})", state_id, from, input as char);
-            }
-        }
+    /// 从NFA（可以带空转移）构造极小化的稠密DFA。
+    ///
+    /// 先用`NFA::determinize`的子集构造法确定化，再极小化。NFA的状态数没有限制，
+    /// 字母表可以是任意字符；NFA的字母表是{0,1}的子集时，DFA的字母表仍然是{0,1}。
+    pub fn build_from_nfa(nfa: &NFA) -> Self {
+        Self::try_build_from_nfa(nfa).unwrap()
     }
 
-    /// 将这个DFA转换为正则文法。
-    pub fn to_rg(&self) -> String {
-        let mut rg = String::new();
-        rg.push_str(&format!("S -> q{}\n", self.start_state()));
-        for from in 1..self.number_of_states() {
-            // 这个变量代表产生式的右部，也就是候选式。
-            let mut candidate = String::new();
-            for input in self.alphabet.to_iter() {
-                let to = self.delta(from, input);
-                if self.accept_states.contains(&to) {
-                    candidate.push_str(&format!(" {} |", input as char));
-                }
-                if to == 0 || self.is_no_way_out(to) {
-                    continue;
-                }
-                candidate.push_str(&format!(" {}q{} |", input as char, to));
-            }
-            if let Some(_) = candidate.pop() {
-                rg.push_str(&format!("q{} ->{}\n", from, candidate));
-            }
-        }
-        rg
-    }
+    /// 和`build_from_nfa`相同，但是出错时返回错误信息而不是panic。
+    ///
+    /// 确定化得到的DFA超过`DEFAULT_DETERMINIZE_LIMIT`个状态时返回错误。
+    pub fn try_build_from_nfa(nfa: &NFA) -> Result<Self, String> {
+        let alphabet = nfa.sorted_alphabet();
+        let alphabet = if alphabet.iter().all(|input| b"01".contains(input)) {
+            vec![b'0', b'1']
+        } else {
+            alphabet
+        };
+        let dense_dfa = nfa.determinize_with_limit(alphabet, DEFAULT_DETERMINIZE_LIMIT)?;
 
-    /// 将状态转移表转化为DOT语言表示的状态转移图。
-    pub fn call_to_dot(&self) -> String {
-        self.to_dot()
+        Ok(dense_dfa.minimize())
     }
 
-    /// 将这个DFA最小化。
+    /// 从稠密DFA构造稀疏DFA，是`build_from_sparse01_dfa`的逆操作。
     ///
-    /// 实现有点复杂。首先我们计算不可区分状态组`indistin_groups`，里面有几组不可区分状态。
-    /// 先从原状态转移表中删除原有的不可区分状态，然后将每一组不可区分状态合并为一个状态，添加到表的末尾。
-    ///
-    /// 之后计算映射表`id_map`，将状态在旧表中的id映射为新表中的id。并且，同一组不可区分的状态会映射到同一个新id。
+    /// `DFA01`只支持字母表{0,1}，字母表不是{0,1}时返回错误。稀疏DFA的状态id和稠密DFA中的相同，
+    /// 部分DFA先用`complete_partial`补全，状态id会加一。
+    pub fn into_sparse01(&self) -> Result<DFA01, AlphabetError> {
+        if self.is_partial() {
+            return self.complete_partial().into_sparse01();
+        }
+        let mut alphabet = self.alphabet.clone();
+        alphabet.sort_unstable();
+        if alphabet != [b'0', b'1'] {
+            return Err(AlphabetError::Mismatch {
+                left: alphabet,
+                right: vec![b'0', b'1'],
+            });
+        }
+
+        let mut sparse_dfa = DFA01::init_empty();
+        for id in 0..self.number_of_states() {
+            let state = sparse_dfa.add_empty_state(id);
+            state.add_transition(b'0', self.delta(id, b'0'));
+            state.add_transition(b'1', self.delta(id, b'1'));
+        }
+        sparse_dfa.set_start_state(self.start_state());
+        for id in self.accept_states.iter() {
+            sparse_dfa.set_accept_state(*id);
+        }
+        Ok(sparse_dfa)
+    }
+
+    /// 直接从状态转移表构造稠密DFA，主要用于手工构造一些DFA做测试或者演示。
+    ///
+    /// `table[q][i]` 就是 δ(q, alphabet\[i\])。按照本crate的约定，0号状态应该是陷阱状态。
+    pub fn from_table(
+        alphabet: Vec<u8>,
+        table: &[Vec<StateId>],
+        start_state: StateId,
+        accept_states: &[StateId],
+    ) -> Self {
+        let number_of_states = table.len();
+        let config = DfaConfig {
+            number_of_states,
+            alphabet,
+            start_state_id: start_state,
+            accept_states: accept_states.iter().cloned().collect(),
+            id_map: (0..number_of_states as StateId).map(|id| (id, id)).collect(),
+        };
+        let mut dense_dfa = Self::init_with_config(&config);
+
+        for (from, row) in table.iter().enumerate() {
+            assert_eq!(
+                row.len(),
+                config.alphabet.len(),
+                "from_table: row of state {} does not match the alphabet",
+                from
+            );
+            for (input, to) in config.alphabet.iter().zip(row.iter()) {
+                assert!(
+                    (*to as usize) < number_of_states,
+                    "from_table: no such a state: {}",
+                    to
+                );
+                dense_dfa.add_transition(from as StateId, *input, *to);
+            }
+        }
+        dense_dfa
+    }
+
+    /// 和`from_table`一样，只是接受状态用闭区间列表给出，例如`[(3, 7)]`表示3到7号状态都是接受状态。
+    ///
+    /// 和`accept_states_as_ranges`配合，可以紧凑地保存接受状态很多的DFA。
+    pub fn from_table_with_accept_ranges(
+        alphabet: Vec<u8>,
+        table: &[Vec<StateId>],
+        start_state: StateId,
+        accept_ranges: &[(StateId, StateId)],
+    ) -> Self {
+        let accept_states: Vec<StateId> = accept_ranges
+            .iter()
+            .flat_map(|(first, last)| *first..=*last)
+            .collect();
+        Self::from_table(alphabet, table, start_state, &accept_states)
+    }
+
+    /// 从转移表构造部分DFA：`None`表示这个转移没有定义，读到这个字符时直接拒绝。
+    ///
+    /// 和`from_table`不同，0号状态是一个普通的状态，可以是接受状态，也可以有自己的转移。
+    /// 识别字符串的方法（`accepts`等）会遵守“没有定义就拒绝”，也可以用`accepts_with_partiality`选择别的语义。
+    /// 极小化、规范化、积等运算会先用`complete_partial`把它转换成完全DFA，语言不变。
+    pub fn from_partial_table(
+        alphabet: Vec<u8>,
+        table: &[Vec<Option<StateId>>],
+        start_state: StateId,
+        accept_states: &[StateId],
+    ) -> Self {
+        let complete: Vec<Vec<StateId>> = table
+            .iter()
+            .map(|row| row.iter().map(|to| to.unwrap_or(0)).collect())
+            .collect();
+        let mut dfa = Self::from_table(alphabet, &complete, start_state, accept_states);
+        let mut defined = vec![false; dfa.out_transitions.trans.len()];
+        for (from, row) in table.iter().enumerate() {
+            for (index, to) in row.iter().enumerate() {
+                defined[(from << dfa.out_transitions.stride_as_power_of_2) + index] = to.is_some();
+            }
+        }
+        dfa.defined = Some(defined);
+        dfa
+    }
+
+    /// 是否是用`from_partial_table`构造的部分DFA。
+    pub fn is_partial(&self) -> bool {
+        self.defined.is_some()
+    }
+
+    /// 把部分DFA转换成接受相同语言的完全DFA，完全DFA原样返回一份拷贝。
+    ///
+    /// 部分DFA的0号状态是普通的状态，可能是接受状态，所以不能直接套用陷阱约定。
+    /// 这里把所有状态的id都加一，新的0号状态是陷阱状态，没有定义的转移都指向它。
+    pub fn complete_partial(&self) -> DenseDFA {
+        if !self.is_partial() {
+            return self.clone();
+        }
+        let mut table: Vec<Vec<StateId>> = vec![vec![0; self.alphabet.len()]];
+        for state in 0..self.number_of_states() {
+            table.push(
+                self.alphabet
+                    .iter()
+                    .map(|input| {
+                        if self.is_defined(state, *input) {
+                            self.delta(state, *input) + 1
+                        } else {
+                            0
+                        }
+                    })
+                    .collect(),
+            );
+        }
+        let accept_states: Vec<StateId> = self
+            .accept_states_sorted()
+            .into_iter()
+            .map(|state| state + 1)
+            .collect();
+        Self::from_table(
+            self.alphabet.clone(),
+            &table,
+            self.start_state() + 1,
+            &accept_states,
+        )
+    }
+
+    /// 状态`from`在字符`input`上的转移是否有定义。完全DFA的转移总是有定义的。
+    pub fn is_defined(&self, from: StateId, input: u8) -> bool {
+        match &self.defined {
+            Some(defined) => {
+                defined[((from as usize) << self.out_transitions.stride_as_power_of_2)
+                    + self.alphabet_index_of(input)]
+            }
+            None => true,
+        }
+    }
+
+    /// 从`from`出发的所有有定义的转移 (输入字符, 到达状态)，按字母表的顺序排列。
+    ///
+    /// 把DFA当成图来搜索的方法用它代替`delta`，部分DFA中没有定义的转移就不是图上的边。
+    pub(crate) fn defined_transitions(
+        &self,
+        from: StateId,
+    ) -> impl Iterator<Item = (u8, StateId)> + '_ {
+        let base = (from as usize) << self.out_transitions.stride_as_power_of_2;
+        self.alphabet
+            .iter()
+            .enumerate()
+            .filter(move |(index, _)| match &self.defined {
+                Some(defined) => defined[base + index],
+                None => true,
+            })
+            .map(move |(index, input)| (*input, self.out_transitions.trans[base + index]))
+    }
+
+    /// 把接受状态的id中连续的一段合并成一个闭区间，区间从小到大排列。
+    ///
+    /// 例如接受状态是{1, 2, 3, 5}时返回`[(1, 3), (5, 5)]`。
+    pub fn accept_states_as_ranges(&self) -> Vec<(StateId, StateId)> {
+        let mut ranges: Vec<(StateId, StateId)> = Vec::new();
+        for state in self.accept_states_sorted() {
+            match ranges.last_mut() {
+                Some((_, last)) if *last + 1 == state => *last = state,
+                _ => ranges.push((state, state)),
+            }
+        }
+        ranges
+    }
+
+    /// 用真值表定义一个只含长度为`bit_length`的01串的有限语言：`accepted`中的串被接受，其余都被拒绝。
+    ///
+    /// 先构造一棵深度为`bit_length`的完全二叉树：状态按堆的方式编号，i号状态读0到2i号、读1到2i + 1号，
+    /// 叶子上被接受的串对应的叶子是接受状态，叶子的所有转移都到陷阱状态。然后极小化，
+    /// 可以看到极小化把这棵树压缩成了多小。树有2^(bit_length+1)个状态，所以`bit_length`不能太大。
+    ///
+    /// `accepted`中有长度不是`bit_length`的串，或者含有0和1以外的字符时panic。
+    pub fn from_truth_table(bit_length: usize, accepted: &[&[u8]]) -> Self {
+        let leaves = 1usize << bit_length;
+        let mut table = vec![vec![0, 0]];
+        for state in 1..leaves {
+            table.push(vec![2 * state as StateId, 2 * state as StateId + 1]);
+        }
+        table.extend((0..leaves).map(|_| vec![0, 0]));
+
+        let accept_states: Vec<StateId> = accepted
+            .iter()
+            .map(|word| {
+                assert_eq!(
+                    word.len(),
+                    bit_length,
+                    "from_truth_table: {:?} does not have {} bits",
+                    String::from_utf8_lossy(word),
+                    bit_length
+                );
+                word.iter().fold(1, |state, bit| match bit {
+                    b'0' => 2 * state,
+                    b'1' => 2 * state + 1,
+                    _ => panic!("from_truth_table: invalid bit: {}", *bit as char),
+                })
+            })
+            .collect();
+        let tree = Self::from_table(b"01".to_vec(), &table, 1, &accept_states);
+        tree.minimize()
+    }
+
+    /// 构造识别“`base`进制下能被`k`整除的数”的DFA，常见的课堂例题。
+    ///
+    /// 字母表是`'0'`到`base - 1`对应的数字字符，高位在前。状态r + 1表示已经读入的数模k余r，
+    /// 开始状态和唯一的接受状态都是余数为0的状态，0号陷阱状态不可达。
+    /// 约定空串表示0，所以空串被接受；前导零不改变数值，所以`0110`和`110`一样被接受。
+    ///
+    /// `k`必须大于0，`base`必须在2到10之间，否则panic。
+    pub fn divisible_by(k: u32, base: u8) -> Self {
+        assert!(k > 0, "divisible_by: k must be positive");
+        assert!(
+            (2..=10).contains(&base),
+            "divisible_by: base must be between 2 and 10"
+        );
+        let alphabet: Vec<u8> = (0..base).map(|digit| b'0' + digit).collect();
+        let k = k as StateId;
+        let mut table = vec![vec![0; base as usize]];
+        for remainder in 0..k {
+            table.push(
+                (0..base as StateId)
+                    .map(|digit| (remainder * base as StateId + digit) % k + 1)
+                    .collect(),
+            );
+        }
+        Self::from_table(alphabet, &table, 1, &[1])
+    }
+
+    /// 随机生成一个DFA，用于压力测试和性质测试（比如“极小化是幂等的”）。需要打开`random` feature。
+    ///
+    /// 除了0号陷阱状态，还有`num_states`个状态，1号是开始状态。
+    /// 每个转移都均匀随机地指向某个状态（包括陷阱状态），每个非陷阱状态以`accept_prob`的概率成为接受状态。
+    /// 生成的DFA不一定是极小的，也可能有不可达状态和死状态。
+    ///
+    /// `num_states`为0或者`alphabet`为空时panic。
+    #[cfg(feature = "random")]
+    pub fn random(
+        num_states: usize,
+        alphabet: &[u8],
+        accept_prob: f64,
+        rng: &mut oorandom::Rand64,
+    ) -> Self {
+        assert!(num_states > 0, "random: num_states must be positive");
+        assert!(!alphabet.is_empty(), "random: alphabet is empty");
+        let mut table = vec![vec![0; alphabet.len()]];
+        for _ in 0..num_states {
+            table.push(
+                alphabet
+                    .iter()
+                    .map(|_| rng.rand_range(0..num_states as u64 + 1) as StateId)
+                    .collect(),
+            );
+        }
+        let accept_states: Vec<StateId> = (1..=num_states as StateId)
+            .filter(|_| rng.rand_float() < accept_prob)
+            .collect();
+        Self::from_table(alphabet.to_vec(), &table, 1, &accept_states)
+    }
+
+    /// 只保留字母表中的一部分字符，返回一个新的DFA。
+    ///
+    /// 新DFA的字母表只包含`keep`中的字符（按原字母表的顺序排列），
+    /// 含有被删去字符的字符串都会被拒绝，相当于把这些字符的转移都指向了陷阱状态，
+    /// 也就是求原语言和`(keep)*`的交集。
+    ///
+    /// 如果`keep`中有原字母表里没有的字符，返回错误。
+    pub fn restrict_to_alphabet(&self, keep: &[u8]) -> Result<Self, AlphabetError> {
+        if let Some(input) = keep.iter().find(|input| !self.alphabet.contains(input)) {
+            return Err(AlphabetError::UnknownSymbol(*input));
+        }
+        let alphabet: Vec<u8> = self
+            .alphabet
+            .iter()
+            .filter(|input| keep.contains(input))
+            .cloned()
+            .collect();
+
+        let table: Vec<Vec<Option<StateId>>> = (0..self.number_of_states())
+            .map(|from| {
+                alphabet
+                    .iter()
+                    .map(|input| {
+                        self.is_defined(from, *input)
+                            .then(|| self.delta(from, *input))
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(self.rebuild_over(alphabet, &table))
+    }
+
+    /// 往字母表里添加新的字符，返回一个新的DFA。
+    ///
+    /// 新字符加在原字母表的后面，所有状态读到新字符都转移到陷阱状态，所以语言不变，
+    /// 只是含有新字符的字符串现在也能被“正常地”拒绝了。已经在字母表里的字符会被忽略。
+    /// 部分DFA的结果仍然是部分DFA，新字符上的转移都没有定义。
+    pub fn with_extended_alphabet(&self, extra: &[u8]) -> Self {
+        let mut alphabet = self.alphabet.clone();
+        for input in extra {
+            if !alphabet.contains(input) {
+                alphabet.push(*input);
+            }
+        }
+
+        let table: Vec<Vec<Option<StateId>>> = (0..self.number_of_states())
+            .map(|from| {
+                alphabet
+                    .iter()
+                    .map(|input| {
+                        (self.alphabet.contains(input) && self.is_defined(from, *input))
+                            .then(|| self.delta(from, *input))
+                    })
+                    .collect()
+            })
+            .collect();
+        self.rebuild_over(alphabet, &table)
+    }
+
+    /// 用新的字母表和转移表构造DFA，开始状态和接受状态不变。
+    /// 部分DFA用`from_partial_table`，完全DFA用`from_table`，`None`表示转移到陷阱状态。
+    fn rebuild_over(&self, alphabet: Vec<u8>, table: &[Vec<Option<StateId>>]) -> Self {
+        let accept_states: Vec<StateId> = self.accept_states.iter().cloned().collect();
+        if self.is_partial() {
+            return Self::from_partial_table(alphabet, table, self.start_state(), &accept_states);
+        }
+        let table: Vec<Vec<StateId>> = table
+            .iter()
+            .map(|row| row.iter().map(|to| to.unwrap_or(0)).collect())
+            .collect();
+        Self::from_table(alphabet, &table, self.start_state(), &accept_states)
+    }
+
+    /// 返回所有能一步转移到`to`的 (出发状态, 输入字符)，也就是所有满足 δ(from, input) = to 的 (from, input)。
+    ///
+    /// 结果按出发状态排序，出发状态相同时按字母表顺序排列。做反向的可达性分析时很有用。
+    /// 部分DFA中没有定义的转移在入表里记成了指向0号状态，这里会跳过它们。
+    pub fn symbols_reaching(&self, to: StateId) -> Vec<(StateId, u8)> {
+        let base = (to as usize) << self.in_transitions.stride_as_power_of_2;
+        let mut result: Vec<(StateId, u8)> = self
+            .alphabet
+            .iter()
+            .enumerate()
+            .flat_map(|(index, input)| {
+                self.in_transitions.trans[base + index]
+                    .iter()
+                    .map(move |from| (*from, *input))
+            })
+            .filter(|(from, input)| self.is_defined(*from, *input))
+            .collect();
+        result.sort_by_key(|(from, _)| *from);
+        result
+    }
+
+    pub fn test_print_in_transitions(&self) {
+        let stride2 = self.in_transitions.stride_as_power_of_2;
+        for (index, froms) in self.in_transitions.trans.iter().enumerate() {
+            let state_id = index >> stride2;
+            let input = self.alphabet[index & ((1 << stride2) - 1)];
+            for from in froms {
+                println!("{} <- {} ({})", state_id, from, input as char);
+            }
+        }
+    }
+
+    /// 将这个DFA转换为正则文法。不需要导入`CompletedDfa`也能调用。
+    pub fn to_rg(&self) -> String {
+        CompletedDfa::to_rg(self)
+    }
+
+    /// 将状态转移表转化为DOT语言表示的状态转移图。
+    pub fn call_to_dot(&self) -> String {
+        self.to_dot()
+    }
+
+    /// 将这个DFA最小化。
+    ///
+    /// 实现有点复杂。首先我们计算不可区分状态组`indistin_groups`，里面有几组不可区分状态。
+    /// 先从原状态转移表中删除原有的不可区分状态，然后将每一组不可区分状态合并为一个状态，添加到表的末尾。
+    ///
+    /// 之后计算映射表`id_map`，将状态在旧表中的id映射为新表中的id。并且，同一组不可区分的状态会映射到同一个新id。
     /// 例如一组不可区分状态{q1，q2，q3}，那么这个映射表的记录就是：
     /// map(q1) = map(q2) = map(q3) = new_id。
     ///
@@ -737,7 +1707,155 @@ impl DenseDFA {
     ///     2. 如果 p 是一个不可区分状态，将转移函数δ(q, a) = map(p)添加到极小化DFA中。
     ///     3. 如果 q 和 p 都不是不可区分状态，那么直接把δ(q,a)=p添加到新DFA中。
     /// 4. 把原DFA的初始状态和接收状态过一遍映射表，得到极小化DFA的初始状态和接收状态。
-    pub fn minimize(&self) -> Option<Self> {
+    ///
+    /// 在debug构建中，还会检查返回的DFA确实已经是极小的（再极小化一次不会有任何变化），
+    /// 以便尽早发现`distinguish`等地方的错误。release构建中这个检查会被编译掉。
+    ///
+    /// 如果这个DFA已经是极小的，返回它的一份拷贝。想知道是不是已经极小可以用`is_minimal`。
+    /// 部分DFA先用`complete_partial`转换成完全DFA。
+    pub fn minimize(&self) -> Self {
+        if self.is_partial() {
+            return self.complete_partial().minimize();
+        }
+        match self.minimize_with_config() {
+            Some((minimized_dfa, _)) => minimized_dfa,
+            None => self.clone(),
+        }
+    }
+
+    /// 判断这个DFA是否已经是极小的：所有状态都可达，并且没有两个状态不可区分。
+    ///
+    /// 此时`minimize`原样返回这个DFA的拷贝。部分DFA判断的是`complete_partial`补全之后的DFA。
+    pub fn is_minimal(&self) -> bool {
+        if self.is_partial() {
+            return self.complete_partial().is_minimal();
+        }
+        self.trim_unreachable().is_none()
+            && minimize::compute_indistin_state_groups(self).num_of_groups() == 0
+    }
+
+    /// 将这个DFA极小化，同时报告原DFA的哪些状态被合并成了极小化DFA的哪个状态。
+    ///
+    /// 返回值的第二项以极小化DFA的状态id为索引，第i项是被合并进新状态i的所有原状态id，按从小到大排列。
+    /// 不可达的原状态被删去了，不出现在任何一项中。
+    /// 如果这个DFA已经是极小的，返回它的一份拷贝，每个状态只吸收了它自己。
+    /// 部分DFA先用`complete_partial`转换成完全DFA，报告中仍然是原来的状态id。
+    pub fn minimize_and_report(&self) -> (Self, Vec<Vec<StateId>>) {
+        if self.is_partial() {
+            let (minimized_dfa, groups) = self.complete_partial().minimize_and_report();
+            let groups = groups
+                .into_iter()
+                .map(|group| {
+                    group
+                        .into_iter()
+                        .filter(|state| *state != 0)
+                        .map(|state| state - 1)
+                        .collect()
+                })
+                .collect();
+            return (minimized_dfa, groups);
+        }
+        match self.minimize_with_config() {
+            Some((minimized_dfa, config)) => {
+                let mut groups = vec![Vec::new(); minimized_dfa.number_of_states() as usize];
+                for old_id in 0..self.number_of_states() {
+                    if let Some(new_id) = config.id_map.get(&old_id) {
+                        groups[*new_id as usize].push(old_id);
+                    }
+                }
+                (minimized_dfa, groups)
+            }
+            None => (
+                self.clone(),
+                (0..self.number_of_states()).map(|id| vec![id]).collect(),
+            ),
+        }
+    }
+
+    /// 极小化的具体实现，顺便返回极小化时使用的配置，里面有新旧状态id的映射表。
+    ///
+    /// 和教科书上的算法一样，先删去不可达状态，再合并不可区分的状态。
+    /// 不可达状态不会出现在映射表中。如果没有不可达状态，也没有可以合并的状态，返回None。
+    fn minimize_with_config(&self) -> Option<(Self, DfaConfig)> {
+        let minimized = self.minimize_with_config_unchecked();
+        if let Some((minimized_dfa, _)) = &minimized {
+            debug_assert!(
+                minimized_dfa.is_minimal(),
+                "minimize produced a DFA that is not minimal"
+            );
+        }
+        minimized
+    }
+
+    /// `minimize_with_config`中不带检查的部分。
+    fn minimize_with_config_unchecked(&self) -> Option<(Self, DfaConfig)> {
+        let (trimmed_dfa, trim_map) = match self.trim_unreachable() {
+            Some(trimmed) => trimmed,
+            None => return self.merge_indistinguishable(),
+        };
+        match trimmed_dfa.merge_indistinguishable() {
+            Some((minimized_dfa, mut config)) => {
+                config.id_map = trim_map
+                    .iter()
+                    .map(|(old, trimmed)| (*old, config.id_map[trimmed]))
+                    .collect();
+                Some((minimized_dfa, config))
+            }
+            None => {
+                let config = DfaConfig {
+                    number_of_states: trimmed_dfa.number_of_states() as usize,
+                    alphabet: trimmed_dfa.alphabet.clone(),
+                    start_state_id: trimmed_dfa.start_state(),
+                    accept_states: trimmed_dfa.accept_states.clone(),
+                    id_map: trim_map,
+                };
+                Some((trimmed_dfa, config))
+            }
+        }
+    }
+
+    /// 删去从开始状态不可达的状态，返回新的DFA和旧id到新id的映射。
+    /// 陷阱状态总是保留为0号状态，其他状态按原来的顺序重新编号。
+    /// 如果所有状态都可达，返回None。
+    fn trim_unreachable(&self) -> Option<(Self, HashMap<StateId, StateId>)> {
+        let reachable = self.reachable_states();
+        let kept: Vec<StateId> = (0..self.number_of_states())
+            .filter(|state| *state == 0 || reachable.contains(state))
+            .collect();
+        if kept.len() == self.number_of_states() as usize {
+            return None;
+        }
+
+        let id_map: HashMap<StateId, StateId> = kept
+            .iter()
+            .enumerate()
+            .map(|(new, old)| (*old, new as StateId))
+            .collect();
+        let table: Vec<Vec<StateId>> = kept
+            .iter()
+            .map(|old| {
+                self.alphabet
+                    .iter()
+                    .map(|input| id_map[&self.delta(*old, *input)])
+                    .collect()
+            })
+            .collect();
+        let accept_states: Vec<StateId> = self
+            .accept_states
+            .iter()
+            .filter_map(|state| id_map.get(state).cloned())
+            .collect();
+        let trimmed_dfa = Self::from_table(
+            self.alphabet.clone(),
+            &table,
+            id_map[&self.start_state()],
+            &accept_states,
+        );
+        Some((trimmed_dfa, id_map))
+    }
+
+    /// 合并不可区分的状态。如果没有可以合并的状态，返回None。
+    fn merge_indistinguishable(&self) -> Option<(Self, DfaConfig)> {
         let indistin_groups = minimize::compute_indistin_state_groups(self);
         if indistin_groups.num_of_groups() == 0 {
             return None;
@@ -762,12 +1880,779 @@ impl DenseDFA {
             let from = config.id_map[old_id];
             for input in self.alphabet.to_iter() {
                 let to = config.id_map[&self.delta(*old_id, input)];
-                dbg!(from, input, to);
                 minimized_dfa.add_transition(from, input, to);
             }
         }
 
-        Some(minimized_dfa)
+        Some((minimized_dfa, config))
+    }
+}
+
+/// dot输出的辅助方法
+impl DenseDFA {
+    /// 输出带自定义标签的dot，比如在幂集构造的状态图上把每个状态显示成它对应的NFA状态子集`{0,2,5}`。
+    ///
+    /// `labels`中没有的状态显示为`q{id}`。接受状态仍然画成双圈，开始状态前面有一个指向它的箭头。
+    /// 陷阱状态和`to_dot`一样不显示。部分DFA的0号状态是普通状态，会显示出来。
+    pub fn to_dot_with_labels(&self, labels: &HashMap<StateId, String>) -> String {
+        let label_of = |state: StateId| {
+            labels
+                .get(&state)
+                .cloned()
+                .unwrap_or_else(|| format!("q{}", state))
+                .replace('"', "\\\"")
+        };
+
+        let mut dot = String::new();
+        dot.push_str("digraph DFA {\n");
+        dot.push_str("rankdir=LR;\n");
+        dot.push_str("node [shape = doublecircle];\n");
+        for state_id in self.accept_states_sorted() {
+            if state_id != 0 || self.is_partial() {
+                dot.push_str(&format!(
+                    "{} [label = \"{}\"];\n",
+                    state_id,
+                    label_of(state_id)
+                ));
+            }
+        }
+        dot.push_str("node [shape = circle];\n");
+        for state_id in self.first_drawn_state(false)..self.number_of_states() {
+            if !self.accept_states.contains(&state_id) {
+                dot.push_str(&format!(
+                    "{} [label = \"{}\"];\n",
+                    state_id,
+                    label_of(state_id)
+                ));
+            }
+        }
+        dot.push_str("start [shape = point];\n");
+        dot.push_str(&format!("start -> {};\n", self.start_state()));
+        self.push_dot_edges(&mut dot, false);
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// 输出dot，并且把同一个Myhill-Nerode等价类中的状态框在同一个`subgraph cluster_*`里，
+    /// 这样在极小化之前就能看出哪些状态会被合并。
+    ///
+    /// 每个等价类一个框，标签是类中的状态，例如`{q2,q3}`。和`to_dot`一样不显示陷阱状态，
+    /// 所以只包含陷阱状态的等价类不会出现。
+    pub fn to_dot_clustered(&self) -> String {
+        let mut dot = String::new();
+        dot.push_str("digraph DFA {\n");
+        dot.push_str("rankdir=LR;\n");
+        let classes = self.equivalence_classes();
+        for (index, class) in classes.iter().enumerate() {
+            let states: Vec<StateId> = class
+                .iter()
+                .cloned()
+                .filter(|state| *state != 0 || self.is_partial())
+                .collect();
+            if states.is_empty() {
+                continue;
+            }
+            dot.push_str(&format!("subgraph cluster_{} {{\n", index));
+            dot.push_str(&format!(
+                "label = \"{{{}}}\";\n",
+                states.iter().map(|state| format!("q{}", state)).join(",")
+            ));
+            for state in states {
+                let shape = if self.accept_states.contains(&state) {
+                    "doublecircle"
+                } else {
+                    "circle"
+                };
+                dot.push_str(&format!("{} [shape = {}];\n", state, shape));
+            }
+            dot.push_str("}\n");
+        }
+        dot.push_str("start [shape = point];\n");
+        dot.push_str(&format!("start -> {};\n", self.start_state()));
+        self.push_dot_edges(&mut dot, false);
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// 讲解极小化用的一对dot：极小化之前的DFA（用`to_dot_clustered`框出等价类）和极小化之后的DFA。
+    ///
+    /// 极小DFA中每个状态的标签就是它所合并的那个等价类的框的标签，例如`{q2,q3}`，
+    /// 所以左右两张图可以一一对照。原DFA不可达的状态在极小化时被删掉了，它们的框在右边没有对应的状态。
+    pub fn minimization_dot_pair(&self) -> (String, String) {
+        let before = self.to_dot_clustered();
+        let minimized = self.minimize();
+
+        // 同时运行两个DFA，得到原DFA的每个可达状态对应的极小DFA状态
+        let start = (self.start_state(), minimized.start_state());
+        let mut visited: HashSet<(StateId, StateId)> = HashSet::new();
+        visited.insert(start);
+        let mut stack = vec![start];
+        let mut merged_into: HashMap<StateId, StateId> = HashMap::new();
+        while let Some((original, merged)) = stack.pop() {
+            merged_into.insert(original, merged);
+            for (input, to) in self.defined_transitions(original) {
+                let next = (to, minimized.delta(merged, input));
+                if visited.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+
+        let mut labels: HashMap<StateId, String> = HashMap::new();
+        for class in self.equivalence_classes() {
+            let states: Vec<StateId> = class
+                .iter()
+                .cloned()
+                .filter(|state| *state != 0 || self.is_partial())
+                .collect();
+            if let Some(merged) = states.iter().find_map(|state| merged_into.get(state)) {
+                labels.insert(
+                    *merged,
+                    format!(
+                        "{{{}}}",
+                        states.iter().map(|state| format!("q{}", state)).join(",")
+                    ),
+                );
+            }
+        }
+        (before, minimized.to_dot_with_labels(&labels))
+    }
+
+    /// 按照给定的选项输出dot。开始状态前面有一个指向它的箭头，平行的边合并成一条。
+    ///
+    /// `subset_labels`对稠密DFA没有作用，需要自定义标签时用`to_dot_with_labels`。
+    pub fn to_dot_opts(&self, options: &DotOptions) -> String {
+        let first = self.first_drawn_state(options.show_trap);
+        let mut dot = String::new();
+        dot.push_str("digraph DFA {\n");
+        dot.push_str("rankdir=LR;\n");
+        dot.push_str("node [shape = doublecircle];\n");
+        for state_id in self.accept_states_sorted() {
+            dot.push_str(&options.node(state_id, None));
+        }
+        dot.push_str("node [shape = circle];\n");
+        for state_id in first..self.number_of_states() {
+            if !self.accept_states.contains(&state_id) {
+                dot.push_str(&options.node(state_id, None));
+            }
+        }
+        if options.rank_by_distance {
+            self.push_dot_ranks(&mut dot, options.show_trap);
+        }
+        dot.push_str("start [shape = point];\n");
+        dot.push_str(&format!("start -> {};\n", self.start_state()));
+        self.push_dot_edges(&mut dot, options.show_trap);
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// 按到开始状态的距离把状态分层，每层输出一行`{ rank=same; ... }`，层内按状态id排列。
+    /// 不可达的状态不属于任何一层。
+    fn push_dot_ranks(&self, dot: &mut String, show_trap: bool) {
+        let mut layers: Vec<Vec<StateId>> = Vec::new();
+        for (state, distance) in self.distance_from_start() {
+            if state < self.first_drawn_state(show_trap) {
+                continue;
+            }
+            if layers.len() <= distance {
+                layers.resize(distance + 1, Vec::new());
+            }
+            layers[distance].push(state);
+        }
+        for mut layer in layers.into_iter().filter(|layer| !layer.is_empty()) {
+            layer.sort_unstable();
+            dot.push_str("{ rank=same; ");
+            for state in layer {
+                dot.push_str(&format!("{}; ", state));
+            }
+            dot.push_str("}\n");
+        }
+    }
+
+    /// 状态图上画出的第一个状态：显示陷阱状态或者是部分DFA时是0，否则是1。
+    fn first_drawn_state(&self, show_trap: bool) -> StateId {
+        if show_trap || self.is_partial() {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// 输出所有边，`show_trap`为false时不输出陷阱状态的边和指向陷阱状态的边。
+    /// 从同一个状态出发、到达同一个状态的多条边合并成一条，标签按字母表顺序用逗号连接。
+    /// 部分DFA没有陷阱状态，输出的是所有有定义的转移。
+    fn push_dot_edges(&self, dot: &mut String, show_trap: bool) {
+        self.push_dot_edges_highlighting(dot, show_trap, show_trap, None);
+    }
+
+    /// 和`push_dot_edges`相同，但是把`highlight`这条边画成红色的粗线。
+    /// 被高亮的边即使指向陷阱状态也会输出。
+    ///
+    /// `trap_drawn`为true时陷阱状态画在了图上，这时也输出陷阱状态自己的边（也就是它的自环），
+    /// 但是其他状态指向陷阱状态的边仍然由`show_trap`决定。
+    fn push_dot_edges_highlighting(
+        &self,
+        dot: &mut String,
+        show_trap: bool,
+        trap_drawn: bool,
+        highlight: Option<(StateId, StateId)>,
+    ) {
+        let show_trap = show_trap || self.is_partial();
+        for from in self.first_drawn_state(trap_drawn)..self.number_of_states() {
+            let mut edges: Vec<(StateId, Vec<String>)> = Vec::new();
+            for (input, to) in self.defined_transitions(from) {
+                if to == 0 && from != 0 && !show_trap && highlight != Some((from, to)) {
+                    continue;
+                }
+                match edges.iter_mut().find(|(target, _)| *target == to) {
+                    Some((_, labels)) => labels.push((input as char).to_string()),
+                    None => edges.push((to, vec![(input as char).to_string()])),
+                }
+            }
+            for (to, labels) in edges {
+                let style = if highlight == Some((from, to)) {
+                    ", color = red, penwidth = 2"
+                } else {
+                    ""
+                };
+                dot.push_str(&format!(
+                    "{} -> {} [label = \"{}\"{}];\n",
+                    from,
+                    to,
+                    labels.join(","),
+                    style
+                ));
+            }
+        }
+    }
+
+    /// 生成逐步运行`input`的动画帧，每一帧是一个完整的dot，前端可以一帧一帧地切换。
+    ///
+    /// 一共有`input.len() + 1`帧。第i帧表示读了i个字符之后的情形：当前状态填成黄色，
+    /// 下一步要走的边画成红色的粗线。最后一帧把当前状态填成绿色（接受）或红色（拒绝），
+    /// 并且在图的标签上写明`accepted`或`rejected`。
+    ///
+    /// 运行掉进0号陷阱状态时，陷阱状态、它的自环和走进去的那条边会显示出来，之后的帧一直停在陷阱状态。
+    /// 读到字母表之外的字符时也当作掉进陷阱状态。部分DFA读到没有定义的转移时运行直接结束，
+    /// 之后的帧不再高亮任何状态。
+    pub fn to_dot_animation_frames(&self, input: &[u8]) -> Vec<String> {
+        // run[i]是读了i个字符之后的状态，None表示部分DFA的运行已经结束
+        let mut run = vec![Some(self.start_state())];
+        for byte in input {
+            let next = run.last().cloned().flatten().and_then(|state| {
+                if !self.alphabet.contains(byte) {
+                    (!self.is_partial()).then_some(0)
+                } else if self.is_defined(state, *byte) {
+                    Some(self.delta(state, *byte))
+                } else {
+                    None
+                }
+            });
+            run.push(next);
+        }
+        let accepted =
+            matches!(run[input.len()], Some(state) if self.accept_states.contains(&state));
+        let show_trap = !self.is_partial() && run.contains(&Some(0));
+
+        (0..=input.len())
+            .map(|step| {
+                let current = run[step];
+                let mut dot = String::new();
+                dot.push_str("digraph DFA {\n");
+                dot.push_str("rankdir=LR;\n");
+                if step == input.len() {
+                    let verdict = if accepted { "accepted" } else { "rejected" };
+                    dot.push_str(&format!("label = \"{}\";\n", verdict));
+                }
+                dot.push_str("node [shape = doublecircle];\n");
+                for state_id in self.accept_states_sorted() {
+                    dot.push_str(&format!("{};\n", state_id));
+                }
+                dot.push_str("node [shape = circle];\n");
+                for state_id in self.first_drawn_state(show_trap)..self.number_of_states() {
+                    if !self.accept_states.contains(&state_id) {
+                        dot.push_str(&format!("{};\n", state_id));
+                    }
+                }
+                if let Some(state) = current {
+                    let color = match (step == input.len(), accepted) {
+                        (false, _) => "yellow",
+                        (true, true) => "green",
+                        (true, false) => "red",
+                    };
+                    dot.push_str(&format!(
+                        "{} [style = filled, fillcolor = {}];\n",
+                        state, color
+                    ));
+                }
+                dot.push_str("start [shape = point];\n");
+                dot.push_str(&format!("start -> {};\n", self.start_state()));
+                let highlight = match (current, run.get(step + 1)) {
+                    (Some(from), Some(Some(to))) if self.alphabet.contains(&input[step]) => {
+                        Some((from, *to))
+                    }
+                    _ => None,
+                };
+                self.push_dot_edges_highlighting(&mut dot, false, show_trap, highlight);
+                dot.push_str("}\n");
+                dot
+            })
+            .collect()
+    }
+}
+
+/// GraphML输出，用于yEd、Gephi等通用的图分析工具
+impl DenseDFA {
+    /// 把这个DFA输出为GraphML。和`to_dot`一样不显示陷阱状态。
+    pub fn to_graphml(&self) -> String {
+        self.to_graphml_with(false)
+    }
+
+    /// 把这个DFA输出为GraphML，`show_trap`为true时也输出陷阱状态和指向它的边。
+    ///
+    /// 节点的id是`q{状态id}`，带有布尔类型的`accept`和`start`两个数据；
+    /// 每个字符上的转移都是一条单独的边，带有字符串类型的`symbol`数据。
+    /// 部分DFA的0号状态是普通状态，总是输出，没有定义的转移不输出。
+    pub fn to_graphml_with(&self, show_trap: bool) -> String {
+        let first = self.first_drawn_state(show_trap);
+        let show_trap = show_trap || self.is_partial();
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        xml.push_str(
+            "<key id=\"accept\" for=\"node\" attr.name=\"accept\" attr.type=\"boolean\"/>\n",
+        );
+        xml.push_str(
+            "<key id=\"start\" for=\"node\" attr.name=\"start\" attr.type=\"boolean\"/>\n",
+        );
+        xml.push_str(
+            "<key id=\"symbol\" for=\"edge\" attr.name=\"symbol\" attr.type=\"string\"/>\n",
+        );
+        xml.push_str("<graph id=\"DFA\" edgedefault=\"directed\">\n");
+        for state in first..self.number_of_states() {
+            xml.push_str(&format!(
+                "<node id=\"q{}\"><data key=\"accept\">{}</data><data key=\"start\">{}</data></node>\n",
+                state,
+                self.accept_states.contains(&state),
+                state == self.start_state()
+            ));
+        }
+        for from in first..self.number_of_states() {
+            for (input, to) in self.defined_transitions(from) {
+                if to == 0 && !show_trap {
+                    continue;
+                }
+                xml.push_str(&format!(
+                    "<edge source=\"q{}\" target=\"q{}\"><data key=\"symbol\">{}</data></edge>\n",
+                    from,
+                    to,
+                    escape_html(&(input as char).to_string())
+                ));
+            }
+        }
+        xml.push_str("</graph>\n");
+        xml.push_str("</graphml>\n");
+        xml
+    }
+}
+
+/// 识别字符串时的选项。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatchOptions {
+    /// 忽略大小写。打开之后，输入和字母表中的ASCII字母都不区分大小写，
+    /// 这样一个只用小写字母构造的DFA也能识别大写的输入，反过来也一样。
+    /// 字母表中同时有一个字母的大小写两种形式时，输入按原样匹配。非ASCII字节不受影响。
+    pub case_insensitive: bool,
+}
+
+/// `accepts_detailed`拒绝一个字符串的原因。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    /// 读入第`at`个字节`byte`之后进入了陷阱状态，后面的输入不可能再被接受。
+    FellIntoTrap { at: usize, byte: u8 },
+    /// 读完了整个输入，但停在了非接受状态`final_state`。
+    EndedInNonAccepting { final_state: StateId },
+    /// 第`at`个字节`byte`不在字母表中。
+    UnknownSymbol { at: usize, byte: u8 },
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RejectReason::FellIntoTrap { at, byte } => {
+                write!(
+                    f,
+                    "no match possible after '{}' at {}",
+                    display_byte(*byte),
+                    at
+                )
+            }
+            RejectReason::EndedInNonAccepting { final_state } => {
+                write!(f, "input ended in non-accepting state {}", final_state)
+            }
+            RejectReason::UnknownSymbol { at, byte } => {
+                write!(
+                    f,
+                    "symbol '{}' at {} is not in the alphabet",
+                    display_byte(*byte),
+                    at
+                )
+            }
+        }
+    }
+}
+
+/// 把错误信息中的一个字节显示成人能看懂的样子。可打印的ASCII字符原样显示。
+///
+/// 其他字节打开`bstr` feature时用bstr的有损UTF-8显示，不能单独解码的字节显示为`�`；
+/// 没有打开时显示为`\xNN`，例如`\xE9`。`byte as char`会把它当成Latin-1，显示成不相干的字符。
+fn display_byte(byte: u8) -> String {
+    if byte.is_ascii_graphic() || byte == b' ' {
+        return (byte as char).to_string();
+    }
+    #[cfg(feature = "bstr")]
+    {
+        use bstr::ByteSlice;
+        [byte].as_bstr().to_string()
+    }
+    #[cfg(not(feature = "bstr"))]
+    {
+        format!("\\x{:02X}", byte)
+    }
+}
+
+impl std::error::Error for RejectReason {}
+
+/// 部分DFA中没有定义的转移的语义。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Partiality {
+    /// 没有定义的转移表示“卡住了”，直接拒绝。`accepts`等方法默认使用这种语义。
+    #[default]
+    UndefinedRejects,
+    /// 没有定义的转移指向0号状态，然后继续读后面的输入，也就是完全DFA的陷阱语义。
+    UndefinedIsTrap,
+}
+
+/// 用DFA识别字符串的相关方法
+impl DenseDFA {
+    /// 和`accepts`一样判断这个DFA是否接受输入，拒绝时报告失败的位置和原因，可以用来生成错误信息。
+    ///
+    /// 如果开始状态就是陷阱状态，非空的输入在第0个字节处报告`FellIntoTrap`。
+    /// 部分DFA中0号状态是普通的状态，读到没有定义的转移时报告`FellIntoTrap`。
+    pub fn accepts_detailed(&self, input: &[u8]) -> Result<(), RejectReason> {
+        let table = self.symbol_table();
+        let mut state = self.start_state();
+        for (at, byte) in input.iter().enumerate() {
+            let index = match table[*byte as usize] {
+                Some(index) => index,
+                None => return Err(RejectReason::UnknownSymbol { at, byte: *byte }),
+            };
+            let cell = ((state as usize) << self.out_transitions.stride_as_power_of_2) + index;
+            if matches!(&self.defined, Some(defined) if !defined[cell]) {
+                return Err(RejectReason::FellIntoTrap { at, byte: *byte });
+            }
+            state = self.out_transitions.trans[cell];
+            if state == 0 && !self.is_partial() {
+                return Err(RejectReason::FellIntoTrap { at, byte: *byte });
+            }
+        }
+        if self.accept_states.contains(&state) {
+            Ok(())
+        } else {
+            Err(RejectReason::EndedInNonAccepting { final_state: state })
+        }
+    }
+
+    /// 判断这个DFA是否接受输入，同时返回经过的状态序列，用于调试“为什么不接受”。
+    ///
+    /// 序列的第一个元素是开始状态，之后每读一个字节追加一个到达的状态。
+    /// 进入0号陷阱状态时，0是序列的最后一个元素，后面的输入不再读取。
+    /// 遇到字母表之外的字节（或者部分DFA中没有定义的转移）时，序列停在最后一个合法的状态，结果为false。
+    pub fn accepts_with_trace(&self, input: &str) -> (bool, Vec<StateId>) {
+        let table = self.symbol_table();
+        let mut state = self.start_state();
+        let mut trace = vec![state];
+        for byte in input.bytes() {
+            if state == 0 && !self.is_partial() {
+                return (false, trace);
+            }
+            let index = match table[byte as usize] {
+                Some(index) => index,
+                None => return (false, trace),
+            };
+            let cell = ((state as usize) << self.out_transitions.stride_as_power_of_2) + index;
+            if matches!(&self.defined, Some(defined) if !defined[cell]) {
+                return (false, trace);
+            }
+            state = self.out_transitions.trans[cell];
+            trace.push(state);
+        }
+        (self.accept_states.contains(&state), trace)
+    }
+
+    /// 判断这个DFA是否接受输入的字符串。
+    ///
+    /// 如果输入中有字母表之外的字符，直接返回false，而不是panic。
+    ///
+    /// 字母表恰好是{0,1}时（课程作业中最常见的情况）走一条专门的快速路径，见`accepts_binary`。
+    pub fn accepts(&self, input: &str) -> bool {
+        self.accepts_bytes(input.as_bytes())
+    }
+
+    /// 和`accepts`相同，但是输入是任意的字节串，不要求是合法的UTF-8。
+    ///
+    /// 字母表中有非ASCII字节（比如直接用`from_table`构造的DFA）时，输入常常不能写成`&str`。
+    pub fn accepts_bytes(&self, input: &[u8]) -> bool {
+        match self.accepts_binary(input) {
+            Some(accepted) => accepted,
+            None => self.accepts_with_table(&self.symbol_table(), input),
+        }
+    }
+
+    /// 按照给定的部分DFA语义判断这个DFA是否接受输入。对完全DFA来说两种语义的结果相同。
+    pub fn accepts_with_partiality(&self, input: &[u8], partiality: Partiality) -> bool {
+        match partiality {
+            Partiality::UndefinedRejects => self.accepts_with_table(&self.symbol_table(), input),
+            Partiality::UndefinedIsTrap => {
+                let table = self.symbol_table();
+                let mut state = self.start_state();
+                for byte in input {
+                    match table[*byte as usize] {
+                        Some(index) => {
+                            state = self.out_transitions.trans[((state as usize)
+                                << self.out_transitions.stride_as_power_of_2)
+                                + index]
+                        }
+                        None => return false,
+                    }
+                }
+                self.accept_states.contains(&state)
+            }
+        }
+    }
+
+    /// 按照给定的选项判断这个DFA是否接受输入的字符串。
+    pub fn accepts_with(&self, input: &str, options: &MatchOptions) -> bool {
+        if options.case_insensitive {
+            self.accepts_with_table(&self.folded_symbol_table(), input.as_bytes())
+        } else {
+            self.accepts(input)
+        }
+    }
+
+    /// 批量判断这个DFA是否接受每一个输入字符串，返回值和输入一一对应。
+    pub fn accepts_all_of(&self, inputs: &[&str]) -> Vec<bool> {
+        let table = self.symbol_table();
+        inputs
+            .iter()
+            .map(|input| self.accepts_with_table(&table, input.as_bytes()))
+            .collect()
+    }
+
+    /// 把输入字符串分成两组，第一组是被接受的，第二组是被拒绝的。
+    pub fn partition_inputs(&self, inputs: &[&str]) -> (Vec<String>, Vec<String>) {
+        let table = self.symbol_table();
+        let (accepted, rejected): (Vec<&str>, Vec<&str>) = inputs
+            .iter()
+            .partition(|input| self.accepts_with_table(&table, input.as_bytes()));
+        (
+            accepted.into_iter().map(String::from).collect(),
+            rejected.into_iter().map(String::from).collect(),
+        )
+    }
+
+    /// 判断`input`是否属于这个DFA的语言的反转，也就是`input`倒过来之后是否被接受。
+    ///
+    /// 不构造反转的DFA，而是利用入表做NFA式的反向模拟：从接受状态集合出发，
+    /// 按顺序读入`input`的每个字符，每次把集合换成所有能读这个字符转移到集合中的状态，
+    /// 最后集合包含开始状态就接受。结果和`reverse_to_dfa().accepts`一致。
+    /// 部分DFA中没有定义的转移虽然在入表里记成了指向0号状态，这里会跳过它们。
+    pub fn accepts_reversed(&self, input: &[u8]) -> bool {
+        let table = self.symbol_table();
+        let mut current: HashSet<StateId> = self.accept_states.clone();
+        for byte in input {
+            let index = match table[*byte as usize] {
+                Some(index) => index,
+                None => return false,
+            };
+            current = current
+                .iter()
+                .flat_map(|to| {
+                    self.in_transitions.trans
+                        [((*to as usize) << self.in_transitions.stride_as_power_of_2) + index]
+                        .iter()
+                        .cloned()
+                })
+                .filter(|from| match &self.defined {
+                    Some(defined) => {
+                        defined[((*from as usize) << self.out_transitions.stride_as_power_of_2)
+                            + index]
+                    }
+                    None => true,
+                })
+                .collect();
+            if current.is_empty() {
+                return false;
+            }
+        }
+        current.contains(&self.start_state())
+    }
+
+    /// 返回`input`被接受的最长前缀的长度，没有任何前缀被接受时返回None。
+    ///
+    /// 空串被接受时，长度为0的前缀也算。进入陷阱状态或者读到字母表之外的字符时停止扫描。
+    pub fn longest_accepting_prefix(&self, input: &[u8]) -> Option<usize> {
+        self.longest_accepting_prefix_with_table(&self.symbol_table(), input)
+    }
+
+    /// 在`input`中查找所有匹配，返回每个匹配的字节范围`(start, end)`，左闭右开。
+    ///
+    /// 采用最左最长、互不重叠的语义：从左往右扫描，在每个位置上取最长的被接受前缀作为一个匹配，
+    /// 然后从这个匹配的结尾继续扫描；这个位置没有匹配时就往后移一个字节。
+    /// 空串不算作匹配，所以即使DFA接受空串，结果中也不会出现`start == end`的范围。
+    pub fn find_matches(&self, input: &[u8]) -> Vec<(usize, usize)> {
+        let table = self.symbol_table();
+        let mut matches = Vec::new();
+        let mut start = 0;
+        while start < input.len() {
+            match self.longest_accepting_prefix_with_table(&table, &input[start..]) {
+                Some(len) if len > 0 => {
+                    matches.push((start, start + len));
+                    start += len;
+                }
+                _ => start += 1,
+            }
+        }
+        matches
+    }
+
+    fn longest_accepting_prefix_with_table(
+        &self,
+        table: &[Option<usize>; 256],
+        input: &[u8],
+    ) -> Option<usize> {
+        let mut state = self.start_state();
+        let mut longest = None;
+        if self.accept_states.contains(&state) {
+            longest = Some(0);
+        }
+        for (position, byte) in input.iter().enumerate() {
+            let index = match table[*byte as usize] {
+                Some(index) => index,
+                None => break,
+            };
+            let cell = ((state as usize) << self.out_transitions.stride_as_power_of_2) + index;
+            if matches!(&self.defined, Some(defined) if !defined[cell]) {
+                break;
+            }
+            state = self.out_transitions.trans[cell];
+            if state == 0 && !self.is_partial() {
+                break;
+            }
+            if self.accept_states.contains(&state) {
+                longest = Some(position + 1);
+            }
+        }
+        longest
+    }
+
+    /// 生成一张从输入字符到它在字母表中的索引的查找表。
+    ///
+    /// 批量识别字符串时只需要生成一次，之后每读一个字符都不用再在字母表里搜索了。
+    fn symbol_table(&self) -> [Option<usize>; 256] {
+        let mut table = [None; 256];
+        for (index, input) in self.alphabet.iter().enumerate() {
+            table[*input as usize] = Some(index);
+        }
+        table
+    }
+
+    /// 和`symbol_table`相同，但是不区分ASCII字母的大小写：字母表里没有的字母，
+    /// 查它的另一种大小写形式。
+    fn folded_symbol_table(&self) -> [Option<usize>; 256] {
+        let exact = self.symbol_table();
+        let mut table = exact;
+        for byte in 0..=u8::MAX {
+            if exact[byte as usize].is_none() && byte.is_ascii_alphabetic() {
+                table[byte as usize] = exact[(byte ^ 0x20) as usize];
+            }
+        }
+        table
+    }
+
+    /// 字母表恰好是{0,1}时的快速路径，字母表不是{0,1}时返回None。
+    ///
+    /// 不需要构造256项的字符表，直接用`byte - b'0'`作为下标，字母表的顺序是"10"时再和1异或一下。
+    /// 识别的时间主要花在“查表得到下一个状态”这条前后依赖的链上，所以输入较长时，
+    /// 先算出每个状态连续读入4个字符之后到达的状态（每个状态16项），一次查表前进4个字符，链的长度变为原来的1/4。
+    fn accepts_binary(&self, input: &[u8]) -> Option<bool> {
+        if self.is_partial() {
+            return None;
+        }
+        let flip = match self.alphabet.as_slice() {
+            b"01" => 0,
+            b"10" => 1,
+            _ => return None,
+        };
+        let trans = &self.out_transitions.trans;
+        let shift = self.out_transitions.stride_as_power_of_2;
+        let step =
+            |state: usize, digit: u8| trans[(state << shift) + (digit ^ flip) as usize] as usize;
+
+        let mut state = self.start_state() as usize;
+        let mut rest = input;
+        let number_of_states = self.number_of_states() as usize;
+        if input.len() >= 64 * number_of_states {
+            let nibbles: Vec<usize> = (0..number_of_states * 16)
+                .map(|index| {
+                    (0..4).fold(index >> 4, |state, bit| {
+                        step(state, ((index >> (3 - bit)) & 1) as u8)
+                    })
+                })
+                .collect();
+            let chunks = input.chunks_exact(4);
+            rest = chunks.remainder();
+            for chunk in chunks {
+                let mut nibble = 0;
+                for byte in chunk {
+                    let digit = byte.wrapping_sub(b'0');
+                    if digit > 1 {
+                        return Some(false);
+                    }
+                    nibble = (nibble << 1) | digit as usize;
+                }
+                state = nibbles[(state << 4) | nibble];
+            }
+        }
+        for byte in rest {
+            let digit = byte.wrapping_sub(b'0');
+            if digit > 1 {
+                return Some(false);
+            }
+            state = step(state, digit);
+        }
+        Some(self.accept_states.contains(&(state as StateId)))
+    }
+
+    /// 部分DFA中没有定义的转移直接拒绝。
+    fn accepts_with_table(&self, table: &[Option<usize>; 256], input: &[u8]) -> bool {
+        let mut state = self.start_state();
+        for byte in input {
+            match table[*byte as usize] {
+                Some(index) => {
+                    let cell =
+                        ((state as usize) << self.out_transitions.stride_as_power_of_2) + index;
+                    if let Some(defined) = &self.defined {
+                        if !defined[cell] {
+                            return false;
+                        }
+                    }
+                    state = self.out_transitions.trans[cell]
+                }
+                None => return false,
+            }
+        }
+        self.accept_states.contains(&state)
     }
 }
 
@@ -780,6 +2665,7 @@ impl fmt::Display for DenseDFA {
 /// 输入字符可以是任意ASCII码的稀疏DFA的状态。
 ///
 /// 目前还没实现这样的DFA，所以这个结构体也没人用。
+#[allow(dead_code)]
 struct StateAscii {
     to: Vec<(u8, StateId)>,
 }
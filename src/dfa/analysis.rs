@@ -0,0 +1,850 @@
+use std::cmp::Reverse;
+use std::collections::{hash_map::Entry, BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::convert::TryFrom;
+
+use itertools::Itertools;
+
+use super::{AlphabetError, CompletedDfa, DenseDFA, ProductError, DEFAULT_PRODUCT_LIMIT};
+
+type StateId = u128;
+
+/// 泵引理中的分解 w = xyz。
+///
+/// `y` 非空，并且对任意 k ≥ 0，x y^k z 都被DFA接受。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PumpDecomposition {
+    pub x: Vec<u8>,
+    pub y: Vec<u8>,
+    pub z: Vec<u8>,
+    /// 读完 x 和读完 xy 之后所处的状态，也就是运行路径上第一个重复出现的状态。
+    pub repeated_state: StateId,
+}
+
+impl PumpDecomposition {
+    /// 返回 x y^k z。
+    pub fn pump(&self, k: usize) -> Vec<u8> {
+        let mut word = self.x.clone();
+        for _ in 0..k {
+            word.extend_from_slice(&self.y);
+        }
+        word.extend_from_slice(&self.z);
+        word
+    }
+}
+
+impl DenseDFA {
+    /// 找一个长度至少为`min_len`的、被接受的字符串，并按泵引理把它分解为 xyz。
+    ///
+    /// 根据鸽巢原理，长度不小于状态数的字符串在运行时一定会经过重复的状态，
+    /// 所以实际搜索的长度下限是`min_len`和状态数中较大的那个。
+    /// 找到的是满足条件的最短字符串，y 是运行路径上第一个重复状态所构成的环。
+    ///
+    /// 如果语言是有限的，不存在这样的分解，返回None。
+    pub fn find_pumpable_run(&self, min_len: usize) -> Option<PumpDecomposition> {
+        let min_len = min_len.max(self.number_of_states() as usize);
+        let word = self.shortest_accepted_at_least(min_len)?;
+
+        // 记录运行路径上的状态，找到第一个重复的状态。
+        let mut path = vec![self.start_state()];
+        for input in word.iter() {
+            let next = self.delta(*path.last().unwrap(), *input);
+            if let Some(first) = path.iter().position(|state| *state == next) {
+                let second = path.len();
+                return Some(PumpDecomposition {
+                    x: word[..first].to_vec(),
+                    y: word[first..second].to_vec(),
+                    z: word[second..].to_vec(),
+                    repeated_state: next,
+                });
+            }
+            path.push(next);
+        }
+        None
+    }
+
+    /// 返回这个DFA接受的最短字符串。长度相同时，按字母表顺序取最小的那个。
+    ///
+    /// 如果DFA不接受任何字符串，返回None。
+    pub fn shortest_accepted(&self) -> Option<Vec<u8>> {
+        self.shortest_accepted_at_least(0)
+    }
+
+    /// 判断语言是否非空，并且给出一个证据：从开始状态到最近的接受状态的路径上读的字符串。
+    ///
+    /// 和`shortest_accepted`是同一件事，只是换了个角度：返回None就说明语言是空的，
+    /// 返回Some就同时得到了语言中的一个成员，不需要把可达性分析跑两遍。
+    pub fn nonempty_witness(&self) -> Option<Vec<u8>> {
+        self.shortest_accepted()
+    }
+
+    /// 用BFS求每个可达状态到开始状态的最短距离，也就是从开始状态到达它最少要读几个字符。
+    ///
+    /// 不可达的状态不会出现在结果中。画状态图时可以按距离把状态分成一列一列的。
+    pub fn distance_from_start(&self) -> HashMap<StateId, usize> {
+        let mut distance = HashMap::new();
+        let mut queue = VecDeque::new();
+        distance.insert(self.start_state(), 0);
+        queue.push_back(self.start_state());
+
+        while let Some(state) = queue.pop_front() {
+            let next_distance = distance[&state] + 1;
+            for (_, next) in self.defined_transitions(state) {
+                if let Entry::Vacant(entry) = distance.entry(next) {
+                    entry.insert(next_distance);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distance
+    }
+
+    /// 返回从开始状态到达`state`的最短输入字符串，长度相同时按字母表顺序取最小的那个。
+    /// `state`不可达或者不存在时返回None。
+    ///
+    /// 和`shortest_accepted`不同，目标是一个指定的状态，不管它是不是接受状态。
+    /// 可以用来构造走到某个状态的测试输入，或者在界面上点击一个状态时显示怎样到达它。
+    pub fn shortest_string_reaching(&self, state: StateId) -> Option<Vec<u8>> {
+        // 记录每个状态的前驱状态和进入它时读的字符，用于最后还原字符串。
+        let mut previous: HashMap<StateId, Option<(StateId, u8)>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        previous.insert(self.start_state(), None);
+        queue.push_back(self.start_state());
+
+        while let Some(current) = queue.pop_front() {
+            if current == state {
+                let mut word = Vec::new();
+                let mut current = current;
+                while let Some((prev, input)) = previous[&current] {
+                    word.push(input);
+                    current = prev;
+                }
+                word.reverse();
+                return Some(word);
+            }
+            for (input, next) in self.defined_transitions(current) {
+                if let Entry::Vacant(entry) = previous.entry(next) {
+                    entry.insert(Some((current, input)));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// 用BFS找到长度至少为`min_len`的最短的被接受字符串。
+    ///
+    /// 搜索的节点是 (状态, min(已读长度, min_len))，所以节点数最多是 状态数 × (min_len + 1)。
+    fn shortest_accepted_at_least(&self, min_len: usize) -> Option<Vec<u8>> {
+        let number_of_states = self.number_of_states() as usize;
+        let node_of = |state: StateId, len: usize| state as usize * (min_len + 1) + len;
+
+        // 记录每个节点的前驱节点和进入它时读的字符，用于最后还原字符串。
+        let mut previous: Vec<Option<(usize, u8)>> = vec![None; number_of_states * (min_len + 1)];
+        let mut visited = vec![false; number_of_states * (min_len + 1)];
+        let mut queue = VecDeque::new();
+
+        let start = node_of(self.start_state(), 0);
+        visited[start] = true;
+        queue.push_back((self.start_state(), 0));
+
+        while let Some((state, len)) = queue.pop_front() {
+            if len == min_len && self.accept_states.contains(&state) {
+                let mut word = Vec::new();
+                let mut node = node_of(state, len);
+                while let Some((prev, input)) = previous[node] {
+                    word.push(input);
+                    node = prev;
+                }
+                word.reverse();
+                return Some(word);
+            }
+            for (input, next) in self.defined_transitions(state) {
+                let next_len = (len + 1).min(min_len);
+                let next_node = node_of(next, next_len);
+                if !visited[next_node] {
+                    visited[next_node] = true;
+                    previous[next_node] = Some((node_of(state, len), input));
+                    queue.push_back((next, next_len));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// 计数相关的方法
+impl DenseDFA {
+    /// 这个DFA接受的长度恰好为`n`的字符串的数量。
+    ///
+    /// 结果超过`u128`的范围时会饱和在`u128::MAX`。
+    pub fn count_accepted_of_length(&self, n: usize) -> u128 {
+        *self.count_accepted_by_length(n).last().unwrap()
+    }
+
+    /// 和`count_accepted_of_length`一样，但是用大整数计数，结果总是精确的。需要打开`num-bigint` feature。
+    ///
+    /// 比如全体01串在n = 128时就有2^128个，已经超出了`u128`。不需要这么大的数时用`u128`的版本更快。
+    #[cfg(feature = "num-bigint")]
+    pub fn count_accepted_of_length_big(&self, n: usize) -> num_bigint::BigUint {
+        let number_of_states = self.number_of_states() as usize;
+        let mut counts = vec![num_bigint::BigUint::default(); number_of_states];
+        counts[self.start_state() as usize] = 1u32.into();
+        for _ in 0..n {
+            let mut next = vec![num_bigint::BigUint::default(); number_of_states];
+            for (state, count) in counts.iter().enumerate() {
+                if *count == num_bigint::BigUint::default() {
+                    continue;
+                }
+                for (_, to) in self.defined_transitions(state as StateId) {
+                    next[to as usize] += count;
+                }
+            }
+            counts = next;
+        }
+        self.accept_states
+            .iter()
+            .map(|state| &counts[*state as usize])
+            .sum()
+    }
+
+    /// 判断这个DFA是否接受字母表上所有长度恰好为`n`的字符串，例如L是否包含所有3位的01串。
+    ///
+    /// 等价于`count_accepted_of_length(n) == 字母表大小^n`。
+    /// 字母表大小^n超出`u128`时计数已经饱和，不能再比较，改为检查长度为`n`的字符串能到达的状态是否都是接受状态。
+    pub fn language_contains_all_of_length(&self, n: usize) -> bool {
+        if self.is_partial() {
+            return self.complete_partial().language_contains_all_of_length(n);
+        }
+        let total = u32::try_from(n)
+            .ok()
+            .and_then(|n| (self.alphabet.len() as u128).checked_pow(n));
+        if let Some(total) = total {
+            return self.count_accepted_of_length(n) == total;
+        }
+
+        let mut current: HashSet<StateId> = HashSet::new();
+        current.insert(self.start_state());
+        for _ in 0..n {
+            current = current
+                .iter()
+                .flat_map(|state| self.alphabet.iter().map(move |input| (*state, *input)))
+                .map(|(state, input)| self.delta(state, input))
+                .collect();
+        }
+        current.is_subset(&self.accept_states)
+    }
+
+    /// 按照先长度、后字典序的顺序，返回这个DFA接受的前`limit`个字符串。字典序按字符的大小比较。
+    ///
+    /// 对每个长度，先算出哪些状态恰好再读这么多个字符能到达接受状态，枚举时只走这些状态，
+    /// 所以每输出一个字符串最多只需要 O(长度 × 字母表大小) 的时间，不会在死路上浪费时间。
+    ///
+    /// 语言有限并且不足`limit`个字符串时返回全部。长度不小于状态数n的字符串可以被泵，
+    /// 所以语言无限时每连续n个长度中都有被接受的字符串，连续n个长度都没有时就可以停止了。
+    pub fn enumerate_accepted(&self, limit: usize) -> Vec<Vec<u8>> {
+        AcceptedPaths::new(self)
+            .take(limit)
+            .map(|(word, _)| word)
+            .collect()
+    }
+
+    /// 按照和`enumerate_accepted`相同的顺序，惰性地枚举被接受的字符串，以及读这个字符串时经过的状态。
+    ///
+    /// 状态路径从开始状态开始，比字符串长1。不能到达接受状态的路径在枚举时就被剪掉了。
+    /// 语言无限时迭代器也是无限的，需要用`take`等方法截断。字符串中不是UTF-8的字节会被替换成`�`。
+    pub fn iter_accepted_with_state_paths(
+        &self,
+    ) -> impl Iterator<Item = (String, Vec<StateId>)> + '_ {
+        AcceptedPaths::new(self)
+            .map(|(word, path)| (String::from_utf8_lossy(&word).into_owned(), path))
+    }
+
+    /// 返回两个DFA的语言的对称差中最短的至多`k`个字符串，先按长度、再按字典序排列，
+    /// 用于展示两个正则表达式在哪些字符串上不一致。两个语言相同时返回空表。
+    ///
+    /// 对称差的积DFA的状态数超过`DEFAULT_PRODUCT_LIMIT`时返回`ProductTooLarge`，
+    /// 需要别的上限时用`language_difference_examples_with_limit`。
+    pub fn language_difference_examples(
+        &self,
+        other: &DenseDFA,
+        k: usize,
+    ) -> Result<Vec<Vec<u8>>, ProductError> {
+        self.language_difference_examples_with_limit(other, k, DEFAULT_PRODUCT_LIMIT)
+    }
+
+    /// 和`language_difference_examples`相同，但是对称差的积DFA的状态超过`limit`个时返回`ProductTooLarge`。
+    pub fn language_difference_examples_with_limit(
+        &self,
+        other: &DenseDFA,
+        k: usize,
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, ProductError> {
+        Ok(self
+            .product_with_limit(other, |left, right| left != right, limit)?
+            .enumerate_accepted(k))
+    }
+
+    /// 逐个长度比较两个DFA接受的字符串的数量。
+    ///
+    /// 返回值的每一项是 (长度, 本DFA接受的数量 - 另一个DFA接受的数量)，长度从0到`up_to`。
+    /// 两个DFA的字母表必须相同。
+    pub fn length_count_diff(
+        &self,
+        other: &DenseDFA,
+        up_to: usize,
+    ) -> Result<Vec<(usize, i128)>, AlphabetError> {
+        self.check_same_alphabet(other)?;
+        let to_i128 = |count: u128| count.min(i128::MAX as u128) as i128;
+        Ok(self
+            .count_accepted_by_length(up_to)
+            .into_iter()
+            .zip(other.count_accepted_by_length(up_to))
+            .map(|(left, right)| to_i128(left).saturating_sub(to_i128(right)))
+            .enumerate()
+            .collect())
+    }
+
+    /// 状态转移矩阵：第i行第j列是从状态i一步转移到状态j的字符的数量。
+    /// 部分DFA中没有定义的转移不计入。
+    pub fn to_transition_matrix(&self) -> Vec<Vec<usize>> {
+        let number_of_states = self.number_of_states() as usize;
+        let mut matrix = vec![vec![0; number_of_states]; number_of_states];
+        for (from, row) in matrix.iter_mut().enumerate() {
+            for (_, to) in self.defined_transitions(from as StateId) {
+                row[to as usize] += 1;
+            }
+        }
+        matrix
+    }
+
+    /// 被接受的字符串的数量随长度指数增长的底数，也就是语言的“熵”的指数形式。
+    ///
+    /// 等于只保留“活”状态（可达并且能到达接受状态）之后，转移矩阵的谱半径。
+    /// 有限语言返回0.0；像`0*`这样每个长度只有多项式多个字符串的语言返回1.0（数值上可能略大一点）。
+    ///
+    /// 用幂迭代求谱半径。直接对转移矩阵迭代，遇到周期性的结构（比如`(01)*`）时不收敛，
+    /// 所以对 矩阵+单位矩阵 迭代，求出的谱半径再减1。非负矩阵加上单位矩阵之后，模最大的特征值只有一个。
+    pub fn growth_rate(&self) -> f64 {
+        let coreachable = self.coreachable_states();
+        let mut live: Vec<StateId> = self
+            .reachable_states()
+            .into_iter()
+            .filter(|state| coreachable.contains(state))
+            .collect();
+        live.sort_unstable();
+        let matrix = self.to_transition_matrix();
+        let live_matrix: Vec<Vec<f64>> = live
+            .iter()
+            .map(|from| {
+                live.iter()
+                    .map(|to| matrix[*from as usize][*to as usize] as f64)
+                    .collect()
+            })
+            .collect();
+
+        if !has_cycle(&live_matrix) {
+            return 0.0;
+        }
+
+        let n = live.len();
+        let mut vector = vec![1.0; n];
+        let mut rate = 0.0;
+        for _ in 0..10000 {
+            let mut next = vector.clone();
+            for (from, row) in live_matrix.iter().enumerate() {
+                for (to, count) in row.iter().enumerate() {
+                    next[to] += vector[from] * count;
+                }
+            }
+            let norm = next.iter().cloned().fold(0.0, f64::max);
+            let new_rate = norm / vector.iter().cloned().fold(0.0, f64::max) - 1.0;
+            vector = next.into_iter().map(|x| x / norm).collect();
+            if (new_rate - rate).abs() < 1e-12 {
+                return new_rate;
+            }
+            rate = new_rate;
+        }
+        rate
+    }
+
+    /// 动态规划：counts\[q\] 是从开始状态读入长度为 len 的字符串后停在 q 的字符串的数量。
+    /// 返回每个长度 0..=up_to 的被接受字符串的数量。
+    fn count_accepted_by_length(&self, up_to: usize) -> Vec<u128> {
+        let number_of_states = self.number_of_states() as usize;
+        let mut counts = vec![0u128; number_of_states];
+        counts[self.start_state() as usize] = 1;
+
+        let mut result = Vec::with_capacity(up_to + 1);
+        for len in 0..=up_to {
+            result.push(self.accept_states.iter().fold(0u128, |sum, state| {
+                sum.saturating_add(counts[*state as usize])
+            }));
+            if len == up_to {
+                break;
+            }
+            let mut next = vec![0u128; number_of_states];
+            for (state, count) in counts.iter().enumerate() {
+                if *count == 0 {
+                    continue;
+                }
+                for (_, to) in self.defined_transitions(state as StateId) {
+                    next[to as usize] = next[to as usize].saturating_add(*count);
+                }
+            }
+            counts = next;
+        }
+        result
+    }
+}
+
+/// 可达性和闭包性质相关的方法
+impl DenseDFA {
+    /// 从开始状态出发可达的所有状态。
+    pub(crate) fn reachable_states(&self) -> HashSet<StateId> {
+        self.distance_from_start().into_keys().collect()
+    }
+
+    /// 能到达某个接受状态的所有状态（“余可达”状态）。通过入表从接受状态反向搜索。
+    pub(crate) fn coreachable_states(&self) -> HashSet<StateId> {
+        let mut coreachable: HashSet<StateId> = self.accept_states.iter().cloned().collect();
+        let mut stack: Vec<StateId> = coreachable.iter().cloned().collect();
+        while let Some(state) = stack.pop() {
+            for (from, _) in self.symbols_reaching(state) {
+                if coreachable.insert(from) {
+                    stack.push(from);
+                }
+            }
+        }
+        coreachable
+    }
+
+    /// 语言实际用到的字符，也就是语言的“有效字母表”。
+    ///
+    /// 只统计trim之后两个活状态之间的转移上的字符。声明在字母表里、但是只会通向陷阱状态
+    /// 或者只出现在不可达状态上的字符不算在内，例如字母表{0,1}上的`0*`只返回{0}。
+    pub fn language_symbols_used(&self) -> HashSet<u8> {
+        let trimmed = self.trim();
+        let mut used = HashSet::new();
+        for from in 1..trimmed.number_of_states() {
+            for input in trimmed.alphabet.iter() {
+                if trimmed.delta(from, *input) != 0 {
+                    used.insert(*input);
+                }
+            }
+        }
+        used
+    }
+
+    /// 判断语言是否是前缀封闭的，也就是每个被接受字符串的每个前缀也都被接受。
+    ///
+    /// 这等价于：任何可达的非接受状态都到不了接受状态。
+    /// 因为被接受字符串的运行路径上的每个状态都是可达的、并且能到达接受状态，它们都必须是接受状态。
+    pub fn is_prefix_closed(&self) -> bool {
+        let coreachable = self.coreachable_states();
+        self.reachable_states()
+            .iter()
+            .all(|state| self.accept_states.contains(state) || !coreachable.contains(state))
+    }
+
+    /// 判断语言是否是后缀封闭的，也就是每个被接受字符串的每个后缀也都被接受。
+    ///
+    /// 如果 uv 被接受，并且读入 u 之后到达状态 p，那么 v 属于 p 的右语言。
+    /// 所以后缀封闭等价于：每个可达状态的右语言都包含于开始状态的右语言（也就是整个语言）。
+    /// 同时运行两个DFA副本检查包含关系：一对状态 (p, q) 中p接受而q不接受，就说明包含关系不成立。
+    pub fn is_suffix_closed(&self) -> bool {
+        if self.is_partial() {
+            return self.complete_partial().is_suffix_closed();
+        }
+        let start = self.start_state();
+        let mut visited: HashSet<(StateId, StateId)> = self
+            .reachable_states()
+            .into_iter()
+            .map(|state| (state, start))
+            .collect();
+        let mut stack: Vec<(StateId, StateId)> = visited.iter().cloned().collect();
+        while let Some((state, whole)) = stack.pop() {
+            if self.accept_states.contains(&state) && !self.accept_states.contains(&whole) {
+                return false;
+            }
+            for input in self.alphabet.iter() {
+                let next = (self.delta(state, *input), self.delta(whole, *input));
+                if visited.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        true
+    }
+
+    /// 判断语言是否是因子封闭的，也就是每个被接受字符串的每个连续子串也都被接受。
+    ///
+    /// 子串就是前缀的后缀，所以因子封闭等价于同时是前缀封闭和后缀封闭的。
+    pub fn is_factor_closed(&self) -> bool {
+        self.is_prefix_closed() && self.is_suffix_closed()
+    }
+}
+
+/// 编辑距离相关的方法
+impl DenseDFA {
+    /// 把`input`变成某个被接受的字符串最少需要几次单字符的插入、删除或替换。
+    ///
+    /// 在（已读的输入位置, DFA状态）组成的分层图上求最短路：
+    /// 读入和当前状态匹配的输入字符代价为0，替换、删除、插入代价为1。
+    /// 到达 (输入末尾, 接受状态) 时的代价就是答案，所以`input`本身被接受时返回0。
+    /// 不在字母表里的输入字符只能被删除或替换。
+    ///
+    /// 如果DFA不接受任何字符串，返回`usize::MAX`。
+    pub fn edit_distance_to_language(&self, input: &[u8]) -> usize {
+        if self.is_partial() {
+            return self.complete_partial().edit_distance_to_language(input);
+        }
+        self.remaining_edit_costs(input)[0][self.start_state() as usize]
+    }
+
+    /// 返回和`input`编辑距离最小的被接受字符串，DFA不接受任何字符串时返回None。
+    ///
+    /// 编辑距离相同的候选有多个时，返回字典序最小的那个（较短的前缀排在前面），所以结果是确定的。
+    pub fn nearest_accepted(&self, input: &[u8]) -> Option<Vec<u8>> {
+        if self.is_partial() {
+            return self.complete_partial().nearest_accepted(input);
+        }
+        let remaining = self.remaining_edit_costs(input);
+        let best = remaining[0][self.start_state() as usize];
+        if best == usize::MAX {
+            return None;
+        }
+        // 一个节点 (位置, 状态, 已用代价) 还能走到最优解，当且仅当 已用代价 + 剩余代价 <= best。
+        let viable = |position: usize, state: StateId, used: usize| {
+            used.saturating_add(remaining[position][state as usize]) <= best
+        };
+
+        let mut alphabet = self.alphabet.clone();
+        alphabet.sort_unstable();
+
+        // 当前输出前缀可能对应的所有节点，记录每个节点的最小已用代价。
+        let mut frontier: HashMap<(usize, StateId), usize> = HashMap::new();
+        frontier.insert((0, self.start_state()), 0);
+        let mut output = Vec::new();
+        loop {
+            // 删除输入字符不产生输出，先把它们都展开。
+            let mut nodes: Vec<((usize, StateId), usize)> = frontier.drain().collect();
+            let mut index = 0;
+            while index < nodes.len() {
+                let ((position, state), used) = nodes[index];
+                if position < input.len() && viable(position + 1, state, used + 1) {
+                    nodes.push(((position + 1, state), used + 1));
+                }
+                index += 1;
+            }
+
+            // 能在这里结束，就不要再输出更多字符，空的后缀字典序最小。
+            if nodes.iter().any(|((position, state), _)| {
+                *position == input.len() && self.accept_states.contains(state)
+            }) {
+                return Some(output);
+            }
+
+            for symbol in alphabet.iter() {
+                let mut next: HashMap<(usize, StateId), usize> = HashMap::new();
+                let mut add = |position: usize, state: StateId, used: usize| {
+                    if viable(position, state, used) {
+                        let entry = next.entry((position, state)).or_insert(used);
+                        *entry = (*entry).min(used);
+                    }
+                };
+                for ((position, state), used) in nodes.iter() {
+                    let to = self.delta(*state, *symbol);
+                    if *position < input.len() {
+                        let weight = if input[*position] == *symbol { 0 } else { 1 };
+                        add(position + 1, to, used + weight);
+                    }
+                    add(*position, to, used + 1);
+                }
+                if !next.is_empty() {
+                    output.push(*symbol);
+                    frontier = next;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// 对每个输入位置和每个状态，求从这个节点出发走到 (输入末尾, 接受状态) 最少还要多少代价。
+    /// 到不了的记为`usize::MAX`。
+    ///
+    /// 从最后一层往前算。同一层内只有插入边，每条代价为1，
+    /// 所以先用到下一层的代价作为初值，再沿入表反向做一次Dijkstra。
+    fn remaining_edit_costs(&self, input: &[u8]) -> Vec<Vec<usize>> {
+        let n = self.number_of_states() as usize;
+        let mut remaining = vec![vec![usize::MAX; n]; input.len() + 1];
+
+        for position in (0..=input.len()).rev() {
+            let mut layer = vec![usize::MAX; n];
+            for (state, cost) in layer.iter_mut().enumerate() {
+                if position == input.len() {
+                    if self.accept_states.contains(&(state as StateId)) {
+                        *cost = 0;
+                    }
+                    continue;
+                }
+                let next_layer = &remaining[position + 1];
+                // 删除input[position]
+                *cost = next_layer[state].saturating_add(1);
+                for symbol in self.alphabet.iter() {
+                    // 读入或者替换input[position]
+                    let weight = if *symbol == input[position] { 0 } else { 1 };
+                    let to = self.delta(state as StateId, *symbol) as usize;
+                    *cost = (*cost).min(next_layer[to].saturating_add(weight));
+                }
+            }
+
+            let mut heap: BinaryHeap<Reverse<(usize, StateId)>> = layer
+                .iter()
+                .enumerate()
+                .filter(|(_, cost)| **cost != usize::MAX)
+                .map(|(state, cost)| Reverse((*cost, state as StateId)))
+                .collect();
+            while let Some(Reverse((cost, state))) = heap.pop() {
+                if cost > layer[state as usize] {
+                    continue;
+                }
+                // 在前驱状态插入一个字符就能到这里
+                for (from, _) in self.symbols_reaching(state) {
+                    if cost + 1 < layer[from as usize] {
+                        layer[from as usize] = cost + 1;
+                        heap.push(Reverse((cost + 1, from)));
+                    }
+                }
+            }
+            remaining[position] = layer;
+        }
+        remaining
+    }
+}
+
+/// 测试覆盖率相关的方法
+impl DenseDFA {
+    /// 一组输入经过了这个DFA的多大比例的转移。
+    ///
+    /// 只统计从非陷阱状态出发的转移（包括转移到陷阱状态的那些），
+    /// 返回 被经过的转移数 / 非陷阱状态的转移总数。如果除了陷阱状态没有别的状态，返回1.0。
+    /// 部分DFA没有陷阱状态，统计的是所有有定义的转移。
+    pub fn transition_coverage(&self, inputs: &[&str]) -> f64 {
+        let total = self.coverable_transitions().len();
+        if total == 0 {
+            return 1.0;
+        }
+        let covered = self.covered_transitions(inputs).len();
+        covered as f64 / total as f64
+    }
+
+    /// 一组输入没有经过的转移，按状态编号和字母表的顺序排列。
+    pub fn uncovered_transitions(&self, inputs: &[&str]) -> Vec<(StateId, u8)> {
+        let covered = self.covered_transitions(inputs);
+        self.coverable_transitions()
+            .into_iter()
+            .filter(|transition| !covered.contains(transition))
+            .collect()
+    }
+
+    /// 参与覆盖率统计的所有转移，按状态编号和字母表的顺序排列。
+    fn coverable_transitions(&self) -> Vec<(StateId, u8)> {
+        let first = if self.is_partial() { 0 } else { 1 };
+        (first..self.number_of_states())
+            .flat_map(|state| {
+                self.defined_transitions(state)
+                    .map(move |(symbol, _)| (state, symbol))
+            })
+            .collect()
+    }
+
+    /// 运行每个输入，记录经过的非陷阱状态出发的转移。
+    /// 遇到不在字母表中的字符或者没有定义的转移时，这个输入就停止运行。
+    fn covered_transitions(&self, inputs: &[&str]) -> HashSet<(StateId, u8)> {
+        let mut covered = HashSet::new();
+        for input in inputs {
+            let mut state = self.start_state();
+            for byte in input.bytes() {
+                if (state == 0 && !self.is_partial())
+                    || !self.alphabet.contains(&byte)
+                    || !self.is_defined(state, byte)
+                {
+                    break;
+                }
+                covered.insert((state, byte));
+                state = self.delta(state, byte);
+            }
+        }
+        covered
+    }
+}
+
+/// 状态转移图的度数相关的方法
+impl DenseDFA {
+    /// 从`state`出发、不指向陷阱状态的转移的数量。部分DFA统计的是有定义的转移。
+    pub fn out_degree(&self, state: StateId) -> usize {
+        self.defined_transitions(state)
+            .filter(|(_, to)| self.is_partial() || *to != 0)
+            .count()
+    }
+
+    /// 指向`state`的转移的数量，直接从入表中读取。
+    pub fn in_degree(&self, state: StateId) -> usize {
+        self.symbols_reaching(state).len()
+    }
+
+    /// 度数的分布：key是度数（入度 + 出度），value是有这么多度数的状态的数量。
+    ///
+    /// 陷阱状态不参与统计，所以每条非陷阱状态之间的转移正好被计算两次，一次出度一次入度。
+    /// 部分DFA的0号状态是普通状态，也参与统计。
+    pub fn degree_histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        let first = if self.is_partial() { 0 } else { 1 };
+        for state in first..self.number_of_states() {
+            let degree = self.in_degree(state) + self.out_degree(state);
+            *histogram.entry(degree).or_insert(0) += 1;
+        }
+        histogram
+    }
+}
+
+/// 用拓扑排序判断邻接矩阵表示的图中有没有环（包括自环）。
+fn has_cycle(matrix: &[Vec<f64>]) -> bool {
+    let n = matrix.len();
+    let mut in_degree = vec![0; n];
+    for row in matrix.iter() {
+        for (to, count) in row.iter().enumerate() {
+            if *count > 0.0 {
+                in_degree[to] += 1;
+            }
+        }
+    }
+    let mut stack: Vec<usize> = (0..n).filter(|node| in_degree[*node] == 0).collect();
+    let mut removed = 0;
+    while let Some(node) = stack.pop() {
+        removed += 1;
+        for (to, count) in matrix[node].iter().enumerate() {
+            if *count > 0.0 {
+                in_degree[to] -= 1;
+                if in_degree[to] == 0 {
+                    stack.push(to);
+                }
+            }
+        }
+    }
+    removed < n
+}
+
+/// 按照先长度、后字典序的顺序枚举被接受的字符串和状态路径的迭代器。
+///
+/// 对每个长度做一次带剪枝的深度优先搜索，用显式的栈保存搜索的进度，这样每次`next`只需要往前走到下一个字符串。
+struct AcceptedPaths<'a> {
+    dfa: &'a DenseDFA,
+    alphabet: Vec<u8>,
+    /// exact[r]：从这些状态出发，恰好读入r个字符能到达接受状态。
+    exact: Vec<HashSet<StateId>>,
+    /// 当前枚举的长度。
+    len: usize,
+    /// 最近一个有被接受字符串的长度，用于判断语言有限时什么时候停止。
+    last_found: Option<usize>,
+    /// 深度优先搜索的栈，每一项是路径上的状态和下一个要尝试的字符的下标。栈为空表示要开始下一个长度。
+    stack: Vec<(StateId, usize)>,
+    word: Vec<u8>,
+    done: bool,
+}
+
+impl<'a> AcceptedPaths<'a> {
+    fn new(dfa: &'a DenseDFA) -> Self {
+        AcceptedPaths {
+            dfa,
+            alphabet: dfa.alphabet.iter().cloned().sorted().collect(),
+            exact: vec![dfa.accept_states.clone()],
+            len: 0,
+            last_found: None,
+            stack: Vec::new(),
+            word: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// 找到下一个有被接受字符串的长度，并且把开始状态压入栈中。所有字符串都枚举完了就返回false。
+    fn start_next_length(&mut self) -> bool {
+        // 完全DFA的陷阱状态不在任何被接受字符串的路径上，部分DFA没有陷阱状态，相当于多一个状态。
+        let number_of_states =
+            self.dfa.number_of_states() as usize + self.dfa.is_partial() as usize;
+        loop {
+            if self.len >= number_of_states
+                && !matches!(self.last_found, Some(found) if self.len - found < number_of_states)
+            {
+                return false;
+            }
+            while self.exact.len() <= self.len {
+                let last = self.exact.last().unwrap();
+                let next: HashSet<StateId> = (0..self.dfa.number_of_states())
+                    .filter(|state| {
+                        self.dfa
+                            .defined_transitions(*state)
+                            .any(|(_, next)| last.contains(&next))
+                    })
+                    .collect();
+                self.exact.push(next);
+            }
+            if self.exact[self.len].contains(&self.dfa.start_state()) {
+                self.last_found = Some(self.len);
+                self.stack.push((self.dfa.start_state(), 0));
+                return true;
+            }
+            self.len += 1;
+        }
+    }
+
+    /// 弹出栈顶，栈空了就说明这个长度枚举完了。
+    fn pop(&mut self) {
+        self.stack.pop();
+        self.word.pop();
+        if self.stack.is_empty() {
+            self.len += 1;
+        }
+    }
+}
+
+impl Iterator for AcceptedPaths<'_> {
+    type Item = (Vec<u8>, Vec<StateId>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.done {
+                return None;
+            }
+            if self.stack.is_empty() && !self.start_next_length() {
+                self.done = true;
+                return None;
+            }
+            let remaining = self.len - self.word.len();
+            if remaining == 0 {
+                let item = (
+                    self.word.clone(),
+                    self.stack.iter().map(|(state, _)| *state).collect(),
+                );
+                self.pop();
+                return Some(item);
+            }
+
+            let (state, next_index) = *self.stack.last().unwrap();
+            let found = (next_index..self.alphabet.len()).find(|index| {
+                let input = self.alphabet[*index];
+                self.dfa.is_defined(state, input)
+                    && self.exact[remaining - 1].contains(&self.dfa.delta(state, input))
+            });
+            match found {
+                Some(index) => {
+                    let input = self.alphabet[index];
+                    self.stack.last_mut().unwrap().1 = index + 1;
+                    self.stack.push((self.dfa.delta(state, input), 0));
+                    self.word.push(input);
+                }
+                None => self.pop(),
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt::Write;
+
+use super::{CompletedDfa, DenseDFA};
+
+impl DenseDFA {
+    /// 把这个DFA生成为一段独立的rust源代码，其中包含常量形式的状态转移表和一个识别函数：
+    ///
+    /// ```text
+    /// pub fn <name>_accepts(input: &[u8]) -> bool
+    /// ```
+    ///
+    /// 生成的代码不依赖本crate，可以直接粘贴到别的程序里使用。
+    /// 常量的名字是`name`的大写形式加上后缀，所以`name`应该是一个合法的snake_case标识符。
+    /// 部分DFA先用`complete_partial`补全，状态编号会加一。
+    pub fn to_rust_source(&self, name: &str) -> String {
+        if self.is_partial() {
+            return self.complete_partial().to_rust_source(name);
+        }
+        let upper = name.to_ascii_uppercase();
+        let number_of_states = self.number_of_states() as usize;
+        let stride = self.alphabet.len();
+        let mut source = String::new();
+
+        // 输入字节到字母表索引的映射，u16::MAX 表示这个字节不在字母表中。
+        let mut classes = [u16::MAX; 256];
+        for (index, input) in self.alphabet.iter().enumerate() {
+            classes[*input as usize] = index as u16;
+        }
+
+        writeln!(
+            source,
+            "// Generated from a DFA with {} states over the alphabet {:?}.",
+            number_of_states,
+            String::from_utf8_lossy(&self.alphabet)
+        )
+        .unwrap();
+        writeln!(source).unwrap();
+
+        writeln!(source, "const {}_CLASSES: [u16; 256] = [", upper).unwrap();
+        for row in classes.chunks(16) {
+            let row: Vec<String> = row.iter().map(|class| class.to_string()).collect();
+            writeln!(source, "    {},", row.join(", ")).unwrap();
+        }
+        writeln!(source, "];").unwrap();
+        writeln!(source).unwrap();
+
+        writeln!(
+            source,
+            "const {}_TRANSITIONS: [[usize; {}]; {}] = [",
+            upper, stride, number_of_states
+        )
+        .unwrap();
+        for from in 0..number_of_states {
+            let row: Vec<String> = self
+                .alphabet
+                .iter()
+                .map(|input| self.delta(from as u128, *input).to_string())
+                .collect();
+            writeln!(source, "    [{}],", row.join(", ")).unwrap();
+        }
+        writeln!(source, "];").unwrap();
+        writeln!(source).unwrap();
+
+        let accept: Vec<String> = (0..number_of_states)
+            .map(|state| self.accept_states.contains(&(state as u128)).to_string())
+            .collect();
+        writeln!(
+            source,
+            "const {}_ACCEPT: [bool; {}] = [{}];",
+            upper,
+            number_of_states,
+            accept.join(", ")
+        )
+        .unwrap();
+        writeln!(
+            source,
+            "const {}_START: usize = {};",
+            upper,
+            self.start_state()
+        )
+        .unwrap();
+        writeln!(source).unwrap();
+
+        writeln!(source, "pub fn {}_accepts(input: &[u8]) -> bool {{", name).unwrap();
+        writeln!(source, "    let mut state = {}_START;", upper).unwrap();
+        writeln!(source, "    for byte in input {{").unwrap();
+        writeln!(
+            source,
+            "        let class = {}_CLASSES[*byte as usize];",
+            upper
+        )
+        .unwrap();
+        writeln!(source, "        if class == u16::MAX {{").unwrap();
+        writeln!(source, "            return false;").unwrap();
+        writeln!(source, "        }}").unwrap();
+        writeln!(
+            source,
+            "        state = {}_TRANSITIONS[state][class as usize];",
+            upper
+        )
+        .unwrap();
+        writeln!(source, "    }}").unwrap();
+        writeln!(source, "    {}_ACCEPT[state]", upper).unwrap();
+        writeln!(source, "}}").unwrap();
+        source
+    }
+}
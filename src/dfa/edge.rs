@@ -31,7 +31,7 @@ impl DfaAsEdges {
         self.trans
             .iter()
             .enumerate()
-            .filter(|(_, e)| f(*e, (from, to)))
+            .filter(|(_, e)| f(e, (from, to)))
             .map(|(i, _)| i)
             .collect()
     }
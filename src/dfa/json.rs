@@ -0,0 +1,271 @@
+use super::{CompletedDfa, DenseDFA};
+
+type StateId = u128;
+
+/// `from_json_compact`最多接受的状态数。转移表按状态数预先分配，不限制的话一个很大的数就会耗尽内存。
+const MAX_STATES: StateId = 1 << 16;
+
+/// 紧凑的JSON格式
+///
+/// ```text
+/// {"alphabet":["0","1"],"states":3,"start":1,"accept":[2],"transitions":[[1,"0",2],[2,"1",2]]}
+/// ```
+///
+/// 和dot、表格、正则文法不同，这个格式不关心怎么画，网页前端可以用任意的JS图形库自己绘制。
+/// 和`to_dot`一样，陷阱状态和指向它的转移都不输出，读入时缺少的转移都指向陷阱状态。
+impl DenseDFA {
+    /// 输出紧凑的JSON。字母表中的每个字符都是一个单字符的字符串，转移是`[from, 字符, to]`的三元组。
+    /// 部分DFA先用`complete_partial`补全，状态编号会加一。
+    pub fn to_json_compact(&self) -> String {
+        if self.is_partial() {
+            return self.complete_partial().to_json_compact();
+        }
+        let mut transitions = Vec::new();
+        for from in 1..self.number_of_states() {
+            for input in self.alphabet.iter() {
+                let to = self.delta(from, *input);
+                if to != 0 {
+                    transitions.push(format!("[{},{},{}]", from, json_symbol(*input), to));
+                }
+            }
+        }
+        format!(
+            "{{\"alphabet\":[{}],\"states\":{},\"start\":{},\"accept\":[{}],\"transitions\":[{}]}}",
+            self.alphabet
+                .iter()
+                .map(|input| json_symbol(*input))
+                .collect::<Vec<_>>()
+                .join(","),
+            self.number_of_states(),
+            self.start_state(),
+            self.accept_states_sorted()
+                .iter()
+                .map(|state| state.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+            transitions.join(",")
+        )
+    }
+
+    /// 读入`to_json_compact`输出的JSON。
+    ///
+    /// 格式不对、字符不是单个字节、状态id越界、同一个状态在同一个字符上有两个转移时返回错误。
+    /// 0号状态是陷阱状态，它是接受状态或者有转移时也返回错误；状态数超过65536时同样返回错误。
+    pub fn from_json_compact(json: &str) -> Result<DenseDFA, String> {
+        let mut parser = Parser {
+            input: json.as_bytes(),
+            pos: 0,
+        };
+        let mut alphabet: Option<Vec<u8>> = None;
+        let mut states: Option<StateId> = None;
+        let mut start: Option<StateId> = None;
+        let mut accept: Option<Vec<StateId>> = None;
+        let mut transitions: Option<Vec<(StateId, u8, StateId)>> = None;
+
+        parser.expect(b'{')?;
+        loop {
+            let key = parser.string()?;
+            parser.expect(b':')?;
+            match key.as_str() {
+                "alphabet" => alphabet = Some(parser.list(|parser| parser.symbol())?),
+                "states" => states = Some(parser.number()?),
+                "start" => start = Some(parser.number()?),
+                "accept" => accept = Some(parser.list(|parser| parser.number())?),
+                "transitions" => {
+                    transitions = Some(parser.list(|parser| {
+                        parser.expect(b'[')?;
+                        let from = parser.number()?;
+                        parser.expect(b',')?;
+                        let input = parser.symbol()?;
+                        parser.expect(b',')?;
+                        let to = parser.number()?;
+                        parser.expect(b']')?;
+                        Ok((from, input, to))
+                    })?)
+                }
+                _ => return Err(format!("unknown key \"{}\"", key)),
+            }
+            if !parser.eat(b',') {
+                break;
+            }
+        }
+        parser.expect(b'}')?;
+        parser.skip_whitespace();
+        if parser.pos != parser.input.len() {
+            return Err(format!("unexpected trailing input at {}", parser.pos));
+        }
+
+        let missing = |key: &str| format!("missing key \"{}\"", key);
+        let alphabet = alphabet.ok_or_else(|| missing("alphabet"))?;
+        let states = states.ok_or_else(|| missing("states"))?;
+        let start = start.ok_or_else(|| missing("start"))?;
+        let accept = accept.ok_or_else(|| missing("accept"))?;
+        let transitions = transitions.ok_or_else(|| missing("transitions"))?;
+
+        let check = |state: StateId| {
+            if state < states {
+                Ok(())
+            } else {
+                Err(format!("state {} is out of range (0..{})", state, states))
+            }
+        };
+        if states > MAX_STATES {
+            return Err(format!(
+                "too many states: {}, at most {} are supported",
+                states, MAX_STATES
+            ));
+        }
+        check(start)?;
+        accept.iter().try_for_each(|state| check(*state))?;
+        if accept.contains(&0) {
+            return Err("state 0 is the trap state and cannot be accepting".to_string());
+        }
+        let mut table = vec![vec![0; alphabet.len()]; states as usize];
+        for (from, input, to) in transitions {
+            check(from)?;
+            check(to)?;
+            if from == 0 {
+                return Err("state 0 is the trap state and cannot have transitions".to_string());
+            }
+            let index = alphabet
+                .iter()
+                .position(|symbol| *symbol == input)
+                .ok_or_else(|| format!("symbol '{}' is not in the alphabet", input as char))?;
+            let slot = &mut table[from as usize][index];
+            if *slot != 0 {
+                return Err(format!(
+                    "state {} has two transitions on '{}'",
+                    from, input as char
+                ));
+            }
+            *slot = to;
+        }
+        Ok(DenseDFA::from_table(alphabet, &table, start, &accept))
+    }
+}
+
+/// 把一个字符写成JSON字符串。可打印的ASCII字符原样输出，其他字节用`\uXXXX`转义。
+fn json_symbol(input: u8) -> String {
+    match input {
+        b'"' => "\"\\\"\"".to_string(),
+        b'\\' => "\"\\\\\"".to_string(),
+        0x20..=0x7e => format!("\"{}\"", input as char),
+        _ => format!("\"\\u{:04x}\"", input),
+    }
+}
+
+/// 只能读`to_json_compact`用到的那一小部分JSON：对象、数组、非负整数和字符串。
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.input.len() && self.input[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    /// 如果下一个字符是`byte`，就跳过它并返回true。
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        if self.input.get(self.pos) == Some(&byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}' at {}", byte as char, self.pos))
+        }
+    }
+
+    fn number(&mut self) -> Result<StateId, String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.pos < self.input.len() && self.input[self.pos].is_ascii_digit() {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.pos])
+            .unwrap()
+            .parse()
+            .map_err(|_| format!("expected a number at {}", start))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let byte = *self
+                .input
+                .get(self.pos)
+                .ok_or_else(|| "unterminated string".to_string())?;
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = *self
+                        .input
+                        .get(self.pos)
+                        .ok_or_else(|| "unterminated string".to_string())?;
+                    self.pos += 1;
+                    match escaped {
+                        b'"' | b'\\' | b'/' => bytes.push(escaped),
+                        b'u' => {
+                            let hex = self
+                                .input
+                                .get(self.pos..self.pos + 4)
+                                .and_then(|hex| std::str::from_utf8(hex).ok())
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| format!("invalid \\u escape at {}", self.pos))?;
+                            self.pos += 4;
+                            let mut buffer = [0; 4];
+                            bytes.extend_from_slice(hex.encode_utf8(&mut buffer).as_bytes());
+                        }
+                        _ => return Err(format!("unsupported escape at {}", self.pos - 1)),
+                    }
+                }
+                _ => bytes.push(byte),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| "string is not valid UTF-8".to_string())
+    }
+
+    /// 读一个单字节字符的字符串。`\u0080`到`\u00ff`表示对应的字节。
+    fn symbol(&mut self) -> Result<u8, String> {
+        let start = self.pos;
+        let symbol = self.string()?;
+        let mut chars = symbol.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if (c as u32) < 256 => Ok(c as u32 as u8),
+            _ => Err(format!("expected a single-byte symbol at {}", start)),
+        }
+    }
+
+    /// 读一个数组，每个元素用`element`读。
+    fn list<T>(
+        &mut self,
+        mut element: impl FnMut(&mut Self) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        if self.eat(b']') {
+            return Ok(items);
+        }
+        loop {
+            items.push(element(self)?);
+            if !self.eat(b',') {
+                break;
+            }
+        }
+        self.expect(b']')?;
+        Ok(items)
+    }
+}
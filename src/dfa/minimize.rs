@@ -1,6 +1,9 @@
-use std::collections::{HashSet, HashMap};
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+use std::fmt;
 
-use crate::dfa::Alphabet;
+use itertools::Itertools;
+
+use crate::dfa::{Alphabet, CompletedDfa, DenseDFA};
 
 type StateId = u128;
 
@@ -46,7 +49,7 @@ pub fn compute_indistin_state_groups(dfa: &impl super::CompletedDfa) -> Indistin
             }
         }
     }
-    
+
     let mut groups = IndistinGroups { groups: Vec::new() };
     distin_table.for_each(|state1, state2, pair| {
         if !pair.distinguishable {
@@ -71,7 +74,6 @@ pub struct IndistinGroups {
 }
 
 impl IndistinGroups {
-
     fn insert_pair(&mut self, state1: StateId, state2: StateId) {
         let (state1, state2) = order_pair(state1, state2);
         let group_index1 = self.groups.iter().position(|group| group.contains(&state1));
@@ -79,9 +81,7 @@ impl IndistinGroups {
 
         if let Some(index1) = group_index1 {
             if let Some(index2) = group_index2 {
-                if index1 == index2 {
-                    return;
-                } else {
+                if index1 != index2 {
                     let mut group1 = self.groups.remove(index1);
                     let group2 = self.groups.remove(index2);
                     group1.extend(group2);
@@ -127,9 +127,9 @@ impl IndistinGroups {
     }
 
     /// 重映射状态id。
-    /// 
+    ///
     /// 假设↓指向一组不可区分状态，⇓指向另一组不可区分状态，x代表一个状态，
-    /// 
+    ///
     /// 在remap之前，状态列表的分布是：
     /// ```text
     ///  ↓    ↓ ↓         ⇓  ⇓
@@ -141,76 +141,93 @@ impl IndistinGroups {
     /// xxxxxxxxxxxxxxxxxxxxx
     /// ```
     /// 返回值是remap前后的状态id的映射。
+    ///
+    /// 0号陷阱状态和别的死状态不可区分时，它所在的组整体映射到0号，保证结果的0号状态仍然是陷阱状态。
+    /// 这时可区分的状态从1开始编号。
     pub fn remap(&self, max_len: StateId) -> HashMap<StateId, StateId> {
         let mut id_map = HashMap::new();
-        let mut new_id: StateId = 0;
-        let number_of_distin = max_len - self.num_of_indistin_states() as StateId;
+        let trap_group = self.contains_at(0);
+        let mut new_id: StateId = if trap_group.is_some() { 1 } else { 0 };
+        let number_of_distin = max_len - self.num_of_indistin_states() as StateId + new_id;
+        // 陷阱状态所在的组之后的组往前挪一个位置
+        let group_offset = |group_id: usize| match trap_group {
+            Some(trap_group) if group_id > trap_group => group_id as StateId - 1,
+            _ => group_id as StateId,
+        };
 
         for old_id in 0..max_len {
-            if let Some(group_id) = self.contains_at(old_id) {
-                id_map.insert(old_id, number_of_distin + group_id as StateId);
-            } else {
-                id_map.insert(old_id, new_id);
-                new_id += 1;
+            match self.contains_at(old_id) {
+                Some(group_id) if Some(group_id) == trap_group => {
+                    id_map.insert(old_id, 0);
+                }
+                Some(group_id) => {
+                    id_map.insert(old_id, number_of_distin + group_offset(group_id));
+                }
+                None => {
+                    id_map.insert(old_id, new_id);
+                    new_id += 1;
+                }
             }
         }
 
         // 执行完成后，new_id应该等于可区分状态数量。
         assert_eq!(new_id, number_of_distin);
-        
+
         id_map
     }
 }
 
-/// 二维数组实现的可区分状态表，包括状态对关联表。
+/// 可区分状态表，包括状态对关联表。
+///
+/// 只保存 state1 < state2 的状态对，按行展开存在一个一维数组里：
+/// 第 i 行是 (i, i+1), (i, i+2), ..., (i, n-1)，共 n-1-i 个。
+/// 和 n-1 行 n 列的二维数组相比，内存少了一半多，而且每次访问只需要一次寻址。
 struct PairTable {
-    table: Vec<Vec<StatePair>>,
+    table: Vec<StatePair>,
+    state_num: usize,
 }
 
 impl PairTable {
     fn new(state_num: usize) -> Self {
-        let mut table = Vec::new();
-        for _ in 0..state_num - 1 {
-            let mut row = Vec::new();
-            for _ in 0..state_num {
-                row.push(StatePair::new());
-            }
-            table.push(row);
-        }
-        Self { table }
+        let len = state_num * state_num.saturating_sub(1) / 2;
+        let mut table = Vec::with_capacity(len);
+        table.resize_with(len, StatePair::new);
+        Self { table, state_num }
     }
-    fn get(&mut self, state1: StateId, state2: StateId) -> &mut StatePair {
+    /// 状态对在一维数组中的下标。前 i 行一共有 i*(2n-i-1)/2 个状态对。
+    fn index(&self, state1: StateId, state2: StateId) -> usize {
         let (state1, state2) = order_pair(state1, state2);
-        &mut self.table[state1 as usize][state2 as usize]
+        let (i, j) = (state1 as usize, state2 as usize);
+        debug_assert!(i < j && j < self.state_num);
+        i * (2 * self.state_num - i - 1) / 2 + (j - i - 1)
+    }
+    fn get(&mut self, state1: StateId, state2: StateId) -> &mut StatePair {
+        let index = self.index(state1, state2);
+        &mut self.table[index]
     }
     fn is_distinguishable(&self, state1: StateId, state2: StateId) -> bool {
-        let (state1, state2) = order_pair(state1, state2);
-        self.table[state1 as usize][state2 as usize].distinguishable
+        self.table[self.index(state1, state2)].distinguishable
     }
+    /// 标记一对状态为可区分的，并且沿着关联表把所有关联的状态对也标记为可区分的。
+    ///
+    /// 关联链可能和状态数一样长，所以用显式的栈代替递归，否则大的DFA会栈溢出。
+    /// 每个状态对的关联表在标记时被取走，所以每条关联只会处理一次，不会无限循环。
     fn distinguish(&mut self, state1: StateId, state2: StateId) {
-        let (state1, state2) = order_pair(state1, state2);
-        self.table[state1 as usize][state2 as usize].distinguishable = true;
-
-        if self.get(state1, state2).associated.is_empty() {
-            return;
-        }
-
-        let associated_pairs = std::mem::replace(&mut self.get(state1, state2).associated, vec![]);
-
-        for (s1, s2) in associated_pairs.into_iter() {
-            self.distinguish(s1, s2);
-            // 注意，这是一个递归，需要特别小心检查是否会无限递归。
+        let mut stack = vec![(state1, state2)];
+        while let Some((s1, s2)) = stack.pop() {
+            let pair = self.get(s1, s2);
+            pair.distinguishable = true;
+            stack.append(&mut pair.associated);
         }
     }
 
     fn for_each(&self, mut f: impl FnMut(StateId, StateId, &StatePair)) {
-        for state1 in 0..self.table.len() - 1 {
-            for state2 in state1 + 1..self.table.len() {
-                f(
-                    state1 as StateId,
-                    state2 as StateId,
-                    &self.table[state1][state2],
-                );
+        // 按行展开的顺序遍历，下标正好依次加一。
+        let mut pairs = self.table.iter();
+        for state1 in 0..self.state_num {
+            for state2 in state1 + 1..self.state_num {
+                let pair = pairs.next().expect("table has n*(n-1)/2 pairs");
+                f(state1 as StateId, state2 as StateId, pair);
             }
         }
     }
@@ -233,3 +250,622 @@ impl StatePair {
         self.associated.push((state1, state2));
     }
 }
+
+/// 传给`DenseDFA::quotient_by`的划分不合法时返回的错误。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartitionError {
+    /// 这个状态不在DFA中。
+    UnknownState(StateId),
+    /// 这个状态在划分中出现了不止一次。
+    DuplicateState(StateId),
+    /// 这个状态没有出现在划分中。
+    MissingState(StateId),
+    /// 接受状态`accepting`和非接受状态`rejecting`在同一块中。合并它们会改变语言。
+    MixedAcceptance {
+        accepting: StateId,
+        rejecting: StateId,
+    },
+}
+
+impl fmt::Display for PartitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartitionError::UnknownState(id) => write!(f, "no such a state: {}", id),
+            PartitionError::DuplicateState(id) => {
+                write!(f, "state {} appears in the partition more than once", id)
+            }
+            PartitionError::MissingState(id) => {
+                write!(f, "state {} is not covered by the partition", id)
+            }
+            PartitionError::MixedAcceptance {
+                accepting,
+                rejecting,
+            } => write!(
+                f,
+                "accepting state {} and rejecting state {} are in the same block",
+                accepting, rejecting
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PartitionError {}
+
+impl DenseDFA {
+    /// 从给定的初始划分出发，求商DFA。
+    ///
+    /// 先把给定的划分不断细化，直到得到最粗的稳定划分：同一块中的状态读入同一个字符后，仍然到达同一块。
+    /// 然后把每一块合并成一个状态。
+    ///
+    /// `minimize`相当于用“接受状态/非接受状态”作为初始划分调用本方法。
+    /// 给出更细的划分（比如按接受状态的标签分开），就可以求DFA关于某个同余关系的商。
+    ///
+    /// 划分必须恰好覆盖每个状态一次，否则返回错误。包含0号状态的块在商DFA中仍然是0号状态。
+    /// 一块中不能同时有接受状态和非接受状态，否则商DFA的语言会改变，返回`MixedAcceptance`。
+    /// 部分DFA先用`complete_partial`补全，新增的陷阱状态单独成一块。
+    pub fn quotient_by(&self, partition: &[Vec<StateId>]) -> Result<DenseDFA, PartitionError> {
+        if self.is_partial() {
+            if let Some(state) = partition
+                .iter()
+                .flatten()
+                .find(|state| **state >= self.number_of_states())
+            {
+                return Err(PartitionError::UnknownState(*state));
+            }
+            let mut shifted = vec![vec![0]];
+            shifted.extend(
+                partition
+                    .iter()
+                    .map(|block| block.iter().map(|state| state + 1).collect::<Vec<_>>()),
+            );
+            return self
+                .complete_partial()
+                .quotient_by(&shifted)
+                .map_err(|error| match error {
+                    PartitionError::DuplicateState(state) => {
+                        PartitionError::DuplicateState(state - 1)
+                    }
+                    PartitionError::MissingState(state) => PartitionError::MissingState(state - 1),
+                    PartitionError::MixedAcceptance {
+                        accepting,
+                        rejecting,
+                    } => PartitionError::MixedAcceptance {
+                        accepting: accepting - 1,
+                        rejecting: rejecting - 1,
+                    },
+                    unknown => unknown,
+                });
+        }
+        let number_of_states = self.number_of_states() as usize;
+        let mut block_of = vec![None; number_of_states];
+        for (block, states) in partition.iter().enumerate() {
+            for state in states {
+                match block_of.get_mut(*state as usize) {
+                    None => return Err(PartitionError::UnknownState(*state)),
+                    Some(Some(_)) => return Err(PartitionError::DuplicateState(*state)),
+                    Some(slot) => *slot = Some(block),
+                }
+            }
+        }
+        if let Some(state) = block_of.iter().position(|block| block.is_none()) {
+            return Err(PartitionError::MissingState(state as StateId));
+        }
+        for states in partition.iter() {
+            let (accepting, rejecting): (Vec<StateId>, Vec<StateId>) = states
+                .iter()
+                .partition(|state| self.accept_states.contains(state));
+            if let (Some(accepting), Some(rejecting)) = (accepting.first(), rejecting.first()) {
+                return Err(PartitionError::MixedAcceptance {
+                    accepting: *accepting,
+                    rejecting: *rejecting,
+                });
+            }
+        }
+        let mut block_of: Vec<usize> = block_of.into_iter().map(|block| block.unwrap()).collect();
+
+        // 不断细化划分：两个状态留在同一块中，当且仅当它们现在在同一块，并且读入每个字符后都到达同一块。
+        // 新块的编号按照块中最小状态的顺序分配，所以0号状态所在的块总是0号块。
+        let mut number_of_blocks = 0;
+        loop {
+            let mut signatures: HashMap<Vec<usize>, usize> = HashMap::new();
+            let mut new_block_of = Vec::with_capacity(number_of_states);
+            for state in 0..number_of_states {
+                let mut signature = vec![block_of[state]];
+                for input in self.alphabet.iter() {
+                    signature.push(block_of[self.delta(state as StateId, *input) as usize]);
+                }
+                let next_block = signatures.len();
+                new_block_of.push(*signatures.entry(signature).or_insert(next_block));
+            }
+            block_of = new_block_of;
+            if signatures.len() == number_of_blocks {
+                break;
+            }
+            number_of_blocks = signatures.len();
+        }
+
+        let mut table = vec![Vec::new(); number_of_blocks];
+        for state in 0..number_of_states {
+            if table[block_of[state]].is_empty() {
+                table[block_of[state]] = self
+                    .alphabet
+                    .iter()
+                    .map(|input| block_of[self.delta(state as StateId, *input) as usize] as StateId)
+                    .collect();
+            }
+        }
+        let accept_states: Vec<StateId> = self
+            .accept_states
+            .iter()
+            .map(|state| block_of[*state as usize] as StateId)
+            .collect();
+
+        Ok(DenseDFA::from_table(
+            self.alphabet.clone(),
+            &table,
+            block_of[self.start_state() as usize] as StateId,
+            &accept_states,
+        ))
+    }
+
+    /// 按照Myhill-Nerode等价关系把所有状态分成等价类，也就是极小化时会合并成一个状态的那些状态。
+    ///
+    /// 每个等价类中的状态从小到大排列，等价类之间按最小的状态排列。不可达状态也会被分到某个等价类中。
+    pub fn equivalence_classes(&self) -> Vec<Vec<StateId>> {
+        if self.is_partial() {
+            // 在补全的DFA上计算，再去掉新增的陷阱状态，把编号换回来
+            let mut classes: Vec<Vec<StateId>> = self
+                .complete_partial()
+                .equivalence_classes()
+                .into_iter()
+                .map(|class| {
+                    class
+                        .into_iter()
+                        .filter(|state| *state != 0)
+                        .map(|state| state - 1)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|class| !class.is_empty())
+                .collect();
+            classes.sort();
+            return classes;
+        }
+        let groups = compute_indistin_state_groups(self);
+        let mut classes: Vec<Vec<StateId>> = groups
+            .iter()
+            .map(|group| group.iter().cloned().sorted().collect())
+            .collect();
+        for state in 0..self.number_of_states() {
+            if groups.contains_at(state).is_none() {
+                classes.push(vec![state]);
+            }
+        }
+        classes.sort();
+        classes
+    }
+
+    /// 每个状态的“未来”（右语言）的指纹，下标是状态id。
+    ///
+    /// 两个状态的指纹相同当且仅当它们Myhill-Nerode等价，也就是右语言相同。
+    /// 指纹就是状态所在等价类中最小的状态id，所以同一个DFA上的结果是确定的。
+    /// 只在同一个DFA内部可以比较，不同DFA的指纹没有关系。界面上可以按指纹给状态着色。
+    pub fn congruence_classes_by_future(&self) -> Vec<u64> {
+        let mut fingerprints = vec![0; self.number_of_states() as usize];
+        for class in self.equivalence_classes() {
+            for state in class.iter() {
+                fingerprints[*state as usize] = class[0] as u64;
+            }
+        }
+        fingerprints
+    }
+
+    /// 一个状态的右语言的指纹，见`congruence_classes_by_future`。
+    ///
+    /// 每次调用都要重新计算整个划分，需要所有状态的指纹时应该直接用`congruence_classes_by_future`。
+    pub fn right_language_fingerprint(&self, state: StateId) -> u64 {
+        assert!(
+            state < self.number_of_states(),
+            "no such a state: {}",
+            state
+        );
+        self.congruence_classes_by_future()[state as usize]
+    }
+}
+
+impl DenseDFA {
+    /// 合并转移完全相同的状态：接受与否相同，并且读入每个字符后到达的状态也相同。
+    ///
+    /// 合并之后可能又出现新的相同的行，所以重复合并直到不再变化。
+    /// 这只是按结构去重，比完整的极小化便宜得多，但不保证结果是极小的。
+    /// 新状态的编号按照其中最小的原状态的顺序分配，所以陷阱状态仍然是0号状态。
+    /// 部分DFA先用`complete_partial`补全。
+    pub fn fold_identical_states(&self) -> DenseDFA {
+        if self.is_partial() {
+            return self.complete_partial().fold_identical_states();
+        }
+        let number_of_states = self.number_of_states() as usize;
+        let mut block_of: Vec<usize> = (0..number_of_states).collect();
+        let mut number_of_blocks = number_of_states;
+        loop {
+            let mut rows: HashMap<(bool, Vec<usize>), usize> = HashMap::new();
+            let mut new_block_of = Vec::with_capacity(number_of_states);
+            for state in 0..number_of_states {
+                let row = self
+                    .alphabet
+                    .iter()
+                    .map(|input| block_of[self.delta(state as StateId, *input) as usize])
+                    .collect();
+                let is_accept = self.accept_states.contains(&(state as StateId));
+                let next_block = rows.len();
+                new_block_of.push(*rows.entry((is_accept, row)).or_insert(next_block));
+            }
+            block_of = new_block_of;
+            if rows.len() == number_of_blocks {
+                break;
+            }
+            number_of_blocks = rows.len();
+        }
+
+        let mut table = vec![Vec::new(); number_of_blocks];
+        for state in 0..number_of_states {
+            if table[block_of[state]].is_empty() {
+                table[block_of[state]] = self
+                    .alphabet
+                    .iter()
+                    .map(|input| block_of[self.delta(state as StateId, *input) as usize] as StateId)
+                    .collect();
+            }
+        }
+        let accept_states: Vec<StateId> = self
+            .accept_states
+            .iter()
+            .map(|state| block_of[*state as usize] as StateId)
+            .collect();
+
+        DenseDFA::from_table(
+            self.alphabet.clone(),
+            &table,
+            block_of[self.start_state() as usize] as StateId,
+            &accept_states,
+        )
+    }
+}
+
+/// 规范化
+///
+/// 这个crate里的稠密DFA总是完全的：每个状态在每个字符上都有转移，缺少的转移用0号陷阱状态补上，
+/// 所以“补全”这一步不需要单独的操作，只要保证所有死状态都并入0号陷阱状态就行了。
+impl DenseDFA {
+    /// 删去无用的状态：不可达的状态直接删掉，不能到达接受状态的“死”状态都并入0号陷阱状态。
+    ///
+    /// 其他状态按原来的顺序重新编号。如果开始状态本身就是死状态，语言是空的，
+    /// 返回只有一个状态的DFA：0号陷阱状态同时也是开始状态。部分DFA先用`complete_partial`补全。
+    pub fn trim(&self) -> DenseDFA {
+        if self.is_partial() {
+            return self.complete_partial().trim();
+        }
+        let coreachable = self.coreachable_states();
+        let mut live: Vec<StateId> = self
+            .reachable_states()
+            .into_iter()
+            .filter(|state| *state != 0 && coreachable.contains(state))
+            .collect();
+        live.sort_unstable();
+        if !live.contains(&self.start_state()) {
+            let table = vec![vec![0; self.alphabet.len()]];
+            return DenseDFA::from_table(self.alphabet.clone(), &table, 0, &[]);
+        }
+
+        let new_id: HashMap<StateId, StateId> = live
+            .iter()
+            .enumerate()
+            .map(|(index, state)| (*state, index as StateId + 1))
+            .collect();
+        let mut table = vec![vec![0; self.alphabet.len()]];
+        for state in live.iter() {
+            table.push(
+                self.alphabet
+                    .iter()
+                    .map(|input| *new_id.get(&self.delta(*state, *input)).unwrap_or(&0))
+                    .collect(),
+            );
+        }
+        let accept_states: Vec<StateId> = self
+            .accept_states
+            .iter()
+            .filter_map(|state| new_id.get(state).cloned())
+            .collect();
+        DenseDFA::from_table(
+            self.alphabet.clone(),
+            &table,
+            new_id[&self.start_state()],
+            &accept_states,
+        )
+    }
+
+    /// 把陷阱状态变成一个普通的状态：新增一个非接受的、所有转移都指向自己的“死”状态，
+    /// 原来指向0号状态的转移都改为指向它。
+    ///
+    /// 0号状态在这个crate里表示“没有转移”，`to_dot`等输出都不显示它，所以平常的DFA看起来是部分DFA。
+    /// 变换之后0号状态不可达，死状态会出现在状态图和表格中，得到教科书上的完全DFA。
+    /// 如果可达的状态都没有指向0号状态的转移，不需要死状态，返回一份拷贝。
+    /// 部分DFA先用`complete_partial`补全，没有定义的转移也都指向死状态。
+    pub fn materialize_trap(&self) -> DenseDFA {
+        if self.is_partial() {
+            return self.complete_partial().materialize_trap();
+        }
+        let reachable = self.reachable_states();
+        let needs_trap = reachable.iter().any(|state| {
+            *state == 0
+                || self
+                    .alphabet
+                    .iter()
+                    .any(|input| self.delta(*state, *input) == 0)
+        });
+        if !needs_trap {
+            return self.clone();
+        }
+
+        let dead = self.number_of_states();
+        let redirect = |to: StateId| if to == 0 { dead } else { to };
+        let mut table: Vec<Vec<StateId>> = vec![vec![0; self.alphabet.len()]];
+        for state in 1..self.number_of_states() {
+            table.push(
+                self.alphabet
+                    .iter()
+                    .map(|input| redirect(self.delta(state, *input)))
+                    .collect(),
+            );
+        }
+        table.push(vec![dead; self.alphabet.len()]);
+        DenseDFA::from_table(
+            self.alphabet.clone(),
+            &table,
+            redirect(self.start_state()),
+            &self.accept_states_sorted(),
+        )
+    }
+
+    /// 极小化，并且选择是否保留死状态。
+    ///
+    /// `keep_trap`为false时和`minimize`一样，得到“部分”极小DFA：死状态都并入0号状态，也就是没有转移。
+    /// 为true时再用`materialize_trap`把死状态变成普通状态，得到“完全”极小DFA，
+    /// 需要死状态的语言（比如`0(0|1)*`）会多一个状态。不同的教科书用的是不同的那一种。
+    pub fn minimize_opts(&self, keep_trap: bool) -> DenseDFA {
+        let minimized = self.minimize();
+        if keep_trap {
+            minimized.materialize_trap()
+        } else {
+            minimized
+        }
+    }
+
+    /// 按接受状态的标签极小化，例如词法分析器中每个接受状态对应一种记号。
+    ///
+    /// `minimize`的初始划分只有“接受/非接受”两块，这里把接受状态再按`labels`中的标签分开，
+    /// 标签不同的接受状态永远不会被合并；没有标签的接受状态算作同一种。非接受状态的标签被忽略。
+    /// 结果在“标签也要相同”的意义下是极小的。不可达状态被删掉，死状态并入0号陷阱状态。
+    ///
+    /// 状态会被重新编号，要得到新DFA中每个接受状态的标签，可以从两个开始状态出发同时运行两个DFA。
+    /// 部分DFA先用`complete_partial`补全，`labels`仍然按原来的状态编号给出。
+    pub fn minimize_by_accept_labels(&self, labels: &HashMap<StateId, u32>) -> DenseDFA {
+        if self.is_partial() {
+            let shifted: HashMap<StateId, u32> = labels
+                .iter()
+                .map(|(state, label)| (state + 1, *label))
+                .collect();
+            return self.complete_partial().minimize_by_accept_labels(&shifted);
+        }
+        let (reachable, id_map) = match self.trim_unreachable() {
+            Some((trimmed, id_map)) => (trimmed, id_map),
+            None => (
+                self.clone(),
+                (0..self.number_of_states()).map(|id| (id, id)).collect(),
+            ),
+        };
+        // 初始划分的每一块以(是否接受, 标签)为键，0号状态所在的非接受块放在最前面
+        type Key = (bool, Option<u32>);
+        let mut blocks: Vec<(Key, Vec<StateId>)> = vec![((false, None), Vec::new())];
+        for (old, new) in id_map.iter().sorted_by_key(|(_, new)| **new) {
+            let key = if reachable.accept_states.contains(new) {
+                (true, labels.get(old).cloned())
+            } else {
+                (false, None)
+            };
+            match blocks.iter_mut().find(|(block_key, _)| *block_key == key) {
+                Some((_, states)) => states.push(*new),
+                None => blocks.push((key, vec![*new])),
+            }
+        }
+        let partition: Vec<Vec<StateId>> = blocks.into_iter().map(|(_, states)| states).collect();
+        reachable
+            .quotient_by(&partition)
+            .expect("the blocks cover every state exactly once")
+    }
+
+    /// 用Hopcroft的划分细化算法极小化，结果和`minimize`等价，时间复杂度是O(|Σ| n log n)。
+    ///
+    /// `minimize`用的填表法要检查所有状态对，是O(n²)的，状态数上千时很慢。
+    /// Hopcroft算法从“接受/非接受”两块出发，每次取出一个“分裂者”(块S, 字符a)，
+    /// 通过入表找到所有读入a后到达S的状态，用它们把每一块分成两半。
+    /// 一块被分开之后，只需要把较小的那一半作为新的分裂者，这就是n log n的来源。
+    ///
+    /// 和`minimize`一样，不可达状态被删掉，死状态并入0号陷阱状态，部分DFA先转换成完全DFA。
+    pub fn minimize_hopcroft(&self) -> DenseDFA {
+        if self.is_partial() {
+            return self.complete_partial().minimize_hopcroft();
+        }
+        let reachable = match self.trim_unreachable() {
+            Some((trimmed, _)) => trimmed,
+            None => self.clone(),
+        };
+        let number_of_states = reachable.number_of_states() as usize;
+        let alphabet_len = reachable.alphabet.len();
+        let stride2 = reachable.in_transitions.stride_as_power_of_2;
+
+        // 可细化的划分：`elements`是所有状态的一个排列，每一块占其中连续的一段[start, end)，
+        // `location`是每个状态在`elements`中的位置。分块时把被标记的状态交换到这一段的开头。
+        let mut elements: Vec<usize> = (0..number_of_states).collect();
+        let mut location: Vec<usize> = (0..number_of_states).collect();
+        let mut block_of = vec![0; number_of_states];
+        let mut blocks: Vec<(usize, usize)> = Vec::new();
+        let mut marked: Vec<usize> = Vec::new();
+
+        elements.sort_by_key(|state| reachable.accept_states.contains(&(*state as StateId)));
+        for (index, state) in elements.iter().enumerate() {
+            location[*state] = index;
+        }
+        let number_of_rejects = number_of_states - reachable.accept_states.len();
+        for (start, end) in [
+            (0, number_of_rejects),
+            (number_of_rejects, number_of_states),
+        ] {
+            if start < end {
+                for state in &elements[start..end] {
+                    block_of[*state] = blocks.len();
+                }
+                blocks.push((start, end));
+                marked.push(0);
+            }
+        }
+
+        // 待处理的分裂者。两块都存在时只需要较小的那一块。
+        let mut waiting: Vec<(usize, usize)> = Vec::new();
+        if blocks.len() == 2 {
+            let smaller = if blocks[0].1 - blocks[0].0 <= blocks[1].1 - blocks[1].0 {
+                0
+            } else {
+                1
+            };
+            waiting.extend((0..alphabet_len).map(|input| (smaller, input)));
+        }
+
+        let mut touched: Vec<usize> = Vec::new();
+        while let Some((splitter, input)) = waiting.pop() {
+            // 标记所有读入input后到达splitter的状态。DFA是完全的，所以每个状态最多被标记一次。
+            let (start, end) = blocks[splitter];
+            let targets: Vec<usize> = elements[start..end].to_vec();
+            for to in targets {
+                for from in reachable.in_transitions.trans[(to << stride2) + input].iter() {
+                    let from = *from as usize;
+                    let block = block_of[from];
+                    let (block_start, _) = blocks[block];
+                    if marked[block] == 0 {
+                        touched.push(block);
+                    }
+                    let swap_with = block_start + marked[block];
+                    let other = elements[swap_with];
+                    elements.swap(location[from], swap_with);
+                    location[other] = location[from];
+                    location[from] = swap_with;
+                    marked[block] += 1;
+                }
+            }
+
+            // 被部分标记的块分成两块，较小的一半成为新块
+            for block in touched.drain(..) {
+                let (block_start, block_end) = blocks[block];
+                let number_of_marked = std::mem::replace(&mut marked[block], 0);
+                if number_of_marked == block_end - block_start {
+                    continue;
+                }
+                let middle = block_start + number_of_marked;
+                let new_range = if number_of_marked <= block_end - middle {
+                    blocks[block] = (middle, block_end);
+                    (block_start, middle)
+                } else {
+                    blocks[block] = (block_start, middle);
+                    (middle, block_end)
+                };
+                let new_block = blocks.len();
+                for state in &elements[new_range.0..new_range.1] {
+                    block_of[*state] = new_block;
+                }
+                blocks.push(new_range);
+                marked.push(0);
+                // 不管旧块是否还在等待，新块（较小的一半）都要作为分裂者
+                waiting.extend((0..alphabet_len).map(|input| (new_block, input)));
+            }
+        }
+
+        let partition: Vec<Vec<StateId>> = blocks
+            .iter()
+            .map(|(start, end)| {
+                elements[*start..*end]
+                    .iter()
+                    .map(|state| *state as StateId)
+                    .collect()
+            })
+            .collect();
+        reachable
+            .quotient_by(&partition)
+            .expect("the blocks cover every state exactly once")
+    }
+
+    /// 按规范的顺序重新编号：字母表从小到大排序，陷阱状态是0号，开始状态是1号，
+    /// 其他状态按照从开始状态出发、按字母表顺序广度优先搜索时第一次遇到的顺序编号。不可达的状态被删掉。
+    ///
+    /// 两个极小DFA接受相同的语言，当且仅当它们规范化之后完全一样。部分DFA先用`complete_partial`补全。
+    pub fn canonicalize(&self) -> DenseDFA {
+        if self.is_partial() {
+            return self.complete_partial().canonicalize();
+        }
+        let mut alphabet = self.alphabet.clone();
+        alphabet.sort_unstable();
+
+        let mut new_id: HashMap<StateId, StateId> = HashMap::new();
+        new_id.insert(0, 0);
+        let mut order = vec![0];
+        if let Entry::Vacant(entry) = new_id.entry(self.start_state()) {
+            entry.insert(1);
+            order.push(self.start_state());
+        }
+        let mut index = 1;
+        while index < order.len() {
+            for input in alphabet.iter() {
+                let to = self.delta(order[index], *input);
+                if let Entry::Vacant(entry) = new_id.entry(to) {
+                    entry.insert(order.len() as StateId);
+                    order.push(to);
+                }
+            }
+            index += 1;
+        }
+
+        let table: Vec<Vec<StateId>> = order
+            .iter()
+            .map(|state| {
+                alphabet
+                    .iter()
+                    .map(|input| new_id[&self.delta(*state, *input)])
+                    .collect()
+            })
+            .collect();
+        let accept_states: Vec<StateId> = self
+            .accept_states
+            .iter()
+            .filter_map(|state| new_id.get(state).cloned())
+            .collect();
+        DenseDFA::from_table(
+            alphabet,
+            &table,
+            new_id[&self.start_state()],
+            &accept_states,
+        )
+    }
+
+    /// 不管DFA是怎么构造出来的，都得到一个干净的、规范的极小DFA。
+    ///
+    /// 依次执行：
+    /// 1. `trim`：删去不可达状态，死状态并入0号陷阱状态（这一步之后DFA也是完全的）；
+    /// 2. `minimize`：合并不可区分的状态；
+    /// 3. `canonicalize`：排序字母表，按广度优先的顺序重新编号。
+    ///
+    /// 所以接受相同语言、字母表相同的DFA规范化之后结构完全相同。
+    /// 空语言规范化之后只剩下一个状态，它既是开始状态，也是陷阱状态。
+    pub fn normalize(&self) -> DenseDFA {
+        let trimmed = self.complete_partial().trim();
+        let minimized = trimmed.minimize();
+        minimized.canonicalize()
+    }
+}
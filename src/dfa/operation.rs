@@ -0,0 +1,442 @@
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+
+use crate::nfa::NFA;
+
+use super::{CompletedDfa, DenseDFA, ProductError, DEFAULT_PRODUCT_LIMIT};
+
+type StateId = u128;
+
+/// 反转
+impl DenseDFA {
+    /// 构造接受反转语言的NFA，也就是接受所有本DFA接受的字符串倒过来写之后的字符串。
+    ///
+    /// 把每个转移 δ(p, a) = q 反过来变成 q -a-> p，原来的开始状态变成唯一的接收状态，
+    /// 再添加一个新的开始状态，用空转移连到原来的每个接受状态。
+    /// 反转之后一个状态可能读同一个字符到达多个状态，所以结果只能是NFA。部分DFA中没有定义的转移被跳过。
+    pub fn reverse(&self) -> NFA {
+        let mut nfa = NFA::init_empty();
+        for _ in 0..self.number_of_states() {
+            nfa.add_non_epsilon_state();
+        }
+        for from in 0..self.number_of_states() {
+            for (input, to) in self.defined_transitions(from) {
+                nfa.add_transition(to as u32, input, from as u32);
+            }
+        }
+
+        let start = nfa.add_epsilon_state();
+        for accept in self.accept_states_sorted() {
+            nfa.add_epsilon_transition(start, accept as u32);
+        }
+        nfa.set_start_state(start);
+        nfa.set_accept_state(self.start_state() as u32);
+        nfa
+    }
+
+    /// 直接求反转语言的极小DFA。
+    ///
+    /// 先用`reverse`构造NFA，再走一遍和`re_to_dfa`相同的 消除空转移 -> 确定化 -> 极小化 流程。
+    pub fn reverse_to_dfa(&self) -> DenseDFA {
+        DenseDFA::build_from_nfa(&self.reverse())
+    }
+}
+
+/// 语言的比较
+impl DenseDFA {
+    /// 判断两个DFA是否接受相同的语言。
+    ///
+    /// 字母表不同时，先把两边都扩展到字母表的并集，新字符都转移到各自的陷阱状态。
+    /// 然后从两个开始状态组成的状态对出发，同时运行两个DFA，
+    /// 如果能到达一个“一边接受、一边不接受”的状态对，两个语言就不相等。
+    /// 部分DFA先用`complete_partial`转换成完全DFA。
+    pub fn equivalent(&self, other: &DenseDFA) -> bool {
+        if self.is_partial() || other.is_partial() {
+            return self
+                .complete_partial()
+                .equivalent(&other.complete_partial());
+        }
+        let left = self.with_extended_alphabet(&other.alphabet);
+        let right = other.with_extended_alphabet(&self.alphabet);
+
+        let start = (left.start_state(), right.start_state());
+        let mut visited: HashSet<(StateId, StateId)> = HashSet::new();
+        visited.insert(start);
+        let mut stack = vec![start];
+        while let Some((state1, state2)) = stack.pop() {
+            if left.accept_states.contains(&state1) != right.accept_states.contains(&state2) {
+                return false;
+            }
+            for input in left.alphabet.iter() {
+                let next = (left.delta(state1, *input), right.delta(state2, *input));
+                if visited.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        true
+    }
+
+    /// `equivalent`加上字母表检查：字母表不同（不考虑顺序）时返回`ProductError::Alphabet`，
+    /// 和`intersection_strict`等带`_strict`后缀的运算一致。比如检查学生的DFA和答案是否一致。
+    pub fn is_equivalent(&self, other: &DenseDFA) -> Result<bool, ProductError> {
+        self.check_same_alphabet(other)?;
+        Ok(self.equivalent(other))
+    }
+
+    /// 判断这个DFA的语言是否在反转下不变，也就是 L == reverse(L)。
+    pub fn is_reversal_invariant(&self) -> bool {
+        self.equivalent(&self.reverse_to_dfa())
+    }
+
+    /// 判断这个DFA的语言是否等于`other`的语言的反转。
+    pub fn reverse_language_equals(&self, other: &DenseDFA) -> bool {
+        self.equivalent(&other.reverse_to_dfa())
+    }
+}
+
+/// 积运算
+impl DenseDFA {
+    /// 积构造：新DFA的状态是两个DFA的状态对，两个DFA同步转移，
+    /// 状态对是否接受由`accept(本DFA接受, 另一个DFA接受)`决定。
+    ///
+    /// 只构造从开始状态对可达的状态对。状态对 (0, 0) 总是新DFA的0号状态，
+    /// 只要`accept(false, false)`为false，它就仍然是陷阱状态。其他状态对按广度优先的顺序编号。
+    /// 两个DFA的字母表不同时，先用`with_extended_alphabet`把两边都扩展到字母表的并集，
+    /// 一边没有的字符在那一边转移到陷阱状态，也就是说含有这个字符的字符串总是被那一边拒绝。
+    /// 所以交、并、差等运算在字母表不同时也有明确的语义。
+    /// 新DFA的字母表是本DFA的字母表，后面再接上另一个DFA独有的字符。
+    ///
+    /// 部分DFA先用`complete_partial`转换成完全DFA。
+    ///
+    /// 状态数最多是两个DFA状态数的乘积，超过`DEFAULT_PRODUCT_LIMIT`时返回错误，
+    /// 需要别的上限时用`product_with_limit`。
+    pub fn product_with(
+        &self,
+        other: &DenseDFA,
+        accept: impl Fn(bool, bool) -> bool,
+    ) -> Result<DenseDFA, ProductError> {
+        self.product_with_limit(other, accept, DEFAULT_PRODUCT_LIMIT)
+    }
+
+    /// 和`product_with`相同，但是可达的状态对超过`limit`个时就停止构造，返回`ProductTooLarge`。
+    pub fn product_with_limit(
+        &self,
+        other: &DenseDFA,
+        accept: impl Fn(bool, bool) -> bool,
+        limit: usize,
+    ) -> Result<DenseDFA, ProductError> {
+        if self.is_partial() || other.is_partial() {
+            return self.complete_partial().product_with_limit(
+                &other.complete_partial(),
+                accept,
+                limit,
+            );
+        }
+        if self.check_same_alphabet(other).is_err() {
+            let left = self.with_extended_alphabet(&other.alphabet);
+            let right = other.with_extended_alphabet(&self.alphabet);
+            return left.product_with_limit(&right, accept, limit);
+        }
+
+        let mut ids: HashMap<(StateId, StateId), StateId> = HashMap::new();
+        let mut pairs = vec![(0, 0)];
+        ids.insert((0, 0), 0);
+        let start = (self.start_state(), other.start_state());
+        if let Entry::Vacant(entry) = ids.entry(start) {
+            entry.insert(pairs.len() as StateId);
+            pairs.push(start);
+        }
+
+        let mut table = Vec::new();
+        let mut index = 0;
+        while index < pairs.len() {
+            let (state1, state2) = pairs[index];
+            let mut row = Vec::with_capacity(self.alphabet.len());
+            for input in self.alphabet.iter() {
+                let next = (self.delta(state1, *input), other.delta(state2, *input));
+                let id = match ids.entry(next) {
+                    Entry::Occupied(entry) => *entry.get(),
+                    Entry::Vacant(entry) => {
+                        pairs.push(next);
+                        if pairs.len() > limit {
+                            return Err(ProductError::ProductTooLarge {
+                                reached: pairs.len(),
+                            });
+                        }
+                        *entry.insert(pairs.len() as StateId - 1)
+                    }
+                };
+                row.push(id);
+            }
+            table.push(row);
+            index += 1;
+        }
+
+        let accept_states: Vec<StateId> = pairs
+            .iter()
+            .enumerate()
+            .filter(|(_, (state1, state2))| {
+                accept(
+                    self.accept_states.contains(state1),
+                    other.accept_states.contains(state2),
+                )
+            })
+            .map(|(id, _)| id as StateId)
+            .collect();
+
+        Ok(DenseDFA::from_table(
+            self.alphabet.clone(),
+            &table,
+            ids[&start],
+            &accept_states,
+        ))
+    }
+
+    /// 求两个DFA的语言的交集。
+    pub fn intersect(&self, other: &DenseDFA) -> Result<DenseDFA, ProductError> {
+        self.product_with(other, |left, right| left && right)
+    }
+
+    /// 求两个DFA的语言的并集。
+    pub fn union(&self, other: &DenseDFA) -> Result<DenseDFA, ProductError> {
+        self.product_with(other, |left, right| left || right)
+    }
+
+    /// 和`product_with`相同，但是要求两个DFA的字母表相同（不考虑顺序）。
+    ///
+    /// 字母表不一样时不会自动扩展，而是返回`ProductError::Alphabet`。
+    /// 适合字母表本来就应该一致的场合，比如课堂上用同一个字母表给出的两个DFA，不一致多半是写错了。
+    /// 带`_strict`后缀的运算都通过这个方法检查字母表。
+    pub fn product_strict(
+        &self,
+        other: &DenseDFA,
+        accept: impl Fn(bool, bool) -> bool,
+    ) -> Result<DenseDFA, ProductError> {
+        self.check_same_alphabet(other)?;
+        self.product_with(other, accept)
+    }
+
+    /// 和`intersect`相同，但是字母表不同时返回错误，见`product_strict`。
+    pub fn intersection_strict(&self, other: &DenseDFA) -> Result<DenseDFA, ProductError> {
+        self.product_strict(other, |left, right| left && right)
+    }
+
+    /// 和`union`相同，但是字母表不同时返回错误，见`product_strict`。
+    pub fn union_strict(&self, other: &DenseDFA) -> Result<DenseDFA, ProductError> {
+        self.product_strict(other, |left, right| left || right)
+    }
+
+    /// 求两个DFA的语言的差集，也就是被本DFA接受、但是不被`other`接受的字符串。
+    ///
+    /// 只有`accept(true, false)`为true，所以状态对 (0, 0) 仍然是陷阱状态；
+    /// `other`进入陷阱状态而本DFA还能接受的状态对 (q, 0) 是普通的状态。
+    pub fn difference(&self, other: &DenseDFA) -> Result<DenseDFA, ProductError> {
+        self.product_with(other, |left, right| left && !right)
+    }
+
+    /// 求两个DFA的语言的对称差，也就是恰好被其中一个DFA接受的字符串。
+    ///
+    /// 对称差为空当且仅当两个DFA等价。
+    pub fn symmetric_difference(&self, other: &DenseDFA) -> Result<DenseDFA, ProductError> {
+        self.product_with(other, |left, right| left != right)
+    }
+
+    /// 求多个DFA的语言的交集。
+    ///
+    /// 从左往右逐个求交，每求一次交集就极小化一次，避免中间结果的状态数成倍增长。
+    /// 求交的顺序会影响中间结果的大小，一般把状态少、限制强的DFA放在前面比较好。
+    ///
+    /// `dfas`为空时返回`ProductError::NoOperands`，因为没有字母表就构造不出“接受所有字符串”的DFA。
+    pub fn intersect_many(dfas: &[&DenseDFA]) -> Result<DenseDFA, ProductError> {
+        let (first, rest) = dfas.split_first().ok_or(ProductError::NoOperands)?;
+        let mut result = (*first).clone();
+        for dfa in rest {
+            let product = result.intersect(dfa)?;
+            result = product.minimize();
+        }
+        Ok(result)
+    }
+}
+
+/// 不相交性
+impl DenseDFA {
+    /// 判断两个DFA的语言是否不相交，也就是没有同时被两个DFA接受的字符串。
+    ///
+    /// 在状态对上广度优先搜索，找到两边都接受的状态对就停止，不需要构造出整个交集DFA。
+    pub fn is_disjoint(&self, other: &DenseDFA) -> bool {
+        self.common_witness(other).is_none()
+    }
+
+    /// 返回一个同时被两个DFA接受的最短字符串，长度相同时取字典序最小的。两个语言不相交时返回None。
+    ///
+    /// 字母表不同时，只在一边的字符不可能出现在公共的字符串里，所以只沿着两边共有的字符搜索。
+    /// 部分DFA先用`complete_partial`转换成完全DFA。
+    pub fn common_witness(&self, other: &DenseDFA) -> Option<Vec<u8>> {
+        if self.is_partial() || other.is_partial() {
+            return self
+                .complete_partial()
+                .common_witness(&other.complete_partial());
+        }
+        let mut alphabet: Vec<u8> = self
+            .alphabet
+            .iter()
+            .filter(|input| other.alphabet.contains(input))
+            .cloned()
+            .collect();
+        alphabet.sort_unstable();
+
+        let start = (self.start_state(), other.start_state());
+        // 记录每个状态对的前驱和进入它时读的字符，用于还原字符串。
+        type Pair = (StateId, StateId);
+        let mut previous: HashMap<Pair, Option<(Pair, u8)>> = HashMap::new();
+        previous.insert(start, None);
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+
+        while let Some(pair) = queue.pop_front() {
+            if self.accept_states.contains(&pair.0) && other.accept_states.contains(&pair.1) {
+                let mut word = Vec::new();
+                let mut pair = pair;
+                while let Some((prev, input)) = previous[&pair] {
+                    word.push(input);
+                    pair = prev;
+                }
+                word.reverse();
+                return Some(word);
+            }
+            // 一边进入陷阱状态之后不可能再被接受
+            if pair.0 == 0 || pair.1 == 0 {
+                continue;
+            }
+            for input in alphabet.iter() {
+                let next = (self.delta(pair.0, *input), other.delta(pair.1, *input));
+                if let Entry::Vacant(entry) = previous.entry(next) {
+                    entry.insert(Some((pair, *input)));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// 补集
+///
+/// 补集依赖于字母表：同一个语言`0*`，在字母表{0}上的补集是空集，在字母表{0,1}上的补集是所有含有1的串。
+impl DenseDFA {
+    /// 在这个DFA自己的字母表上求补集：接受字母表上所有不被本DFA接受的字符串。
+    ///
+    /// 0号状态表示“没有转移”，不能直接变成接受状态，所以先用`materialize_trap`把死状态变成普通状态，
+    /// 再交换接受状态和非接受状态。0号状态在结果中不可达，仍然是非接受的陷阱状态。
+    pub fn complement(&self) -> DenseDFA {
+        let complete = self.complete_partial().materialize_trap();
+        let accept_states: Vec<StateId> = (1..complete.number_of_states())
+            .filter(|state| !complete.accept_states.contains(state))
+            .collect();
+        let table: Vec<Vec<StateId>> = (0..complete.number_of_states())
+            .map(|state| {
+                complete
+                    .alphabet
+                    .iter()
+                    .map(|input| complete.delta(state, *input))
+                    .collect()
+            })
+            .collect();
+        DenseDFA::from_table(
+            complete.alphabet.clone(),
+            &table,
+            complete.start_state(),
+            &accept_states,
+        )
+    }
+
+    /// 在给定的字母表上求补集：接受`alphabet`上所有不被本DFA接受的字符串。
+    ///
+    /// `alphabet`中本DFA没有的字符先用`with_extended_alphabet`加进来，含有这些字符的串都在补集中；
+    /// 本DFA有而`alphabet`中没有的字符被删去，含有这些字符的串不在补集中。结果的字母表就是`alphabet`。
+    pub fn complement_over(&self, alphabet: &[u8]) -> DenseDFA {
+        self.with_extended_alphabet(alphabet)
+            .restrict_to_alphabet(alphabet)
+            .expect("every symbol has just been added to the alphabet")
+            .complement()
+    }
+}
+
+/// 连接和闭包
+///
+/// 积构造做不了连接和闭包，所以先把DFA嵌入到NFA里，用空转移连起来，再用子集构造确定化。
+impl DenseDFA {
+    /// 把这个DFA的活状态复制到`nfa`里，返回开始状态和接受状态在`nfa`中的id。
+    ///
+    /// NFA的状态要么只有空转移，要么只有非空转移，所以DFA的每个状态q对应两个NFA状态：
+    /// 一个只有空转移的“入口”，空转移到一个只有非空转移的“出口”，δ(p, a) = q 变成 p的出口 -a-> q的入口。
+    /// 返回的都是入口，之后可以从接受状态的入口添加空转移。指向陷阱状态的转移直接省略。
+    /// 部分DFA的0号状态是普通状态，省略的是没有定义的转移。
+    fn embed_into(&self, nfa: &mut NFA) -> (u32, Vec<u32>) {
+        let entries: Vec<u32> = (0..self.number_of_states())
+            .map(|_| {
+                let entry = nfa.add_epsilon_state();
+                let exit = nfa.add_non_epsilon_state();
+                nfa.add_epsilon_transition(entry, exit);
+                entry
+            })
+            .collect();
+        let first = if self.is_partial() { 0 } else { 1 };
+        for from in first..self.number_of_states() {
+            for (input, to) in self.defined_transitions(from) {
+                if to != 0 || self.is_partial() {
+                    // 出口紧跟在入口后面添加，id是入口加一
+                    nfa.add_transition(entries[from as usize] + 1, input, entries[to as usize]);
+                }
+            }
+        }
+        let accepts = self
+            .accept_states_sorted()
+            .into_iter()
+            .map(|state| entries[state as usize])
+            .collect();
+        (entries[self.start_state() as usize], accepts)
+    }
+
+    /// 确定化并极小化，字母表是`alphabets`的并集。
+    fn from_nfa_over(nfa: &NFA, alphabets: &[&[u8]]) -> DenseDFA {
+        let alphabet: Vec<u8> = alphabets.iter().flat_map(|a| a.iter()).cloned().collect();
+        let dfa = nfa.determinize().with_extended_alphabet(&alphabet);
+        dfa.minimize()
+    }
+
+    /// 求两个DFA的语言的连接，也就是所有 uv，其中u被本DFA接受，v被`other`接受。
+    ///
+    /// 结果是极小DFA，字母表是两个字母表的并集。
+    pub fn concat(&self, other: &DenseDFA) -> DenseDFA {
+        let mut nfa = NFA::init_empty();
+        let (start1, accepts1) = self.embed_into(&mut nfa);
+        let (start2, accepts2) = other.embed_into(&mut nfa);
+        for accept in accepts1 {
+            nfa.add_epsilon_transition(accept, start2);
+        }
+        nfa.set_start_state(start1);
+        for accept in accepts2 {
+            nfa.set_accept_state(accept);
+        }
+        DenseDFA::from_nfa_over(&nfa, &[&self.alphabet, &other.alphabet])
+    }
+
+    /// 求这个DFA的语言的Kleene闭包，也就是零个或多个被接受的字符串连接起来。结果是极小DFA。
+    ///
+    /// 添加一个新的开始状态，它本身是接受状态（接受空串），空转移到原来的开始状态；
+    /// 原来的每个接受状态再空转移回原来的开始状态。
+    pub fn star(&self) -> DenseDFA {
+        let mut nfa = NFA::init_empty();
+        let (start, accepts) = self.embed_into(&mut nfa);
+        let new_start = nfa.add_epsilon_state();
+        nfa.add_epsilon_transition(new_start, start);
+        nfa.set_start_state(new_start);
+        nfa.set_accept_state(new_start);
+        for accept in accepts {
+            nfa.add_epsilon_transition(accept, start);
+            nfa.set_accept_state(accept);
+        }
+        DenseDFA::from_nfa_over(&nfa, &[&self.alphabet])
+    }
+}
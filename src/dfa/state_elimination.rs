@@ -0,0 +1,253 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::{CompletedDfa, DenseDFA};
+
+type StateId = u128;
+
+/// 状态消去法消去状态的顺序。
+///
+/// 得到的正则表达式的长度和消去顺序关系很大，不同顺序之间可能相差好几个数量级。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EliminationOrder {
+    /// 按状态id从小到大消去。
+    Index,
+    /// 每次消去当前入边数加出边数最少的状态（不算自环），度数相同时先消去id小的。
+    /// 这是一个常用的启发式规则，一般能得到短得多的正则表达式。
+    LowestDegreeFirst,
+    /// 按给定的顺序消去。没有列出的状态最后再按id从小到大消去，不存在或者没用的状态会被忽略。
+    Custom(Vec<StateId>),
+}
+
+/// 消去过程中边上的正则表达式。
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Re {
+    Epsilon,
+    Symbol(u8),
+    Concat(Vec<Re>),
+    Alternation(Vec<Re>),
+    Star(Box<Re>),
+}
+
+impl Re {
+    fn concat(items: Vec<Re>) -> Re {
+        let mut flat = Vec::new();
+        for item in items {
+            match item {
+                Re::Epsilon => (),
+                Re::Concat(inner) => flat.extend(inner),
+                other => flat.push(other),
+            }
+        }
+        match flat.len() {
+            0 => Re::Epsilon,
+            1 => flat.pop().unwrap(),
+            _ => Re::Concat(flat),
+        }
+    }
+
+    fn alternation(left: Re, right: Re) -> Re {
+        let mut flat = Vec::new();
+        for item in [left, right] {
+            let items = match item {
+                Re::Alternation(inner) => inner,
+                other => vec![other],
+            };
+            for item in items {
+                if !flat.contains(&item) {
+                    flat.push(item);
+                }
+            }
+        }
+        if flat.len() == 1 {
+            flat.pop().unwrap()
+        } else {
+            Re::Alternation(flat)
+        }
+    }
+
+    fn star(inner: Re) -> Re {
+        match inner {
+            Re::Epsilon => Re::Epsilon,
+            Re::Star(_) => inner,
+            // (ε|r)* = r*
+            Re::Alternation(items) if items.contains(&Re::Epsilon) => {
+                let rest: Vec<Re> = items
+                    .into_iter()
+                    .filter(|item| *item != Re::Epsilon)
+                    .collect();
+                let rest = if rest.len() == 1 {
+                    rest.into_iter().next().unwrap()
+                } else {
+                    Re::Alternation(rest)
+                };
+                Re::star(rest)
+            }
+            other => Re::Star(Box::new(other)),
+        }
+    }
+
+    /// 输出成`re_to_dfa`能读的语法。`precedence`是外层要求的优先级：
+    /// 0是或运算，1是连接，2是闭包的操作数，优先级不够时加括号。
+    fn write(&self, precedence: u8, output: &mut String) {
+        match self {
+            Re::Epsilon => output.push_str("()"),
+            Re::Symbol(input) => {
+                if input.is_ascii_alphanumeric() {
+                    output.push(*input as char);
+                } else {
+                    output.push_str(&format!("\\x{:02X}", input));
+                }
+            }
+            Re::Concat(items) => {
+                if precedence > 1 {
+                    output.push('(');
+                }
+                for item in items {
+                    item.write(1, output);
+                }
+                if precedence > 1 {
+                    output.push(')');
+                }
+            }
+            Re::Alternation(items) => {
+                if precedence > 0 {
+                    output.push('(');
+                }
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        output.push('|');
+                    }
+                    item.write(0, output);
+                }
+                if precedence > 0 {
+                    output.push(')');
+                }
+            }
+            Re::Star(inner) => {
+                inner.write(2, output);
+                output.push('*');
+            }
+        }
+    }
+}
+
+/// 转换为正则表达式
+impl DenseDFA {
+    /// 用状态消去法把这个DFA转换为正则表达式，按状态id从小到大消去状态。
+    ///
+    /// 输出的语法和`re_to_dfa`的输入一致，空串写作`()`，字母和数字以外的字符写成`\xHH`。
+    /// 如果DFA不接受任何字符串，返回None。
+    pub fn to_regex(&self) -> Option<String> {
+        self.to_regex_with(EliminationOrder::Index)
+    }
+
+    /// 用状态消去法把这个DFA转换为正则表达式，按`order`指定的顺序消去状态。
+    ///
+    /// 只有既可达、又能到达接受状态的状态会参与消去，陷阱状态之类的无用状态一开始就被删掉了。
+    /// 部分DFA中没有定义的转移不会变成边。
+    pub fn to_regex_with(&self, order: EliminationOrder) -> Option<String> {
+        let coreachable = self.coreachable_states();
+        let mut useful: Vec<StateId> = self
+            .reachable_states()
+            .into_iter()
+            .filter(|state| coreachable.contains(state))
+            .collect();
+        useful.sort_unstable();
+        if !useful.contains(&self.start_state()) {
+            return None;
+        }
+
+        // 广义NFA的结点：0..m是有用的状态，m是新的开始结点，m + 1是新的接受结点。
+        let index: HashMap<StateId, usize> = useful
+            .iter()
+            .enumerate()
+            .map(|(i, state)| (*state, i))
+            .collect();
+        let start = useful.len();
+        let accept = useful.len() + 1;
+
+        let mut edges: BTreeMap<(usize, usize), Re> = BTreeMap::new();
+        let add_edge = |edges: &mut BTreeMap<(usize, usize), Re>, from, to, re| {
+            let re = match edges.remove(&(from, to)) {
+                Some(old) => Re::alternation(old, re),
+                None => re,
+            };
+            edges.insert((from, to), re);
+        };
+        for (from, state) in useful.iter().enumerate() {
+            for (input, to) in self.defined_transitions(*state) {
+                if let Some(to) = index.get(&to) {
+                    add_edge(&mut edges, from, *to, Re::Symbol(input));
+                }
+            }
+            if self.accept_states.contains(state) {
+                add_edge(&mut edges, from, accept, Re::Epsilon);
+            }
+        }
+        add_edge(&mut edges, start, index[&self.start_state()], Re::Epsilon);
+
+        let mut remaining: Vec<usize> = (0..useful.len()).collect();
+        let mut custom: Vec<usize> = match &order {
+            EliminationOrder::Custom(states) => states
+                .iter()
+                .filter_map(|state| index.get(state).cloned())
+                .collect(),
+            _ => Vec::new(),
+        };
+        custom.reverse();
+
+        while !remaining.is_empty() {
+            let position = match &order {
+                EliminationOrder::Index => 0,
+                EliminationOrder::LowestDegreeFirst => {
+                    let degree = |node: usize| {
+                        edges
+                            .keys()
+                            .filter(|(from, to)| from != to && (*from == node || *to == node))
+                            .count()
+                    };
+                    (0..remaining.len())
+                        .min_by_key(|position| degree(remaining[*position]))
+                        .unwrap()
+                }
+                EliminationOrder::Custom(_) => loop {
+                    match custom.pop() {
+                        Some(node) => {
+                            if let Some(position) = remaining.iter().position(|n| *n == node) {
+                                break position;
+                            }
+                        }
+                        None => break 0,
+                    }
+                },
+            };
+            let node = remaining.remove(position);
+
+            let self_loop = edges.remove(&(node, node)).map(Re::star);
+            let incoming: Vec<(usize, Re)> = edges
+                .iter()
+                .filter(|((_, to), _)| *to == node)
+                .map(|((from, _), re)| (*from, re.clone()))
+                .collect();
+            let outgoing: Vec<(usize, Re)> = edges
+                .iter()
+                .filter(|((from, _), _)| *from == node)
+                .map(|((_, to), re)| (*to, re.clone()))
+                .collect();
+            edges.retain(|(from, to), _| *from != node && *to != node);
+
+            for (from, first) in incoming.iter() {
+                for (to, last) in outgoing.iter() {
+                    let mut items = vec![first.clone()];
+                    items.extend(self_loop.clone());
+                    items.push(last.clone());
+                    add_edge(&mut edges, *from, *to, Re::concat(items));
+                }
+            }
+        }
+
+        let mut output = String::new();
+        edges.get(&(start, accept))?.write(0, &mut output);
+        Some(output)
+    }
+}
@@ -0,0 +1,93 @@
+use super::{AlphabetError, CompletedDfa, DenseDFA};
+
+type StateId = u128;
+
+/// 简单的稠密转换器（Mealy机）。
+///
+/// 底层就是一个`DenseDFA`，另外给每个转移附加一个可选的输出字符。
+/// 沿着一条被接受的运行路径，把经过的转移的输出连起来，就是转换的结果。
+#[derive(Clone)]
+pub struct DenseTransducer {
+    dfa: DenseDFA,
+    // 和DFA的转移一一对应，索引是 状态 * 字母表大小 + 字符在字母表中的索引。
+    outputs: Vec<Option<u8>>,
+}
+
+impl DenseTransducer {
+    /// 用一个DFA构造转换器，所有转移一开始都没有输出。
+    pub fn new(dfa: DenseDFA) -> Self {
+        let size = dfa.number_of_states() as usize * dfa.alphabet.len();
+        DenseTransducer {
+            dfa,
+            outputs: vec![None; size],
+        }
+    }
+
+    /// 底层的DFA。
+    pub fn dfa(&self) -> &DenseDFA {
+        &self.dfa
+    }
+
+    /// 设置转移 δ(from, input) 的输出，`None`表示这个转移不输出任何字符。
+    ///
+    /// 如果`input`不在字母表中，返回错误。`from`不是合法的状态时panic。
+    pub fn set_output(
+        &mut self,
+        from: StateId,
+        input: u8,
+        output: Option<u8>,
+    ) -> Result<(), AlphabetError> {
+        let index = self.index_of(from, input)?;
+        self.outputs[index] = output;
+        Ok(())
+    }
+
+    /// 给所有状态上读入`input`的转移都设置同样的输出，适合逐字符改写的任务。
+    pub fn set_output_for_symbol(
+        &mut self,
+        input: u8,
+        output: Option<u8>,
+    ) -> Result<(), AlphabetError> {
+        for from in 0..self.dfa.number_of_states() {
+            self.set_output(from, input, output)?;
+        }
+        Ok(())
+    }
+
+    /// 转换输入字符串。
+    ///
+    /// 如果底层DFA接受`input`，返回运行路径上所有转移的输出连起来的字符串；
+    /// 不接受（包括出现了字母表之外的字符）时返回None。
+    pub fn transduce(&self, input: &[u8]) -> Option<Vec<u8>> {
+        let mut state = self.dfa.start_state();
+        let mut result = Vec::new();
+        for byte in input {
+            let index = self.index_of(state, *byte).ok()?;
+            if !self.dfa.is_defined(state, *byte) {
+                return None;
+            }
+            result.extend(self.outputs[index]);
+            state = self.dfa.delta(state, *byte);
+        }
+        if self.dfa.accept_states.contains(&state) {
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    fn index_of(&self, state: StateId, input: u8) -> Result<usize, AlphabetError> {
+        assert!(
+            state < self.dfa.number_of_states(),
+            "no such a state: {}",
+            state
+        );
+        let position = self
+            .dfa
+            .alphabet
+            .iter()
+            .position(|symbol| *symbol == input)
+            .ok_or(AlphabetError::UnknownSymbol(input))?;
+        Ok(state as usize * self.dfa.alphabet.len() + position)
+    }
+}
@@ -43,28 +43,120 @@ pub mod dfa;
 /// 本模块包含了NFA的结构体和方法，以及从正则表达式构建NFA的方法。
 pub mod nfa;
 
+/// 用链式调用组合正则语言的`Automaton`，例如`a.union(b)?.star().minimize()`。
+pub mod algebra;
+
+/// 带LRU缓存的`re_to_dfa`，需要打开`cache` feature。
+#[cfg(feature = "cache")]
+pub mod cache;
+
+use std::fmt;
+
 use wasm_bindgen::prelude::*;
 
 /// 输入正则表达式，返回对应的DFA的状态转移表和对应的正则文法。
+///
+/// 正则表达式不合法时返回以`error: `开头的错误信息，网页前端直接显示它就行。
 #[wasm_bindgen]
 pub fn get_ans(input: &str) -> String {
-    let dfa = re_to_dfa(input);
+    utils::set_panic_hook();
+    let dfa = match re_to_dfa(input) {
+        Ok(dfa) => dfa,
+        Err(message) => return format!("error: {}", message),
+    };
     let ans = dfa.to_string();
     let rg = dfa.to_rg();
     let dot = dfa.call_to_dot();
     format!("{}@{}@{}", ans, rg, dot)
 }
 
+/// 输入正则表达式，返回消除空转移之后的NFA的状态图（DOT语言），用于展示转换的中间步骤。
+///
+/// 和`get_ans`一样，正则表达式不合法时返回以`error: `开头的错误信息。
+#[wasm_bindgen]
+pub fn get_epsilon_free_nfa_dot(input: &str) -> String {
+    utils::set_panic_hook();
+    let dot = nfa::Builder::new()
+        .build_nfa_from_re(input)
+        .map_err(|e| format!("failed to parse regex {:?}: {}", input, e))
+        .and_then(|nfa| nfa.epsilon_free_dot());
+    match dot {
+        Ok(dot) => dot,
+        Err(message) => format!("error: {}", message),
+    }
+}
+
+/// 输入正则表达式，返回极小化DFA的紧凑JSON，网页前端可以用它自己绘制状态图。
+///
+/// 和`get_ans`一样，正则表达式不合法时返回以`error: `开头的错误信息。
+#[wasm_bindgen]
+pub fn get_dfa_json(input: &str) -> String {
+    utils::set_panic_hook();
+    match re_to_dfa(input) {
+        Ok(dfa) => dfa.to_json_compact(),
+        Err(message) => format!("error: {}", message),
+    }
+}
+
 /// 将正则表达式转化为极小化DFA。
-pub fn re_to_dfa(re: &str) -> dfa::DenseDFA {
-    let nfa = nfa::Builder::new().build_nfa_from_re(&re.to_string()).unwrap();
-    let non_epsilon_nfa = nfa::Builder::new().build_non_epsilon_nfa(&nfa).unwrap();
-    let new_dfa = dfa::DFA01::build_dfa_from_nfa(&non_epsilon_nfa);
-    let newnew_dfa = dfa::DenseDFA::build_from_sparse01_dfa(&new_dfa);
-
-    if let Some(minimized) = newnew_dfa.minimize() {
-        minimized
-    } else {
-        newnew_dfa
+///
+/// 正则表达式有语法错误、用到了不支持的语法（例如零宽断言）、重复展开之后太大或者DFA的状态太多时返回错误信息，不会panic。
+pub fn re_to_dfa(re: &str) -> Result<dfa::DenseDFA, String> {
+    let nfa = nfa::Builder::new()
+        .build_nfa_from_re(re)
+        .map_err(|e| format!("failed to parse regex {:?}: {}", re, e))?;
+    dfa::DenseDFA::try_build_from_nfa(&nfa)
+}
+
+/// 把正则表达式转化为DFA时的错误。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConversionError {
+    /// 正则表达式有语法错误。
+    Syntax(String),
+    /// 正则表达式中出现了指定的字母表之外的字符。
+    UnknownSymbol(u8),
+    /// 从NFA构造DFA失败，信息和`re_to_dfa`返回的相同。
+    Build(String),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::Syntax(message) => write!(f, "syntax error: {}", message),
+            ConversionError::UnknownSymbol(input) => {
+                write!(f, "symbol '{}' is not in the alphabet", *input as char)
+            }
+            ConversionError::Build(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// 将正则表达式转化为字母表恰好是`alphabet`的极小化DFA。
+///
+/// `re_to_dfa`得到的DFA的字母表取决于构造过程，例如`0*`里从来没有出现过`1`。
+/// 这个函数把字母表固定下来：没用到的字符都转移到陷阱状态，这样得到的DFA之间可以直接做补、交等运算。
+/// 字符类（比如`[0-9]`）只会展开成字母表中的字符，正则表达式里直接写出的字符必须在字母表中。
+pub fn re_to_dfa_with_alphabet(
+    re: &str,
+    alphabet: &[u8],
+) -> Result<dfa::DenseDFA, ConversionError> {
+    let nfa = nfa::Builder::with_alphabet(alphabet)
+        .build_nfa_from_re(re)
+        .map_err(ConversionError::Syntax)?;
+    let used = nfa.sorted_alphabet();
+    if let Some(input) = used.iter().find(|input| !alphabet.contains(input)) {
+        return Err(ConversionError::UnknownSymbol(*input));
     }
-}
\ No newline at end of file
+
+    let mut keep = alphabet.to_vec();
+    keep.sort_unstable();
+    keep.dedup();
+    let dfa = dfa::DenseDFA::try_build_from_nfa(&nfa)
+        .map_err(ConversionError::Build)?
+        .with_extended_alphabet(&keep)
+        .restrict_to_alphabet(&keep)
+        .unwrap();
+    Ok(dfa.minimize())
+}
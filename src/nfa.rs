@@ -1,5 +1,11 @@
 use itertools::Itertools;
-use std::{collections::HashSet, iter::FromIterator};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    iter::FromIterator,
+};
+
+use crate::dfa::DenseDFA;
 
 // 这是一个正则语法解析相关的包，用于将正则表达式解析优化过的成语法树。
 // 语法树的节点类型在regex_syntax::hir::HirKind中定义。
@@ -14,7 +20,11 @@ use regex_syntax::{
 type StateId = u32;
 
 /// 表示一个NFA的结构体。
+///
+/// 打开`serde` feature之后可以序列化和反序列化。反序列化时会检查所有状态id都没有越界。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "NfaData"))]
 pub struct NFA {
     states: Vec<State>,
     alphabet: HashSet<u8>,
@@ -22,6 +32,51 @@ pub struct NFA {
     pub accept_states: Vec<StateId>,
 }
 
+/// 反序列化NFA时使用的中间结构，检查通过之后才会转换成NFA。
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct NfaData {
+    states: Vec<State>,
+    alphabet: HashSet<u8>,
+    start_state: Option<StateId>,
+    accept_states: Vec<StateId>,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<NfaData> for NFA {
+    type Error = String;
+
+    fn try_from(data: NfaData) -> Result<Self, Self::Error> {
+        let len = data.states.len() as StateId;
+        let check = |id: StateId| {
+            if id < len {
+                Ok(())
+            } else {
+                Err(format!("state id {} is out of range (0..{})", id, len))
+            }
+        };
+
+        for state in data.states.iter() {
+            match state {
+                State::Epsilon(trans) => trans.iter().try_for_each(|to| check(*to))?,
+                State::NonEpsilon(trans) => trans.iter().try_for_each(|(_, to)| check(*to))?,
+                State::Fail | State::Final => (),
+            }
+        }
+        if let Some(start) = data.start_state {
+            check(start)?;
+        }
+        data.accept_states.iter().try_for_each(|id| check(*id))?;
+
+        Ok(NFA {
+            states: data.states,
+            alphabet: data.alphabet,
+            start_state: data.start_state,
+            accept_states: data.accept_states,
+        })
+    }
+}
+
 /// NFA内的状态的增删改查
 impl NFA {
     /// 创建一个空的NFA。
@@ -103,7 +158,7 @@ impl NFA {
     }
 
     /// 返回本NFA内的所有状态组成的迭代器。
-    pub fn get_states_iter(&self) -> std::slice::Iter<State> {
+    pub fn get_states_iter(&self) -> std::slice::Iter<'_, State> {
         self.states.iter()
     }
 
@@ -111,6 +166,147 @@ impl NFA {
     pub fn alphabet(&self) -> &HashSet<u8> {
         &self.alphabet
     }
+
+    /// 获得从小到大排好序的字母表。
+    ///
+    /// `alphabet`是HashSet，遍历顺序每次运行都可能不同。需要稳定顺序的地方，
+    /// 例如子集构造和画图，都应该用这个方法，这样同一个NFA每次得到的结果都完全一样。
+    pub fn sorted_alphabet(&self) -> Vec<u8> {
+        self.alphabet.iter().cloned().sorted().collect()
+    }
+
+    /// 状态的数量。
+    pub fn number_of_states(&self) -> usize {
+        self.states.len()
+    }
+
+    /// 开始状态。还没有设置开始状态时返回None。
+    pub fn start_state(&self) -> Option<StateId> {
+        self.start_state
+    }
+
+    /// 所有接收状态。
+    pub fn accept_states(&self) -> &[StateId] {
+        &self.accept_states
+    }
+}
+
+/// 从边表构造NFA时的错误。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// 状态id太大，没有空间再添加拆分状态时需要的辅助状态。
+    StateIdOverflow(StateId),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::StateIdOverflow(id) => {
+                write!(f, "state id {} is too large to add auxiliary states", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// NFA和边表之间的转换
+///
+/// 边表是一个不依赖于内部`State`类型的中立表示，每条边是 `(from, input, to)`，`input`为None表示空转移。
+impl NFA {
+    /// 把这个NFA转换为边表，按 (from, input, to) 排序，空转移排在非空转移前面。
+    pub fn to_edge_list(&self) -> Vec<(StateId, Option<u8>, StateId)> {
+        let mut edges = Vec::new();
+        for (from, state) in self.states.iter().enumerate() {
+            match state {
+                State::Epsilon(trans) => {
+                    edges.extend(trans.iter().map(|to| (from as StateId, None, *to)))
+                }
+                State::NonEpsilon(trans) => edges.extend(
+                    trans
+                        .iter()
+                        .map(|(input, to)| (from as StateId, Some(*input), *to)),
+                ),
+                State::Fail | State::Final => (),
+            }
+        }
+        edges.sort_unstable();
+        edges
+    }
+
+    /// 从边表构造NFA。状态的数量是边表、开始状态和接受状态中出现的最大id加一，
+    /// 原来的状态id保持不变，构造过程中添加的辅助状态排在后面。
+    ///
+    /// `State`不允许一个状态同时有空转移和非空转移，所以这样的状态会被拆成两个：
+    /// 原状态变成只有空转移的状态，空转移到一个新添加的状态，由新状态承担所有非空转移，语言不变。
+    ///
+    /// 另外，`Builder::build_non_epsilon_nfa`要求NFA只有一个没有出路的接受状态，
+    /// 所以这里会添加一个新的接收状态作为唯一的接受状态，原来的每个接受状态都空转移到它
+    /// （有非空转移的接受状态也要按上面的方法拆分）。这样构造出的NFA可以直接走确定化的流程。
+    pub fn from_edge_list(
+        transitions: &[(StateId, Option<u8>, StateId)],
+        start: StateId,
+        accepts: &[StateId],
+    ) -> Result<NFA, BuildError> {
+        let max_id = transitions
+            .iter()
+            .flat_map(|(from, _, to)| vec![*from, *to])
+            .chain(accepts.iter().cloned())
+            .fold(start, StateId::max);
+        // 最坏情况下每个状态都要拆分，还要再加一个接收状态。
+        if max_id >= (StateId::MAX - 1) / 2 {
+            return Err(BuildError::StateIdOverflow(max_id));
+        }
+        let number_of_states = max_id as usize + 1;
+
+        let mut epsilon_trans = vec![Vec::new(); number_of_states];
+        let mut non_epsilon_trans = vec![Vec::new(); number_of_states];
+        for (from, input, to) in transitions.iter() {
+            match input {
+                Some(input) => non_epsilon_trans[*from as usize].push((*input, *to)),
+                None => epsilon_trans[*from as usize].push(*to),
+            }
+        }
+        let is_accept = |id: usize| accepts.contains(&(id as StateId));
+
+        // 只有非空转移、并且不是接受状态的状态不需要拆分，其他状态都用只有空转移的状态表示。
+        let mut nfa = NFA::init_empty();
+        for id in 0..number_of_states {
+            if !non_epsilon_trans[id].is_empty() && epsilon_trans[id].is_empty() && !is_accept(id) {
+                nfa.add_non_epsilon_state();
+            } else {
+                nfa.add_epsilon_state();
+            }
+        }
+
+        for id in 0..number_of_states {
+            if let State::NonEpsilon(_) = nfa.states[id] {
+                for (input, to) in non_epsilon_trans[id].iter() {
+                    nfa.add_transition(id as StateId, *input, *to);
+                }
+                continue;
+            }
+            for to in epsilon_trans[id].iter() {
+                nfa.add_epsilon_transition(id as StateId, *to);
+            }
+            if !non_epsilon_trans[id].is_empty() {
+                // 拆分出来的新状态
+                let split = nfa.add_non_epsilon_state();
+                nfa.add_epsilon_transition(id as StateId, split);
+                for (input, to) in non_epsilon_trans[id].iter() {
+                    nfa.add_transition(split, *input, *to);
+                }
+            }
+        }
+
+        let accept = nfa.add_final_state();
+        for id in accepts.iter().sorted().dedup() {
+            nfa.add_epsilon_transition(*id, accept);
+        }
+        nfa.set_start_state(start);
+        nfa.set_accept_state(accept);
+        Ok(nfa)
+    }
 }
 
 /// 状态和转移的计算相关方法
@@ -127,10 +323,8 @@ impl NFA {
         let mut non_epsilon_from = Vec::new();
         for (origin_id, origin_state) in self.states.iter().enumerate() {
             match origin_state {
-                State::Epsilon(trans) => {
-                    if trans.0.contains(&state) {
-                        epsilon_from.push(origin_id as StateId);
-                    }
+                State::Epsilon(trans) if trans.0.contains(&state) => {
+                    epsilon_from.push(origin_id as StateId);
                 }
                 State::NonEpsilon(trans) => {
                     for (input, to) in trans.iter() {
@@ -239,6 +433,9 @@ impl NFA {
                 .iter()
                 .for_each(|s| result.push((input, *s)));
         }
+        // 上面两个集合都是HashSet，迭代顺序不确定。排序之后消除空转移的结果才是稳定的。
+        result.sort_unstable();
+        result.dedup();
         result
     }
 
@@ -248,19 +445,15 @@ impl NFA {
         let mut stack = Vec::new();
         stack.push(self.start_state.unwrap());
 
-        let mut times = 0; // 用于调试，记录搜索次数。
-
         while let Some(state) = stack.pop() {
             if reachable_states.insert(state) {
                 if let State::NonEpsilon(trans) = &self.states[state as usize] {
                     for (_, next_state) in trans.iter() {
                         stack.push(*next_state);
-                        times += 1;
                     }
                 }
             }
         }
-        dbg!(times);
 
         HashSet::from_iter(0 as StateId..self.states.len() as StateId)
             .difference(&reachable_states)
@@ -269,7 +462,7 @@ impl NFA {
     }
 
     /// 重新建立状态集合的索引，去除fail状态。
-    /// 只应该在已去除空转移的NFA上调用！
+    /// 调用之前必须保证没有指向fail状态的转移，公开的版本是`prune_fail_states`。
     fn remap_states(&mut self) {
         // 生成一个从旧状态编号到新状态编号的映射表。
         let mut id_map = Vec::with_capacity(self.states.len());
@@ -290,9 +483,18 @@ impl NFA {
         for id in 0..self.states.len() {
             self.remap_trans(id as StateId, &id_map);
         }
+        // 开始状态和接受状态的编号也要跟着变，被删除的接受状态（不可达）直接丢掉。
+        self.start_state = self
+            .start_state
+            .map(|id| id_map[id as usize].expect("start state is a fail state"));
+        self.accept_states = self
+            .accept_states
+            .iter()
+            .filter_map(|id| id_map[*id as usize])
+            .collect();
 
         for (old, new) in id_map.iter().enumerate().rev() {
-            if let None = new {
+            if new.is_none() {
                 self.states.remove(old);
             }
             // dbg!((old, new));
@@ -311,21 +513,57 @@ impl NFA {
         // 上面这些注释掉的代码不需要了。最后状态列表中应该只有一个接收状态，其他都是非空转移状态。
     }
 
-    fn remap_trans(&mut self, state: StateId, map: &Vec<Option<StateId>>) {
-        if let State::NonEpsilon(ref mut trans) = &mut self.states[state as usize] {
-            trans.0 = trans
-                .iter()
-                .map(|(input, to)| (*input, map[*to as usize].expect("map to a fail state")))
-                .collect();
+    fn remap_trans(&mut self, state: StateId, map: &[Option<StateId>]) {
+        match &mut self.states[state as usize] {
+            State::NonEpsilon(ref mut trans) => {
+                trans.0 = trans
+                    .iter()
+                    .map(|(input, to)| (*input, map[*to as usize].expect("map to a fail state")))
+                    .collect();
+            }
+            State::Epsilon(ref mut trans) => {
+                trans.0 = trans
+                    .iter()
+                    .map(|to| map[*to as usize].expect("map to a fail state"))
+                    .collect();
+            }
+            State::Fail | State::Final => (),
+        }
+    }
+
+    /// 删除所有`Fail`状态，并重新编号其他状态。
+    ///
+    /// 指向`Fail`状态的转移（包括空转移）会先被删掉，因为走到`Fail`状态的路径一定不会被接受，所以语言不变。
+    /// 空转移也会被重新编号，所以对还带有空转移的NFA调用也是安全的。
+    /// 开始状态和接受状态的编号跟着改变，是`Fail`状态的接受状态被丢弃。
+    /// 如果开始状态本身就是`Fail`状态，它会被替换成一个没有转移的空转移状态保留下来，这时NFA不接受任何字符串。
+    pub fn prune_fail_states(&mut self) {
+        let is_fail: Vec<bool> = self
+            .states
+            .iter()
+            .map(|state| matches!(state, State::Fail))
+            .collect();
+        for state in self.states.iter_mut() {
+            match state {
+                State::NonEpsilon(trans) => trans.0.retain(|(_, to)| !is_fail[*to as usize]),
+                State::Epsilon(trans) => trans.0.retain(|to| !is_fail[*to as usize]),
+                State::Fail | State::Final => (),
+            }
+        }
+        if let Some(start) = self.start_state {
+            if is_fail[start as usize] {
+                self.states[start as usize] = State::new_epsilon();
+            }
         }
+        self.remap_states();
     }
 }
 
 /// 一些开发时的测试
 impl NFA {
     pub fn test_print_alphabet(&self) {
-        for ele in &self.alphabet {
-            println!("{}", *ele as char);
+        for ele in self.sorted_alphabet() {
+            println!("{}", ele as char);
         }
     }
 
@@ -345,46 +583,187 @@ impl NFA {
     }
 }
 
+/// 确定化
+impl NFA {
+    /// 用子集构造法直接把这个NFA确定化为稠密DFA，不经过`DFA01`。
+    ///
+    /// `DFA01`用`u128`的位掩码表示NFA状态子集，所以只能处理01字母表和不超过128个状态的NFA。
+    /// 这里用排好序的`Vec<StateId>`表示子集，并用HashMap给每个子集分配DFA状态id，
+    /// 字母表就是NFA自己的字母表，NFA可以带空转移。
+    ///
+    /// 空子集是0号陷阱状态，开始状态的空闭包是1号状态（空闭包为空时开始状态就是陷阱状态），
+    /// 其余子集按照发现的顺序编号。返回的DFA没有极小化。
+    pub fn determinize(&self) -> DenseDFA {
+        self.determinize_with_limit(self.sorted_alphabet(), usize::MAX)
+            .expect("no state limit")
+    }
+
+    /// 在给定的字母表上确定化，DFA的状态数超过`limit`时返回错误。
+    ///
+    /// 字母表可以比NFA自己的字母表大，多出来的字符都转移到陷阱状态。
+    pub(crate) fn determinize_with_limit(
+        &self,
+        alphabet: Vec<u8>,
+        limit: usize,
+    ) -> Result<DenseDFA, String> {
+        let accepts: HashSet<StateId> = self.accept_states.iter().cloned().collect();
+
+        // 稠密DFA的状态id是u128。
+        let mut ids: HashMap<Vec<StateId>, u128> = HashMap::new();
+        let mut subsets: Vec<Vec<StateId>> = vec![Vec::new()];
+        ids.insert(Vec::new(), 0);
+        let start = self.epsilon_closure_of(self.start_state.into_iter());
+        let start_id = *ids.entry(start.clone()).or_insert_with(|| {
+            subsets.push(start);
+            1
+        });
+
+        let mut table: Vec<Vec<u128>> = Vec::new();
+        let mut next = 0;
+        while next < subsets.len() {
+            let mut row = Vec::with_capacity(alphabet.len());
+            for input in alphabet.iter() {
+                let targets = subsets[next].iter().flat_map(|state| {
+                    self.deltas(*state)
+                        .into_iter()
+                        .filter(|(symbol, _)| symbol == input)
+                        .flat_map(|(_, to)| to)
+                });
+                let target = self.epsilon_closure_of(targets);
+                let id = match ids.get(&target) {
+                    Some(id) => *id,
+                    None => {
+                        if subsets.len() >= limit {
+                            return Err(format!(
+                                "too many states: the DFA has more than {} states",
+                                limit
+                            ));
+                        }
+                        let id = subsets.len() as u128;
+                        ids.insert(target.clone(), id);
+                        subsets.push(target);
+                        id
+                    }
+                };
+                row.push(id);
+            }
+            table.push(row);
+            next += 1;
+        }
+
+        let accept_states: Vec<u128> = subsets
+            .iter()
+            .enumerate()
+            .filter(|(_, subset)| subset.iter().any(|state| accepts.contains(state)))
+            .map(|(id, _)| id as u128)
+            .collect();
+        Ok(DenseDFA::from_table(
+            alphabet,
+            &table,
+            start_id,
+            &accept_states,
+        ))
+    }
+
+    /// 一组状态的空闭包，从小到大排列。
+    fn epsilon_closure_of(&self, states: impl Iterator<Item = StateId>) -> Vec<StateId> {
+        let mut closure = HashSet::new();
+        let mut stack: Vec<StateId> = states.collect();
+        while let Some(state) = stack.pop() {
+            if !closure.insert(state) {
+                continue;
+            }
+            if let State::Epsilon(trans) = &self.states[state as usize] {
+                stack.extend(trans.iter().filter(|to| !closure.contains(*to)));
+            }
+        }
+        closure.into_iter().sorted().collect()
+    }
+}
+
 /// 格式化输出相关方法
 impl NFA {
+    /// 消除空转移，返回等价的不带空转移的NFA。和`Builder::new().build_non_epsilon_nfa(self)`相同。
+    ///
+    /// 这是从正则表达式到DFA的流程中的中间一步，单独拿出来可以把它画成状态图。
+    pub fn remove_epsilon(&self) -> Result<NFA, String> {
+        Builder::new().build_non_epsilon_nfa(self)
+    }
+
+    /// 先消除空转移，再生成dot。用于和原来的NFA的状态图对比。
+    pub fn epsilon_free_dot(&self) -> Result<String, String> {
+        Ok(self.remove_epsilon()?.to_dot())
+    }
+
     /// 此方法由copilot生成，👍
     /// 生成dot文件，可以由graphviz生成状态机图
     pub fn to_dot(&self) -> String {
+        self.to_dot_opts(&NfaDotOptions::default())
+    }
+
+    /// 按照给定的选项生成dot。默认选项的输出和`to_dot`相同。
+    pub fn to_dot_opts(&self, options: &NfaDotOptions) -> String {
+        let epsilon_attrs = if options.dashed_epsilon {
+            "label=\"ε\", style=dashed, color=gray"
+        } else {
+            "label=\"ε\""
+        };
         let mut dot = String::new();
         dot.push_str("digraph {\n");
         dot.push_str("rankdir=LR;\n");
         // dot.push_str("size=\"8,5\";\n");
         dot.push_str("node [shape = doublecircle];\n");
-        for state in &self.accept_states {
+        for state in self.accept_states.iter().sorted().dedup() {
             dot.push_str(&format!("{};\n", state));
         }
         dot.push_str("node [shape = circle];\n");
         for (id, state) in self.states.iter().enumerate() {
             match state {
+                // 空转移按到达状态排序，重复的只画一条。
                 State::Epsilon(trans) => {
-                    for to in trans.iter() {
-                        dot.push_str(&format!("{} -> {} [label=\"ε\"];\n", id, to))
+                    if options.hide_epsilon {
+                        continue;
+                    }
+                    for to in trans.iter().sorted().dedup() {
+                        dot.push_str(&format!("{} -> {} [{}];\n", id, to, epsilon_attrs))
                     }
                 }
+                // 到达同一个状态的多个字符合并成一条边，字符排好序用逗号连接。
+                // 比如字符类`[0-1]`得到的状态读0和读1都到同一个状态，只画一条标签为`0,1`的边。
                 State::NonEpsilon(trans) => {
-                    for (input, to) in trans.iter() {
-                        dot.push_str(&format!(
-                            "{} -> {} [label=\"{}\"];\n",
-                            id, to, *input as char
-                        ))
+                    let edges = trans
+                        .iter()
+                        .map(|(input, to)| (*to, *input))
+                        .sorted()
+                        .dedup()
+                        .group_by(|(to, _)| *to);
+                    for (to, group) in edges.into_iter() {
+                        let labels = group
+                            .map(|(_, input)| (input as char).to_string())
+                            .join(",");
+                        dot.push_str(&format!("{} -> {} [label=\"{}\"];\n", id, to, labels))
                     }
                 }
 
                 State::Final | State::Fail => {}
             }
         }
-        dot.push_str("}");
+        dot.push('}');
         dot
     }
 }
 
+/// `NFA::to_dot_opts`使用的选项。默认和`to_dot`一样，空转移画成标签为`ε`的实线。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NfaDotOptions {
+    /// 把空转移画成灰色的虚线，和读字符的转移区分开。
+    pub dashed_epsilon: bool,
+    /// 完全不画空转移，只看读字符的转移构成的“骨架”。
+    pub hide_epsilon: bool,
+}
+
 /// NFA的状态。
-/// 
+///
 /// 有四种类型：
 /// 1. Epsilon，只能添加空转移的状态。
 /// 2. NonEpsilon，只能添加非空转移的状态。
@@ -395,6 +774,7 @@ impl NFA {
 /// 这么做的目的是为了方便后续计算空闭包。
 /// 另外，NoWayOut类状态可以用作接收状态或者陷阱状态。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum State {
     Epsilon(EpsilonTrans),
     NonEpsilon(NonEpsilonTrans),
@@ -406,20 +786,22 @@ pub enum State {
 
 /// 空转移表。
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EpsilonTrans(Vec<StateId>);
 
 impl EpsilonTrans {
-    pub fn iter(&self) -> std::slice::Iter<StateId> {
+    pub fn iter(&self) -> std::slice::Iter<'_, StateId> {
         self.0.iter()
     }
 }
 
 /// 非空转移表。
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NonEpsilonTrans(Vec<(u8, StateId)>);
 
 impl NonEpsilonTrans {
-    pub fn iter(&self) -> std::slice::Iter<(u8, StateId)> {
+    pub fn iter(&self) -> std::slice::Iter<'_, (u8, StateId)> {
         self.0.iter()
     }
 }
@@ -439,9 +821,9 @@ impl State {
 }
 
 /// NFA的构造器。
-/// 
+///
 /// 有两个功能，一个是从正则表达式构造NFA，一个是从已有的NFA构造不带空转移的NFA。
-/// 
+///
 /// thompson 构造法从正则表达式构造NFA，有两种思路：
 ///
 /// 1. 自底向上，先构造子NFA，记录每一个子NFA的开始和接受状态，然后把子NFA合并成一个大NFA。
@@ -454,6 +836,9 @@ impl State {
 pub struct Builder {
     nfa: NFA,
     stack: Vec<Hole>,
+
+    // 如果指定了字母表，字符类只会展开成字母表里的字符。
+    alphabet: Option<HashSet<u8>>,
 }
 
 /// 用于创建NFA时使用的栈的单个栈帧，aka“空穴”。
@@ -461,28 +846,64 @@ pub struct Builder {
 /// 然后在离开这个节点时，将子节点需要的栈帧压入栈中。
 #[derive(Debug)]
 enum Hole {
-    Alternation { come_from: StateId, go_to: StateId },
-    Concatenation { come_from: StateId, go_to: StateId },
-    Repetition { come_from: StateId, go_to: StateId },
+    Alternation {
+        come_from: StateId,
+        go_to: StateId,
+    },
+    Concatenation {
+        come_from: StateId,
+        go_to: StateId,
+    },
+    /// `looping`为true时子NFA可以重复多次，也就是要添加从子NFA的结束节点到开始节点的空转移。
+    Repetition {
+        come_from: StateId,
+        go_to: StateId,
+        looping: bool,
+    },
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Builder {
     /// 初始化NFA构造器。
+    ///
+    /// 没有指定字母表时，`.`、`[^0]`这样的宽字符类只展开成0、1和正则表达式里出现的其他字符，
+    /// 例如`(0|1).`里的`.`只展开成0和1，不会产生255个转移。`[0-9]`这样的窄字符类照常展开。
     pub fn new() -> Builder {
         Builder {
             nfa: NFA::init_empty(),
             stack: Vec::new(),
+            alphabet: None,
+        }
+    }
+
+    /// 初始化一个限定了字母表的NFA构造器。
+    ///
+    /// 正则表达式里的字符类（例如`[0-9]`）只会展开成给定字母表中的字符，
+    /// 所以`[\x00-\xff]`这样的大范围也最多只会产生字母表大小那么多的转移。
+    pub fn with_alphabet(alphabet: &[u8]) -> Builder {
+        Builder {
+            alphabet: Some(alphabet.iter().cloned().collect()),
+            ..Builder::new()
         }
     }
 
     /// 从正则表达式构造NFA。
-    pub fn build_nfa_from_re(mut self, re: &String) -> Result<NFA, String> {
+    pub fn build_nfa_from_re(mut self, re: &str) -> Result<NFA, String> {
         let hir = ParserBuilder::new()
             .unicode(false)
             .utf8(false)
             .build()
             .parse(re)
-            .unwrap();
+            .map_err(|e| e.to_string())?;
+        let hir = hir::visit(&hir, Unroller::default())?;
+        if self.alphabet.is_none() {
+            self.alphabet = hir::visit(&hir, RelevantSymbols::default())?;
+        }
         // parse(re).unwrap();
         // let start = self.nfa.add_epsilon_state();
         let end = self.nfa.add_fail_state();
@@ -506,18 +927,15 @@ impl Builder {
     pub fn build_non_epsilon_nfa(mut self, old_nfa: &NFA) -> Result<NFA, String> {
         // 第一步，将状态转移函数dalta转换成dalta_hat
 
-        
-
         // 首先将原NFA中的状态全部添加到新NFA中。
         for state_id in 0..old_nfa.states.len() {
             let trans = old_nfa.get_dalta_hat_transitions(state_id as StateId);
             if trans.is_empty() {
-                if old_nfa.accept_states.contains(&(state_id as StateId)) {
+                if old_nfa.accept_states().contains(&(state_id as StateId)) {
                     self.nfa.add_final_state();
                 } else {
                     self.nfa.add_fail_state();
                 }
-                println!("empty {}", state_id);
             } else {
                 self.nfa.add_non_epsilon_state();
                 // 如果一边添加状态一边添加转移函数，最后不得不进行复杂的删除陷阱状态的步骤。
@@ -528,6 +946,21 @@ impl Builder {
             }
         }
 
+        let old_start = old_nfa.start_state().unwrap();
+        let old_accept = old_nfa.accept_states()[0];
+        let (closuer, _) = old_nfa.epsilon_closure_and_dalta(old_start);
+        let start_accepts = closuer.contains(&old_accept);
+
+        // 开始状态没有dalta_hat转移时在上面被当成了Fail状态，例如`()`和`a{0}`，但开始状态不能被删掉。
+        // 它的空闭包能到达接受状态时就是一个接受状态，否则和`prune_fail_states`一样保留成没有转移的空转移状态。
+        if let State::Fail = self.nfa.states[old_start as usize] {
+            self.nfa.states[old_start as usize] = if start_accepts {
+                State::new_final()
+            } else {
+                State::new_epsilon()
+            };
+        }
+
         // 然后把原NFA的所有状态转移函数dalta转化为dalta_hat并添加到新NFA中。
         for state_id in 0..old_nfa.states.len() {
             if let State::NonEpsilon(_) = &self.nfa.states[state_id] {
@@ -541,14 +974,9 @@ impl Builder {
             }
         }
 
-        let old_start = old_nfa.start_state.unwrap();
-        let old_accept = old_nfa.accept_states[0];
-        
         self.nfa.set_start_state(old_start);
         self.nfa.set_accept_state(old_accept);
-
-        let (closuer, _) = old_nfa.epsilon_closure_and_dalta(old_start);
-        if closuer.contains(&old_accept) {
+        if start_accepts {
             self.nfa.set_accept_state(old_start);
         }
 
@@ -581,11 +1009,163 @@ impl Builder {
     }
 }
 
+/// 把Builder不能直接构造的有界重复`{n,m}`展开成连接，例如`0{2,3}`展开成`000?`，`0{2,}`展开成`000*`。
+///
+/// 和Builder一样借助regex_syntax的visit方法遍历AST，用栈保存已经改写好的子节点，
+/// 所以不会递归。重复次数很大时展开的结果只是很宽的连接，也不会让AST变深。
+///
+/// 嵌套的重复会相乘，例如`(a{1000}){1000}`展开之后有一百万份。所以同时记录每个子节点展开后的大小
+/// （大约是它会产生的NFA状态数），超过`MAX_UNROLLED_SIZE`时在复制之前就返回错误。
+#[derive(Default)]
+struct Unroller {
+    // 每个正在访问的节点对应一个栈帧，保存它已经改写好的子节点和子节点的大小。
+    stack: Vec<Vec<(Hir, usize)>>,
+}
+
+/// 展开有界重复之后，正则表达式最大的大小。
+const MAX_UNROLLED_SIZE: usize = 100_000;
+
+impl regex_syntax::hir::Visitor for Unroller {
+    type Output = Hir;
+    type Err = String;
+
+    fn start(&mut self) {
+        self.stack.push(Vec::new());
+    }
+
+    fn visit_pre(&mut self, _hir: &Hir) -> Result<(), Self::Err> {
+        self.stack.push(Vec::new());
+        Ok(())
+    }
+
+    fn visit_post(&mut self, hir: &Hir) -> Result<(), Self::Err> {
+        let subs = self.stack.pop().ok_or("stack is empty")?;
+        let size = 1 + subs.iter().map(|(_, size)| *size).sum::<usize>();
+        let mut subs = subs.into_iter().map(|(sub, _)| sub).collect::<Vec<_>>();
+        let (rewritten, size) = match hir.kind() {
+            Literal(literal) => (hir.clone(), literal.0.len()),
+            Empty | Class(_) | Look(_) => (hir.clone(), 1),
+            Concat(_) => (Hir::concat(subs), size),
+            Alternation(_) => (Hir::alternation(subs), size),
+            Capture(capture) => (
+                Hir::capture(hir::Capture {
+                    index: capture.index,
+                    name: capture.name.clone(),
+                    sub: Box::new(subs.pop().ok_or("capture without sub expression")?),
+                }),
+                size,
+            ),
+            Repetition(r) => {
+                let sub = subs.pop().ok_or("repetition without sub expression")?;
+                match (r.min, r.max) {
+                    // *、+、?由Builder直接构造
+                    (0, None) | (1, None) | (0, Some(1)) => (
+                        Hir::repetition(hir::Repetition {
+                            sub: Box::new(sub),
+                            ..r.clone()
+                        }),
+                        size,
+                    ),
+                    (min, max) => {
+                        let number_of_copies = max.unwrap_or(min).max(min) as usize + 1;
+                        let size = (size - 1).saturating_mul(number_of_copies);
+                        if size > MAX_UNROLLED_SIZE {
+                            return Err(format!(
+                                "repetition is too large: unrolling it would produce about {} states, at most {} are supported",
+                                size, MAX_UNROLLED_SIZE
+                            ));
+                        }
+                        let optional = |looping: bool| {
+                            Hir::repetition(hir::Repetition {
+                                min: 0,
+                                max: if looping { None } else { Some(1) },
+                                greedy: r.greedy,
+                                sub: Box::new(sub.clone()),
+                            })
+                        };
+                        let mut copies = vec![sub.clone(); min as usize];
+                        match max {
+                            None => copies.push(optional(true)),
+                            Some(max) => copies.extend((min..max).map(|_| optional(false))),
+                        }
+                        (Hir::concat(copies), size)
+                    }
+                }
+            }
+        };
+        self.stack
+            .last_mut()
+            .ok_or("stack is empty")?
+            .push((rewritten, size));
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Self::Output, Self::Err> {
+        self.stack
+            .pop()
+            .and_then(|mut root| root.pop())
+            .map(|(root, _)| root)
+            .ok_or_else(|| "stack is empty".to_string())
+    }
+}
+
+/// 没有指定字母表时，收集正则表达式里“有关”的字符，用来限制宽字符类的展开。
+///
+/// 字面量和窄的字符类（例如`[0-9]`）里的字符，以及默认字母表{0,1}都是有关的。
+/// 超过一半字节的字符类（例如`.`、`[^0]`、`[\x00-\xff]`）是宽的，只展开成有关的字符。
+/// 正则表达式里没有宽字符类时不需要限制。
+#[derive(Default)]
+struct RelevantSymbols {
+    symbols: HashSet<u8>,
+    has_wide_class: bool,
+}
+
+impl RelevantSymbols {
+    /// 超过这么多字节的字符类是宽的。
+    const WIDE_CLASS: usize = 128;
+}
+
+impl regex_syntax::hir::Visitor for RelevantSymbols {
+    type Output = Option<HashSet<u8>>;
+    type Err = String;
+
+    fn visit_pre(&mut self, hir: &Hir) -> Result<(), Self::Err> {
+        let bytes: Vec<u8> = match hir.kind() {
+            Literal(literal) => literal.0.to_vec(),
+            Class(hir::Class::Bytes(range_set)) => range_set
+                .iter()
+                .flat_map(|range| range.start()..=range.end())
+                .collect(),
+            Class(hir::Class::Unicode(range_set)) => range_set
+                .iter()
+                .flat_map(|range| range.start() as u32..=range.end() as u32)
+                .filter(|c| *c <= u8::MAX as u32)
+                .map(|c| c as u8)
+                .collect(),
+            _ => return Ok(()),
+        };
+        if matches!(hir.kind(), Class(_)) && bytes.len() > Self::WIDE_CLASS {
+            self.has_wide_class = true;
+        } else {
+            self.symbols.extend(bytes);
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Self::Output, Self::Err> {
+        if !self.has_wide_class {
+            return Ok(None);
+        }
+        self.symbols.extend(b"01");
+        Ok(Some(self.symbols))
+    }
+}
+
 /// 实现正则语法树的Visitor trait。
-/// 
+///
 /// regex_syntax包的visit方法会深度优先地遍历AST，每访问一个节点，就会调用visit_pre方法。
 /// 访问完一个节点的所有子节点之后，会调用visit_post方法。
-/// 
+///
 /// 完成遍历后，会调用finish方法，返回构造结果。
 impl regex_syntax::hir::Visitor for Builder {
     type Output = NFA;
@@ -609,7 +1189,9 @@ impl regex_syntax::hir::Visitor for Builder {
                 (come_from, go_to)
             }
             Some(Hole::Alternation { come_from, go_to })
-            | Some(Hole::Repetition { come_from, go_to }) => (come_from, go_to),
+            | Some(Hole::Repetition {
+                come_from, go_to, ..
+            }) => (come_from, go_to),
             None => return Err("stack is empty".to_string()),
         };
 
@@ -648,7 +1230,7 @@ impl regex_syntax::hir::Visitor for Builder {
                 let mut iter = literal.0.iter().peekable();
                 for _ in 0..len {
                     let c = iter.next().unwrap();
-                    if let Some(_) = iter.peek() {
+                    if iter.peek().is_some() {
                         let new_state = self.nfa.add_non_epsilon_state();
                         self.nfa.add_transition(current, *c, new_state);
                         current = new_state;
@@ -666,37 +1248,56 @@ impl regex_syntax::hir::Visitor for Builder {
             Class(class) => {
                 let start = self.nfa.add_non_epsilon_state();
 
+                // 如果指定了字母表，就跳过不在字母表里的字符，不然一个[\x00-\xff]就会产生256个转移。
                 macro_rules! add_range_trans {
-                    ($range_set:expr, $start:expr, $end:expr, $nfa:expr) => {
+                    ($range_set:expr, $start:expr, $end:expr, $nfa:expr, $alphabet:expr) => {
                         for range in $range_set.iter() {
                             for c in range.start()..=range.end() {
-                                $nfa.add_transition($start, c as u8, $end);
+                                if $alphabet.as_ref().map_or(true, |a| a.contains(&(c as u8))) {
+                                    $nfa.add_transition($start, c as u8, $end);
+                                }
                             }
                         }
                     };
                 }
                 match class {
                     hir::Class::Bytes(range_set) => {
-                        add_range_trans!(range_set, start, end, self.nfa)
+                        add_range_trans!(range_set, start, end, self.nfa, self.alphabet)
                     }
 
                     hir::Class::Unicode(range_set) => {
-                        add_range_trans!(range_set, start, end, self.nfa)
+                        add_range_trans!(range_set, start, end, self.nfa, self.alphabet)
                     }
                 }
                 start
             }
 
-            //重复，即闭包操作符*。regex_syntax包还支持正闭包+、非贪婪闭包*?、非贪婪正闭包+?等其他重复语法。
+            //重复，包括克林闭包*、正闭包+和可选?。有界重复{n,m}已经被Unroller展开成了连接。
+            //非贪婪的*?、+?只影响匹配的位置，不影响语言，所以和贪婪的一样处理。
             Repetition(r) => {
-                // 我们只用克林闭包操作符*。如果出现了别的情况，说明输入的RE有错误，直接panic！
-                assert!(r.greedy && r.min == 0 && r.max.is_none());
+                // 至少重复0次时可以跳过子NFA，也就是从开始节点直接空转移到结束节点；
+                // 没有上限时子NFA可以重复多次。
+                let (skippable, looping) = match (r.min, r.max) {
+                    (0, None) => (true, true),
+                    (1, None) => (false, true),
+                    (0, Some(1)) => (true, false),
+                    (min, max) => {
+                        return Err(format!(
+                            "unsupported repetition: {{{},{}}}",
+                            min,
+                            max.map_or(String::new(), |max| max.to_string())
+                        ))
+                    }
+                };
 
                 let start = self.nfa.add_epsilon_state();
-                self.nfa.add_epsilon_transition(start, end);
+                if skippable {
+                    self.nfa.add_epsilon_transition(start, end);
+                }
                 self.stack.push(Hole::Repetition {
                     come_from: start,
                     go_to: end,
+                    looping,
                 });
                 start
             }
@@ -722,17 +1323,16 @@ impl regex_syntax::hir::Visitor for Builder {
         };
 
         // 第四步，收尾工作，将子NFA的填入“空穴”中。
-        // 如果这个“空穴”代表闭包操作符*的子NFA，还需要添加一个从子NFA的结束节点到开始节点的空转移。
+        // 如果这个“空穴”代表闭包的子NFA，还需要添加一个从子NFA的结束节点到开始节点的空转移。
         self.nfa.add_epsilon_transition(come_from, start);
         // self.nfa.add_epsilon_transition(end, go_to);
 
         match hole {
-            Some(Hole::Repetition {
-                come_from: _,
-                go_to: _,
-            }) => {
+            Some(Hole::Repetition { looping, .. }) => {
                 self.nfa.add_epsilon_transition(end, go_to);
-                self.nfa.add_epsilon_transition(end, start);
+                if looping {
+                    self.nfa.add_epsilon_transition(end, start);
+                }
             }
             Some(Hole::Alternation {
                 come_from: _,
@@ -769,7 +1369,6 @@ impl regex_syntax::hir::Visitor for Builder {
         if let Some(Hole::Concatenation { come_from, go_to }) = &self.stack.pop() {
             self.nfa.add_epsilon_transition(*come_from, *go_to);
         }
-        dbg!(&self.stack);
         Ok(self.nfa)
     }
 }
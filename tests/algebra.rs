@@ -0,0 +1,97 @@
+//! Test suite for the `Automaton` algebra.
+
+use wasm_fa::algebra::Automaton;
+use wasm_fa::dfa::{CompletedDfa, DenseDFA};
+
+fn regex(re: &str) -> Automaton {
+    Automaton::from_regex(re).unwrap()
+}
+
+fn binary_strings(max_len: usize) -> Vec<String> {
+    let mut strings = vec![String::new()];
+    for len in 1..=max_len {
+        for value in 0..(1u32 << len) {
+            strings.push(format!("{:0width$b}", value, width = len));
+        }
+    }
+    strings
+}
+
+#[test]
+fn union_star_minimize() {
+    let dfa = regex("0*")
+        .union(regex("1*"))
+        .unwrap()
+        .star()
+        .minimize()
+        .to_dfa();
+    // (0*|1*)* 就是所有01串
+    for input in binary_strings(6) {
+        assert!(dfa.accepts(&input), "{:?}", input);
+    }
+    assert!(dfa.equivalent(&wasm_fa::re_to_dfa("(0|1)*").unwrap()));
+}
+
+#[test]
+fn composed_language() {
+    // 以1开头的偶数长度串，连接上不含“11”的串，并且整体不以0结尾
+    let even = regex("((0|1)(0|1))*");
+    let starts_with_1 = regex("1(0|1)*");
+    let no_11 = regex("(0|10)*(1|)");
+    let ends_with_0 = regex("(0|1)*0");
+    let language = even
+        .intersect(starts_with_1)
+        .unwrap()
+        .concat(no_11)
+        .difference(ends_with_0)
+        .unwrap()
+        .minimize();
+
+    let expected = |input: &str| {
+        !input.ends_with('0')
+            && (2..=input.len()).step_by(2).any(|split| {
+                let (head, tail) = input.split_at(split);
+                head.starts_with('1') && !tail.contains("11")
+            })
+    };
+    for input in binary_strings(10) {
+        assert_eq!(language.accepts(&input), expected(&input), "{:?}", input);
+    }
+    assert!(language.as_dfa().is_minimal());
+}
+
+#[test]
+fn concat_and_star_keep_alphabets() {
+    let a = Automaton::from(DenseDFA::from_table(
+        b"ab".to_vec(),
+        &[vec![0, 0], vec![2, 0], vec![0, 0]],
+        1,
+        &[2],
+    ));
+    let c = regex("0");
+    let ac = a.clone().concat(c).to_dfa();
+    assert!(ac.accepts("a0"));
+    assert!(!ac.accepts("a"));
+    let mut alphabet = ac.alphabet().clone();
+    alphabet.sort_unstable();
+    assert_eq!(alphabet, b"0ab".to_vec());
+
+    let star = a.star();
+    assert!(star.accepts(""));
+    assert!(star.accepts("aaa"));
+    assert!(!star.accepts("b"));
+    assert_eq!(star.as_dfa().alphabet(), &b"ab".to_vec());
+}
+
+#[test]
+fn complement_and_equivalence() {
+    let odd = regex("(0|1)((0|1)(0|1))*");
+    let even = regex("((0|1)(0|1))*");
+    assert!(odd.clone().complement().equivalent(&even));
+    assert!(!odd.equivalent(&even));
+}
+
+#[test]
+fn from_regex_reports_syntax_errors() {
+    assert!(Automaton::from_regex("(0|1").is_err());
+}
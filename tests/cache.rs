@@ -0,0 +1,58 @@
+//! Test suite for `CachingConverter`.
+#![cfg(feature = "cache")]
+
+use wasm_fa::cache::CachingConverter;
+
+#[test]
+fn second_conversion_is_a_hit() {
+    let converter = CachingConverter::new(4);
+    let first = converter.convert("(0|1)*1").unwrap();
+    assert_eq!((converter.hits(), converter.misses()), (0, 1));
+    let second = converter.convert("(0|1)*1").unwrap();
+    assert_eq!((converter.hits(), converter.misses()), (1, 1));
+    assert!(first.equivalent(&second));
+    assert_eq!(converter.len(), 1);
+}
+
+#[test]
+fn least_recently_used_is_evicted() {
+    let converter = CachingConverter::new(2);
+    converter.convert("0").unwrap();
+    converter.convert("1").unwrap();
+    // 用一次"0"，"1"就成了最久没用的
+    converter.convert("0").unwrap();
+    converter.convert("01").unwrap();
+    assert_eq!(converter.len(), 2);
+    assert_eq!(converter.hits(), 1);
+
+    converter.convert("0").unwrap();
+    assert_eq!(converter.hits(), 2);
+    converter.convert("1").unwrap();
+    assert_eq!(converter.hits(), 2);
+    assert_eq!(converter.misses(), 4);
+}
+
+#[test]
+fn zero_capacity_never_caches() {
+    let converter = CachingConverter::new(0);
+    converter.convert("0*").unwrap();
+    converter.convert("0*").unwrap();
+    assert_eq!(converter.hits(), 0);
+    assert!(converter.is_empty());
+}
+
+#[test]
+fn shared_between_threads() {
+    let converter = std::sync::Arc::new(CachingConverter::new(8));
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let converter = converter.clone();
+            std::thread::spawn(move || converter.convert("(01)*").unwrap().accepts("0101"))
+        })
+        .collect();
+    for handle in handles {
+        assert!(handle.join().unwrap());
+    }
+    assert_eq!(converter.hits() + converter.misses(), 4);
+    assert_eq!(converter.len(), 1);
+}
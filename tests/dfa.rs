@@ -0,0 +1,2646 @@
+//! Test suite for the DFA construction and operations.
+
+use wasm_fa::dfa::minimize::PartitionError;
+use wasm_fa::dfa::state_elimination::EliminationOrder;
+use wasm_fa::dfa::{
+    AlphabetError, CompletedDfa, DenseDFA, EditError, FmtOptions, MatchOptions, Partiality,
+    ProductError, RejectReason,
+};
+
+/// 一个有冗余状态的DFA，接受长度至少为2的01串。q2和q3是不可区分的。
+fn redundant_dfa() -> DenseDFA {
+    DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[
+            vec![0, 0],
+            vec![2, 3],
+            vec![4, 4],
+            vec![4, 4],
+            vec![4, 4],
+        ],
+        1,
+        &[4],
+    )
+}
+
+/// 长度不超过`max_len`的所有01串。
+fn binary_strings(max_len: usize) -> Vec<String> {
+    let mut strings = vec![String::new()];
+    let mut last = vec![String::new()];
+    for _ in 0..max_len {
+        last = last
+            .iter()
+            .flat_map(|s| vec![format!("{}0", s), format!("{}1", s)])
+            .collect();
+        strings.extend(last.iter().cloned());
+    }
+    strings
+}
+
+/// 在所有长度不超过`max_len`的01串上比较两个DFA。
+fn agree_on_binary_strings(a: &DenseDFA, b: &DenseDFA, max_len: usize) -> bool {
+    binary_strings(max_len)
+        .iter()
+        .all(|s| a.accepts(s) == b.accepts(s))
+}
+
+#[test]
+fn minimize_and_report_groups() {
+    let (minimized, groups) = redundant_dfa().minimize_and_report();
+    assert_eq!(minimized.number_of_states(), 4);
+    assert_eq!(groups.len(), 4);
+
+    let mut sorted_groups = groups.clone();
+    sorted_groups.sort();
+    assert_eq!(sorted_groups, vec![vec![0], vec![1], vec![2, 3], vec![4]]);
+
+    // 每个新状态吸收的原状态，应该和新状态的身份一致。
+    let start = minimized.start_state() as usize;
+    assert_eq!(groups[start], vec![1]);
+    for accept in minimized.accept_states() {
+        assert_eq!(groups[*accept as usize], vec![4]);
+    }
+}
+
+#[test]
+fn minimize_and_report_already_minimal() {
+    let dfa = wasm_fa::re_to_dfa("(01)*").unwrap();
+    let (minimized, groups) = dfa.minimize_and_report();
+    assert_eq!(minimized.number_of_states(), dfa.number_of_states());
+    assert!(groups.iter().enumerate().all(|(id, group)| group == &vec![id as u128]));
+}
+
+#[test]
+fn minimize_merges_every_indistinguishable_pair() {
+    // 0* 的极小DFA只有陷阱状态和一个接受状态。
+    assert_eq!(wasm_fa::re_to_dfa("0*").unwrap().number_of_states(), 2);
+    assert_eq!(wasm_fa::re_to_dfa("(0|1)*").unwrap().number_of_states(), 2);
+}
+
+#[test]
+fn dfa01_keeps_unreachable_trap_state() {
+    use wasm_fa::dfa::DFA01;
+    use wasm_fa::nfa::Builder;
+
+    // (0|1)*不会走到空子集，但是0号陷阱状态仍然要保留，
+    // 否则转换成稠密DFA之后，0号状态就成了接受所有字符串的开始状态。
+    let nfa = Builder::new().build_nfa_from_re("(0|1)*").unwrap();
+    let nfa = Builder::new().build_non_epsilon_nfa(&nfa).unwrap();
+    let sparse = DFA01::build_dfa_from_nfa(&nfa);
+    assert_eq!(sparse.trap_state(), Some(0));
+    assert!(sparse.state_ids().contains(&0));
+
+    let dense = DenseDFA::build_from_sparse01_dfa(&sparse);
+    assert_ne!(dense.start_state(), 0);
+    assert!(!dense.accept_states().contains(&0));
+    assert_eq!(dense.delta(0, b'0'), 0);
+    assert_eq!(dense.delta(0, b'1'), 0);
+    assert!(dense.accepts("0110"));
+}
+
+#[test]
+fn accepts_batch() {
+    let dfa = wasm_fa::re_to_dfa("(01)*").unwrap();
+    let inputs = ["", "01", "0101", "0", "10", "011", "012"];
+    assert_eq!(
+        dfa.accepts_all_of(&inputs),
+        vec![true, true, true, false, false, false, false]
+    );
+
+    let (accepted, rejected) = dfa.partition_inputs(&inputs);
+    assert_eq!(accepted, vec!["", "01", "0101"]);
+    assert_eq!(rejected, vec!["0", "10", "011", "012"]);
+}
+
+#[test]
+fn to_dot_is_deterministic() {
+    let dot = wasm_fa::re_to_dfa("(0|1)*(00|11)(0|1)*").unwrap().to_dot();
+    for _ in 0..10 {
+        // 每次重新构造，HashSet的迭代顺序都可能不同。
+        assert_eq!(
+            wasm_fa::re_to_dfa("(0|1)*(00|11)(0|1)*").unwrap().to_dot(),
+            dot
+        );
+    }
+
+    let dfa = DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[vec![0, 0], vec![2, 3], vec![4, 4], vec![4, 4], vec![4, 4]],
+        1,
+        &[4, 2, 3],
+    );
+    assert!(dfa.to_dot().contains("node [shape = doublecircle];\n2;\n3;\n4;\n"));
+}
+
+#[test]
+fn restrict_to_alphabet() {
+    // (0|1|2)*2，也就是以2结尾的串。
+    let dfa = DenseDFA::from_table(
+        vec![b'0', b'1', b'2'],
+        &[vec![0, 0, 0], vec![1, 1, 2], vec![1, 1, 2]],
+        1,
+        &[2],
+    );
+    let restricted = dfa.restrict_to_alphabet(b"10").unwrap();
+    assert_eq!(restricted.alphabet(), &vec![b'0', b'1']);
+    assert!(restricted
+        .accepts_all_of(&["", "0", "01", "012", "2"])
+        .iter()
+        .all(|a| !a));
+
+    // (0|1|2)*，限制到{0,1}之后就是(0|1)*。
+    let dfa = DenseDFA::from_table(
+        vec![b'0', b'1', b'2'],
+        &[vec![0, 0, 0], vec![1, 1, 1]],
+        1,
+        &[1],
+    );
+    let restricted = dfa.restrict_to_alphabet(b"01").unwrap();
+    assert_eq!(
+        restricted.accepts_all_of(&["", "0110", "012"]),
+        vec![true, true, false]
+    );
+
+    assert_eq!(
+        dfa.restrict_to_alphabet(b"03").err(),
+        Some(AlphabetError::UnknownSymbol(b'3'))
+    );
+}
+
+#[test]
+fn find_pumpable_run() {
+    let dfa = wasm_fa::re_to_dfa("1(01)*0*").unwrap();
+    let pump = dfa.find_pumpable_run(5).unwrap();
+    assert!(!pump.y.is_empty());
+    assert!(pump.pump(1).len() >= 5);
+    for k in 0..6 {
+        let word = String::from_utf8(pump.pump(k)).unwrap();
+        assert!(dfa.accepts(&word), "{} should be accepted", word);
+    }
+    let after_x = pump
+        .x
+        .iter()
+        .fold(dfa.start_state(), |state, input| dfa.delta(state, *input));
+    let after_xy = pump
+        .y
+        .iter()
+        .fold(after_x, |state, input| dfa.delta(state, *input));
+    assert_eq!(after_x, pump.repeated_state);
+    assert_eq!(after_xy, pump.repeated_state);
+
+    // 有限语言没有可以泵的字符串。
+    assert_eq!(
+        wasm_fa::re_to_dfa("0|01|011").unwrap().find_pumpable_run(0),
+        None
+    );
+}
+
+#[test]
+fn nonempty_witness() {
+    let dfa = wasm_fa::re_to_dfa("1(0|1)*00").unwrap();
+    let witness = dfa.nonempty_witness().unwrap();
+    assert_eq!(witness, b"100".to_vec());
+    assert!(dfa.accepts(&String::from_utf8(witness).unwrap()));
+    assert_eq!(
+        wasm_fa::re_to_dfa("(01)*").unwrap().nonempty_witness(),
+        Some(vec![])
+    );
+
+    // 接受状态不可达，语言是空的。
+    let empty = DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[vec![0, 0], vec![0, 0], vec![2, 2]],
+        1,
+        &[2],
+    );
+    assert_eq!(empty.nonempty_witness(), None);
+}
+
+#[test]
+fn to_dot_merges_parallel_edges() {
+    let dot = redundant_dfa().to_dot();
+    assert!(dot.contains("2 -> 4 [label = \"0,1\"];\n"));
+    assert!(dot.contains("1 -> 2 [label = \"0\"];\n"));
+    assert!(dot.contains("1 -> 3 [label = \"1\"];\n"));
+    assert_eq!(dot.matches("2 -> 4").count(), 1);
+    // 陷阱状态仍然不画出来。
+    assert!(!dot.contains("-> 0 "));
+}
+
+#[test]
+fn accepts_case_insensitive() {
+    // 只接受 abc 的DFA。
+    let dfa = DenseDFA::from_table(
+        vec![b'a', b'b', b'c'],
+        &[
+            vec![0, 0, 0],
+            vec![2, 0, 0],
+            vec![0, 3, 0],
+            vec![0, 0, 4],
+            vec![0, 0, 0],
+        ],
+        1,
+        &[4],
+    );
+    let options = MatchOptions {
+        case_insensitive: true,
+    };
+    assert!(!dfa.accepts("ABC"));
+    assert!(!dfa.accepts_with("ABC", &MatchOptions::default()));
+    assert!(dfa.accepts_with("ABC", &options));
+    assert!(dfa.accepts_with("aBc", &options));
+    assert!(!dfa.accepts_with("ABCD", &options));
+
+    // 字母表里是大写字母时，小写的输入也能匹配
+    let upper = DenseDFA::from_table(
+        vec![b'A', b'b'],
+        &[vec![0, 0], vec![2, 0], vec![0, 2]],
+        1,
+        &[2],
+    );
+    assert!(upper.accepts_with("abB", &options));
+    assert!(upper.accepts_with("Ab", &options));
+    assert!(!upper.accepts_with("ab", &MatchOptions::default()));
+}
+
+#[test]
+fn quotient_by_accept_split_is_minimize() {
+    let dfa = redundant_dfa();
+    let quotient = dfa.quotient_by(&[vec![0, 1, 2, 3], vec![4]]).unwrap();
+    let minimized = dfa.minimize();
+    assert_eq!(quotient.number_of_states(), minimized.number_of_states());
+    assert!(agree_on_binary_strings(&quotient, &minimized, 6));
+
+    // 比接受/非接受更粗的划分会改变语言，返回错误
+    assert_eq!(
+        dfa.quotient_by(&[vec![0, 1, 2, 3, 4]]).err(),
+        Some(PartitionError::MixedAcceptance {
+            accepting: 4,
+            rejecting: 0
+        })
+    );
+    let dfa01 = wasm_fa::re_to_dfa("0*1").unwrap();
+    let all_states: Vec<u128> = (0..dfa01.number_of_states()).collect();
+    assert!(matches!(
+        dfa01.quotient_by(&[all_states]),
+        Err(PartitionError::MixedAcceptance { .. })
+    ));
+
+    assert_eq!(
+        dfa.quotient_by(&[vec![0, 1, 2, 3]]).err(),
+        Some(PartitionError::MissingState(4))
+    );
+    assert_eq!(
+        dfa.quotient_by(&[vec![0, 1, 2, 3, 4], vec![4]]).err(),
+        Some(PartitionError::DuplicateState(4))
+    );
+    assert_eq!(
+        dfa.quotient_by(&[vec![0, 1, 2, 3, 4, 9]]).err(),
+        Some(PartitionError::UnknownState(9))
+    );
+}
+
+#[test]
+fn length_count_diff() {
+    let all_zeros = wasm_fa::re_to_dfa("0*").unwrap();
+    let even_zeros = wasm_fa::re_to_dfa("(00)*").unwrap();
+    assert_eq!(all_zeros.count_accepted_of_length(3), 1);
+    assert_eq!(
+        wasm_fa::re_to_dfa("(0|1)*")
+            .unwrap()
+            .count_accepted_of_length(10),
+        1024
+    );
+    assert_eq!(
+        all_zeros.length_count_diff(&even_zeros, 4),
+        Ok(vec![(0, 0), (1, 1), (2, 0), (3, 1), (4, 0)])
+    );
+
+    let unary = DenseDFA::from_table(vec![b'a'], &[vec![0], vec![1]], 1, &[1]);
+    assert!(matches!(
+        all_zeros.length_count_diff(&unary, 4),
+        Err(AlphabetError::Mismatch { .. })
+    ));
+}
+
+/// 只依赖trait object，同时适用于两种DFA。
+fn describe(dfa: &dyn CompletedDfa<Alphabet = Vec<u8>>) -> (Vec<bool>, Option<Vec<u8>>, usize) {
+    let accepted = ["", "0", "01", "011", "10", "0111", "2"]
+        .iter()
+        .map(|s| dfa.accepts(s))
+        .collect();
+    let productions = dfa.to_rg().lines().count();
+    (accepted, dfa.shortest_accepted(), productions)
+}
+
+#[test]
+fn completed_dfa_trait_object() {
+    use wasm_fa::dfa::DFA01;
+    use wasm_fa::nfa::Builder;
+
+    let nfa = Builder::new().build_nfa_from_re("01*").unwrap();
+    let nfa = Builder::new().build_non_epsilon_nfa(&nfa).unwrap();
+    let sparse = DFA01::build_dfa_from_nfa(&nfa);
+    let dense = wasm_fa::re_to_dfa("01*").unwrap();
+
+    let (accepted, shortest, productions) = describe(&sparse);
+    assert_eq!(accepted, vec![false, true, true, true, false, true, false]);
+    assert_eq!(shortest, Some(b"0".to_vec()));
+    assert_eq!(describe(&dense), (accepted, shortest, productions));
+}
+
+/// 比较两个稠密DFA的结构是否完全相同。
+fn same_structure(a: &DenseDFA, b: &DenseDFA) -> bool {
+    a.number_of_states() == b.number_of_states()
+        && a.alphabet() == b.alphabet()
+        && a.start_state() == b.start_state()
+        && a.accept_states() == b.accept_states()
+        && (0..a.number_of_states()).all(|state| {
+            a.alphabet()
+                .iter()
+                .all(|input| a.delta(state, *input) == b.delta(state, *input))
+        })
+}
+
+#[test]
+fn into_sparse01_round_trip() {
+    let dense = wasm_fa::re_to_dfa("(0|1)*01(0|1)").unwrap();
+    let sparse = dense.into_sparse01().unwrap();
+    assert_eq!(sparse.number_of_states(), dense.number_of_states());
+    assert_eq!(sparse.start_state(), dense.start_state());
+    let round_trip = DenseDFA::build_from_sparse01_dfa(&sparse);
+    assert!(same_structure(&dense, &round_trip));
+
+    let abc = DenseDFA::from_table(vec![b'a', b'b'], &[vec![0, 0], vec![1, 1]], 1, &[1]);
+    assert!(matches!(
+        abc.into_sparse01(),
+        Err(AlphabetError::Mismatch { .. })
+    ));
+}
+
+#[test]
+fn distance_from_start() {
+    // 一条链：q1 -0-> q2 -0-> q3 -0-> q4，读1都进入陷阱状态，q5不可达。
+    let chain = DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[
+            vec![0, 0],
+            vec![2, 0],
+            vec![3, 0],
+            vec![4, 0],
+            vec![4, 0],
+            vec![1, 1],
+        ],
+        1,
+        &[4],
+    );
+    let distance = chain.distance_from_start();
+    assert_eq!(distance[&1], 0);
+    assert_eq!(distance[&0], 1);
+    assert_eq!(distance[&2], 1);
+    assert_eq!(distance[&3], 2);
+    assert_eq!(distance[&4], 3);
+    assert!(!distance.contains_key(&5));
+}
+
+mod generated {
+    include!("fixtures/ends_with_01.rs");
+}
+
+#[test]
+fn to_rust_source() {
+    // 以01结尾的01串。
+    let dfa = DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[vec![0, 0], vec![2, 1], vec![2, 3], vec![2, 1]],
+        1,
+        &[3],
+    );
+    let source = dfa.to_rust_source("ends_with_01");
+    assert_eq!(source, include_str!("fixtures/ends_with_01.rs"));
+    assert!(!source.contains("wasm_fa"));
+
+    for input in binary_strings(6)
+        .iter()
+        .map(|s| s.as_str())
+        .chain(["012", "a"])
+    {
+        assert_eq!(
+            generated::ends_with_01_accepts(input.as_bytes()),
+            dfa.accepts(input),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn symbols_reaching() {
+    let dfa = wasm_fa::re_to_dfa("(0|1)*01(0|1)").unwrap();
+    for to in 0..dfa.number_of_states() {
+        let mut expected = Vec::new();
+        for from in 0..dfa.number_of_states() {
+            for input in dfa.alphabet() {
+                if dfa.delta(from, *input) == to {
+                    expected.push((from, *input));
+                }
+            }
+        }
+        assert_eq!(dfa.symbols_reaching(to), expected);
+    }
+    assert_eq!(redundant_dfa().symbols_reaching(3), vec![(1, b'1')]);
+}
+
+#[test]
+fn is_prefix_closed() {
+    assert!(wasm_fa::re_to_dfa("0*").unwrap().is_prefix_closed());
+    assert!(wasm_fa::re_to_dfa("(0|1)*").unwrap().is_prefix_closed());
+    assert!(wasm_fa::re_to_dfa("1*0*").unwrap().is_prefix_closed());
+    assert!(!wasm_fa::re_to_dfa("01").unwrap().is_prefix_closed());
+    assert!(!wasm_fa::re_to_dfa("(01)*").unwrap().is_prefix_closed());
+}
+
+#[test]
+fn reverse_to_dfa() {
+    let reversed = wasm_fa::re_to_dfa("01*").unwrap().reverse_to_dfa();
+    assert!(agree_on_binary_strings(
+        &reversed,
+        &wasm_fa::re_to_dfa("1*0").unwrap(),
+        8
+    ));
+
+    let reversed = wasm_fa::re_to_dfa("(0|1)*011|1*").unwrap().reverse_to_dfa();
+    assert!(agree_on_binary_strings(
+        &reversed,
+        &wasm_fa::re_to_dfa("110(0|1)*|1*").unwrap(),
+        8
+    ));
+}
+
+#[test]
+fn to_fmt_output_with_custom_markers() {
+    let dfa = redundant_dfa();
+    let options = FmtOptions {
+        accept: 'F',
+        start: '>',
+        none: "-",
+    };
+    let output = dfa.to_fmt_output_with(&options);
+    assert_eq!(
+        output,
+        dfa.to_fmt_output()
+            .replace('*', "F")
+            .replace('#', ">")
+            .replace('N', "-")
+    );
+    assert!(output.contains(">q1\t"));
+    assert!(output.contains("Fq4\t"));
+    assert!(!output.contains('*') && !output.contains('#') && !output.contains('N'));
+}
+
+#[test]
+fn to_fmt_output_uses_the_alphabet() {
+    // 以a结尾：开始状态读a到达接受状态，接受状态读b回到开始状态
+    let dfa = wasm_fa::re_to_dfa("(a|b)*a").unwrap();
+    let start = dfa.start_state();
+    let accept = dfa.delta(start, b'a');
+    let expected = format!(
+        "\ta\tb\n#q{s}\tq{a}\tq{s}\t\n*q{a}\tq{a}\tq{s}\t\n",
+        s = start,
+        a = accept
+    );
+    let output = dfa.to_string();
+    let mut rows: Vec<&str> = output.lines().skip(1).collect();
+    let mut expected_rows: Vec<&str> = expected.lines().skip(1).collect();
+    rows.sort_unstable();
+    expected_rows.sort_unstable();
+    assert!(output.starts_with("\ta\tb\n"));
+    assert_eq!(rows, expected_rows);
+
+    // 字母表是{0,1}时表头不变
+    assert!(wasm_fa::re_to_dfa("1*")
+        .unwrap()
+        .to_string()
+        .starts_with("\t0\t1\n"));
+}
+
+#[test]
+fn edit_distance_to_language() {
+    // 以01结尾的字符串
+    let dfa = wasm_fa::re_to_dfa("(0|1)*01").unwrap();
+    assert_eq!(dfa.edit_distance_to_language(b"1101"), 0);
+    assert_eq!(dfa.edit_distance_to_language(b"0"), 1);
+    assert_eq!(dfa.edit_distance_to_language(b""), 2);
+    assert_eq!(dfa.edit_distance_to_language(b"1"), 1);
+    assert_eq!(dfa.edit_distance_to_language(b"10"), 1);
+    assert_eq!(dfa.edit_distance_to_language(b"111"), 1);
+    // 不在字母表里的字符只能删掉或者替换
+    assert_eq!(dfa.edit_distance_to_language(b"0a1"), 1);
+
+    // 恰好是00
+    let dfa = wasm_fa::re_to_dfa("00").unwrap();
+    assert_eq!(dfa.edit_distance_to_language(b"1111"), 4);
+    assert_eq!(dfa.edit_distance_to_language(b"010"), 1);
+
+    // 空语言
+    let empty = DenseDFA::from_table(vec![b'0', b'1'], &[vec![0, 0], vec![0, 0]], 1, &[]);
+    assert_eq!(empty.edit_distance_to_language(b"01"), usize::MAX);
+}
+
+/// 两个字符串之间的编辑距离，用来检验`nearest_accepted`给出的字符串。
+fn levenshtein(a: &[u8], b: &[u8]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut diagonal = row[0];
+        row[0] = i;
+        for j in 1..=b.len() {
+            let substitution = diagonal + if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            diagonal = row[j];
+            row[j] = substitution.min(row[j] + 1).min(row[j - 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[test]
+fn nearest_accepted() {
+    let dfa = wasm_fa::re_to_dfa("(01)*").unwrap();
+    // 0101和01都只差两次编辑，01的字典序更小
+    assert_eq!(dfa.edit_distance_to_language(b"0011"), 2);
+    assert_eq!(dfa.nearest_accepted(b"0011"), Some(b"01".to_vec()));
+    assert_eq!(dfa.nearest_accepted(b"0101"), Some(b"0101".to_vec()));
+    assert_eq!(dfa.nearest_accepted(b"011"), Some(b"01".to_vec()));
+
+    let dfa = wasm_fa::re_to_dfa("(0|1)*01").unwrap();
+    for input in binary_strings(5) {
+        let nearest = dfa.nearest_accepted(input.as_bytes()).unwrap();
+        assert!(dfa.accepts(std::str::from_utf8(&nearest).unwrap()));
+        assert_eq!(
+            levenshtein(input.as_bytes(), &nearest),
+            dfa.edit_distance_to_language(input.as_bytes()),
+            "{:?} -> {:?}",
+            input,
+            nearest
+        );
+    }
+
+    let empty = DenseDFA::from_table(vec![b'0', b'1'], &[vec![0, 0], vec![0, 0]], 1, &[]);
+    assert_eq!(empty.nearest_accepted(b"01"), None);
+}
+
+#[test]
+fn transition_coverage() {
+    // 非陷阱状态q1..q4，一共8个转移
+    let dfa = redundant_dfa();
+    // "00"经过 q1-0->q2, q2-0->q4
+    assert_eq!(dfa.transition_coverage(&["00"]), 2.0 / 8.0);
+    assert_eq!(
+        dfa.uncovered_transitions(&["00"]),
+        vec![
+            (1, b'1'),
+            (2, b'1'),
+            (3, b'0'),
+            (3, b'1'),
+            (4, b'0'),
+            (4, b'1')
+        ]
+    );
+
+    let all = ["000", "011", "10", "11"];
+    assert_eq!(dfa.transition_coverage(&all), 1.0);
+    assert!(dfa.uncovered_transitions(&all).is_empty());
+    assert_eq!(dfa.transition_coverage(&[]), 0.0);
+}
+
+#[test]
+fn minimize_drops_unreachable_accept_state() {
+    // q1 -0-> q2（接受），q3是不可达的接受状态，并且和q2的转移完全一样。
+    let dfa = DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[vec![0, 0], vec![2, 0], vec![2, 0], vec![2, 0]],
+        1,
+        &[2, 3],
+    );
+    let (minimized, groups) = dfa.minimize_and_report();
+    assert_eq!(minimized.number_of_states(), 3);
+    assert_eq!(groups, vec![vec![0], vec![1], vec![2]]);
+    assert!(agree_on_binary_strings(&dfa, &minimized, 6));
+
+    // 不可达的接受状态也不会阻止其他状态的合并
+    let dfa = DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[
+            vec![0, 0],
+            vec![2, 3],
+            vec![4, 4],
+            vec![4, 4],
+            vec![4, 4],
+            vec![1, 1],
+        ],
+        1,
+        &[4, 5],
+    );
+    let minimized = dfa.minimize();
+    assert_eq!(minimized.number_of_states(), 4);
+    assert_eq!(minimized.accept_states().len(), 1);
+}
+
+#[test]
+fn find_matches() {
+    let dfa = wasm_fa::re_to_dfa("01").unwrap();
+    assert_eq!(dfa.longest_accepting_prefix(b"011"), Some(2));
+    assert_eq!(dfa.longest_accepting_prefix(b"10"), None);
+    assert_eq!(dfa.find_matches(b"0101"), vec![(0, 2), (2, 4)]);
+    assert_eq!(dfa.find_matches(b"1001a01"), vec![(2, 4), (5, 7)]);
+
+    // 最左最长：01*在0111中只匹配一次，匹配整个字符串
+    let dfa = wasm_fa::re_to_dfa("01*").unwrap();
+    assert_eq!(dfa.find_matches(b"0111"), vec![(0, 4)]);
+    assert_eq!(dfa.find_matches(b"00"), vec![(0, 1), (1, 2)]);
+
+    // 空串不算匹配
+    let dfa = wasm_fa::re_to_dfa("1*").unwrap();
+    assert_eq!(dfa.longest_accepting_prefix(b"0"), Some(0));
+    assert_eq!(dfa.find_matches(b"0110"), vec![(1, 3)]);
+}
+
+#[test]
+fn degrees() {
+    // q1 -0-> q2, q1 -1-> q3, q2/q3/q4 都只转移到q4
+    let dfa = redundant_dfa();
+    assert_eq!(dfa.out_degree(0), 0);
+    assert_eq!(dfa.out_degree(1), 2);
+    assert_eq!(dfa.out_degree(4), 2);
+    assert_eq!(dfa.in_degree(1), 0);
+    assert_eq!(dfa.in_degree(2), 1);
+    assert_eq!(dfa.in_degree(4), 6);
+    assert_eq!(dfa.in_degree(0), 2);
+
+    let histogram: Vec<(usize, usize)> = dfa.degree_histogram().into_iter().collect();
+    assert_eq!(histogram, vec![(2, 1), (3, 2), (8, 1)]);
+}
+
+/// 能被3整除的二进制数。
+fn divisible_by_3() -> DenseDFA {
+    DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[vec![0, 0], vec![1, 2], vec![3, 1], vec![2, 3]],
+        1,
+        &[1],
+    )
+}
+
+#[test]
+fn to_regex_orders() {
+    let dfa = divisible_by_3();
+    for order in [
+        EliminationOrder::Index,
+        EliminationOrder::LowestDegreeFirst,
+        EliminationOrder::Custom(vec![3, 2, 1]),
+    ] {
+        let re = dfa.to_regex_with(order).unwrap();
+        assert!(
+            agree_on_binary_strings(&dfa, &wasm_fa::re_to_dfa(&re).unwrap(), 8),
+            "{}",
+            re
+        );
+    }
+
+    let empty = DenseDFA::from_table(vec![b'0', b'1'], &[vec![0, 0], vec![0, 0]], 1, &[]);
+    assert_eq!(empty.to_regex(), None);
+    let epsilon = DenseDFA::from_table(vec![b'0', b'1'], &[vec![0, 0], vec![0, 0]], 1, &[1]);
+    assert_eq!(epsilon.to_regex(), Some("()".to_string()));
+}
+
+#[test]
+fn to_regex_lowest_degree_first_is_shorter() {
+    // 倒数第三个字符是1，中间的状态度数很高
+    let dfa = wasm_fa::re_to_dfa("(0|1)*1(0|1)(0|1)").unwrap();
+    let by_index = dfa.to_regex_with(EliminationOrder::Index).unwrap();
+    let by_degree = dfa
+        .to_regex_with(EliminationOrder::LowestDegreeFirst)
+        .unwrap();
+    assert!(by_degree.len() < by_index.len());
+}
+
+#[test]
+fn accepts_empty_string() {
+    let star = wasm_fa::re_to_dfa("0*").unwrap();
+    assert!(star.accepts_empty_string());
+    assert!(star
+        .to_rg()
+        .starts_with(&format!("S -> q{} | ε\n", star.start_state())));
+
+    let plus = wasm_fa::re_to_dfa("00*").unwrap();
+    assert!(!plus.accepts_empty_string());
+    assert!(!plus.to_rg().contains('ε'));
+}
+
+#[test]
+fn re_to_dfa_with_alphabet() {
+    use wasm_fa::{re_to_dfa_with_alphabet, ConversionError};
+
+    let dfa = re_to_dfa_with_alphabet("0*", b"01").unwrap();
+    let mut alphabet = dfa.alphabet().clone();
+    alphabet.sort_unstable();
+    assert_eq!(alphabet, vec![b'0', b'1']);
+    for input in binary_strings(5) {
+        assert_eq!(dfa.accepts(&input), !input.contains('1'), "{:?}", input);
+    }
+
+    // 字母表里多出来的字符也只会被拒绝
+    let dfa = re_to_dfa_with_alphabet("(0|1)*1", b"012").unwrap();
+    assert!(dfa.accepts("0101"));
+    assert!(!dfa.accepts("021"));
+    assert_eq!(dfa.alphabet().len(), 3);
+
+    // 字母表变小之后，用不到的字符被删掉了
+    let dfa = re_to_dfa_with_alphabet("[0-1]*", b"0").unwrap();
+    assert_eq!(dfa.alphabet(), &vec![b'0']);
+    assert!(dfa.accepts("000"));
+
+    assert!(matches!(
+        re_to_dfa_with_alphabet("01", b"0"),
+        Err(ConversionError::UnknownSymbol(b'1'))
+    ));
+    assert!(matches!(
+        re_to_dfa_with_alphabet("(0", b"01"),
+        Err(ConversionError::Syntax(_))
+    ));
+}
+
+#[test]
+fn with_extended_alphabet() {
+    let dfa = redundant_dfa().with_extended_alphabet(b"12");
+    assert_eq!(dfa.alphabet(), &vec![b'0', b'1', b'2']);
+    assert!(dfa.accepts("00"));
+    assert!(!dfa.accepts("02"));
+    assert_eq!(dfa.delta(1, b'2'), 0);
+}
+
+#[test]
+fn accept_states_sorted() {
+    let dfa = DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[
+            vec![0, 0],
+            vec![5, 3],
+            vec![1, 1],
+            vec![2, 4],
+            vec![1, 1],
+            vec![1, 1],
+        ],
+        1,
+        &[5, 2, 4, 3],
+    );
+    for _ in 0..10 {
+        assert_eq!(dfa.clone().accept_states_sorted(), vec![2, 3, 4, 5]);
+    }
+    assert!(dfa.to_dot().contains("doublecircle];\n2;\n3;\n4;\n5;\n"));
+}
+
+#[test]
+fn reversal_invariance() {
+    assert!(wasm_fa::re_to_dfa("(0|1)*")
+        .unwrap()
+        .is_reversal_invariant());
+    assert!(wasm_fa::re_to_dfa("0(0|1)*0|1(0|1)*1|0|1")
+        .unwrap()
+        .is_reversal_invariant());
+    assert!(!wasm_fa::re_to_dfa("01*").unwrap().is_reversal_invariant());
+
+    let forward = wasm_fa::re_to_dfa("01*").unwrap();
+    assert!(wasm_fa::re_to_dfa("1*0")
+        .unwrap()
+        .reverse_language_equals(&forward));
+    assert!(!forward.reverse_language_equals(&forward));
+
+    assert!(forward.equivalent(&wasm_fa::re_to_dfa("0|01*1").unwrap()));
+    assert!(!forward.equivalent(&wasm_fa::re_to_dfa("01").unwrap()));
+    // 字母表不同的时候，多出来的字符两边都拒绝
+    assert!(forward.equivalent(&forward.with_extended_alphabet(b"2")));
+}
+
+#[test]
+fn fold_identical_states() {
+    // q2和q3的行完全相同
+    let dfa = redundant_dfa();
+    let folded = dfa.fold_identical_states();
+    assert_eq!(folded.number_of_states(), 4);
+    assert_eq!(folded.delta(0, b'0'), 0);
+    assert!(agree_on_binary_strings(&dfa, &folded, 6));
+
+    // 行不同但语言等价的状态不会被合并：q2 -> q3, q3 -> q2，都是接受状态
+    let dfa = DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[vec![0, 0], vec![2, 0], vec![3, 0], vec![2, 0]],
+        1,
+        &[2, 3],
+    );
+    assert_eq!(dfa.fold_identical_states().number_of_states(), 4);
+    assert_eq!(dfa.minimize().number_of_states(), 3);
+}
+
+#[test]
+fn transducer() {
+    use wasm_fa::dfa::transducer::DenseTransducer;
+
+    let mut transducer = DenseTransducer::new(wasm_fa::re_to_dfa("(0|1)*01").unwrap());
+    transducer.set_output_for_symbol(b'0', Some(b'a')).unwrap();
+    transducer.set_output_for_symbol(b'1', Some(b'b')).unwrap();
+    assert_eq!(transducer.transduce(b"1001"), Some(b"baab".to_vec()));
+    // 不被接受的输入没有输出
+    assert_eq!(transducer.transduce(b"10"), None);
+    assert_eq!(transducer.transduce(b"1021"), None);
+
+    // 只让开始状态读0时输出，其他转移不输出
+    let start = transducer.dfa().start_state();
+    transducer.set_output_for_symbol(b'0', None).unwrap();
+    transducer.set_output_for_symbol(b'1', None).unwrap();
+    transducer.set_output(start, b'0', Some(b'x')).unwrap();
+    assert_eq!(transducer.transduce(b"01"), Some(b"x".to_vec()));
+    assert_eq!(
+        transducer.set_output(start, b'2', None),
+        Err(AlphabetError::UnknownSymbol(b'2'))
+    );
+}
+
+#[test]
+fn growth_rate() {
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-3;
+    assert!(close(
+        wasm_fa::re_to_dfa("(0|1)*").unwrap().growth_rate(),
+        2.0
+    ));
+    assert!(close(
+        wasm_fa::re_to_dfa("(0|1)*01").unwrap().growth_rate(),
+        2.0
+    ));
+    assert!(close(wasm_fa::re_to_dfa("0*").unwrap().growth_rate(), 1.0));
+    assert!(close(
+        wasm_fa::re_to_dfa("(01)*").unwrap().growth_rate(),
+        1.0
+    ));
+    assert_eq!(wasm_fa::re_to_dfa("01|110").unwrap().growth_rate(), 0.0);
+    // 不含11的字符串，数量是斐波那契数列，增长率是黄金分割比
+    let golden = (1.0 + 5f64.sqrt()) / 2.0;
+    assert!(close(
+        wasm_fa::re_to_dfa("(0|10)*(1|())").unwrap().growth_rate(),
+        golden
+    ));
+
+    let matrix = redundant_dfa().to_transition_matrix();
+    assert_eq!(matrix[1], vec![0, 0, 1, 1, 0]);
+    assert_eq!(matrix[4], vec![0, 0, 0, 0, 2]);
+}
+
+#[test]
+fn intersect_many() {
+    let by_2 = DenseDFA::divisible_by(2, 2);
+    let by_3 = DenseDFA::divisible_by(3, 2);
+    let by_5 = DenseDFA::divisible_by(5, 2);
+    let by_30 = DenseDFA::intersect_many(&[&by_2, &by_3, &by_5]).unwrap();
+    for n in 0..300u32 {
+        assert_eq!(by_30.accepts(&format!("{:b}", n)), n % 30 == 0, "{}", n);
+    }
+    assert!(by_30.number_of_states() <= 31);
+    assert!(by_30.equivalent(&DenseDFA::divisible_by(30, 2)));
+
+    assert!(DenseDFA::intersect_many(&[&by_2])
+        .unwrap()
+        .equivalent(&by_2));
+    assert_eq!(
+        DenseDFA::intersect_many(&[]).err(),
+        Some(ProductError::NoOperands)
+    );
+}
+
+#[test]
+fn divisible_by() {
+    let by_3 = DenseDFA::divisible_by(3, 2);
+    assert!(by_3.accepts("110"));
+    assert!(!by_3.accepts("111"));
+    assert!(by_3.accepts(""));
+    assert!(by_3.accepts("00110"));
+    assert_eq!(by_3.number_of_states(), 4);
+
+    let by_7 = DenseDFA::divisible_by(7, 10);
+    assert_eq!(by_7.alphabet().len(), 10);
+    for n in 0..500u32 {
+        assert_eq!(by_7.accepts(&n.to_string()), n % 7 == 0, "{}", n);
+    }
+}
+
+#[test]
+fn accepts_reversed() {
+    for re in ["01*", "(0|1)*011", "(01)*1", "0|11"].iter() {
+        let dfa = wasm_fa::re_to_dfa(re).unwrap();
+        let reversed = dfa.reverse_to_dfa();
+        for input in binary_strings(7) {
+            assert_eq!(
+                dfa.accepts_reversed(input.as_bytes()),
+                reversed.accepts(&input),
+                "{} {:?}",
+                re,
+                input
+            );
+        }
+    }
+    assert!(!wasm_fa::re_to_dfa("01*").unwrap().accepts_reversed(b"1a0"));
+}
+
+#[test]
+fn to_dot_with_labels() {
+    let dfa = redundant_dfa();
+    let mut labels = std::collections::HashMap::new();
+    labels.insert(1, "{0}".to_string());
+    labels.insert(4, "{0,2,5}".to_string());
+    labels.insert(2, "say \"hi\"".to_string());
+    let dot = dfa.to_dot_with_labels(&labels);
+    assert!(dot.contains(
+        "node [shape = doublecircle];\n4 [label = \"{0,2,5}\"];\nnode [shape = circle];\n"
+    ));
+    assert!(dot.contains("1 [label = \"{0}\"];\n"));
+    assert!(dot.contains("2 [label = \"say \\\"hi\\\"\"];\n"));
+    assert!(dot.contains("3 [label = \"q3\"];\n"));
+    assert!(dot.contains("start -> 1;\n"));
+    assert!(!dot.contains("0 [label"));
+    // 边和to_dot一样
+    assert!(dot.ends_with(&dfa.to_dot()[dfa.to_dot().find("1 -> ").unwrap()..]));
+}
+
+#[test]
+fn normalize() {
+    // 以01结尾的规范极小DFA：1 -0-> 2, 2 -1-> 3
+    let expected = DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[vec![0, 0], vec![2, 1], vec![2, 3], vec![2, 1]],
+        1,
+        &[3],
+    );
+    assert!(same_structure(
+        &wasm_fa::re_to_dfa("(0|1)*01").unwrap().normalize(),
+        &expected
+    ));
+
+    // 同一个语言：字母表顺序相反、编号打乱、有重复的状态（4和5）、不可达状态（2和6）
+    let messy = DenseDFA::from_table(
+        vec![b'1', b'0'],
+        &[
+            vec![0, 0],
+            vec![3, 5],
+            vec![6, 6],
+            vec![3, 4],
+            vec![1, 5],
+            vec![1, 4],
+            vec![3, 2],
+        ],
+        3,
+        &[1],
+    );
+    assert!(same_structure(&messy.normalize(), &expected));
+
+    // 恰好是01，死状态3不是0号状态
+    let with_dead = DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[vec![0, 0], vec![2, 3], vec![3, 4], vec![3, 3], vec![3, 3]],
+        1,
+        &[4],
+    );
+    let exactly_01 = DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[vec![0, 0], vec![2, 0], vec![0, 3], vec![0, 0]],
+        1,
+        &[3],
+    );
+    assert_eq!(with_dead.trim().number_of_states(), 4);
+    assert!(same_structure(&with_dead.normalize(), &exactly_01));
+    assert!(same_structure(&expected.normalize(), &expected));
+
+    // 空语言
+    let empty = DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[vec![0, 0], vec![2, 2], vec![1, 1]],
+        1,
+        &[],
+    );
+    let normalized = empty.normalize();
+    assert_eq!(normalized.number_of_states(), 1);
+    assert_eq!(normalized.start_state(), 0);
+    assert!(normalized.accept_states().is_empty());
+}
+
+/// 转换过程不应该往stdout或stderr输出任何调试信息。
+///
+/// libtest会捕获测试的输出，所以这里用`--nocapture`重新运行当前的测试程序，
+/// 只执行`conversion_is_quiet`里的转换，然后检查两个标记之间的输出是否为空。
+#[test]
+fn conversion_is_quiet() {
+    const BEGIN: &str = "<<conversion begin>>";
+    const END: &str = "<<conversion end>>";
+
+    if std::env::var_os("WASM_FA_QUIET_CHILD").is_some() {
+        println!("{}", BEGIN);
+        eprintln!("{}", BEGIN);
+        let dfa = wasm_fa::re_to_dfa("(0|1)*01(1|00)*").unwrap();
+        let _ = dfa.to_string();
+        let _ = dfa.to_rg();
+        println!("{}", END);
+        eprintln!("{}", END);
+        return;
+    }
+
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args([
+            "conversion_is_quiet",
+            "--exact",
+            "--nocapture",
+            "--test-threads=1",
+        ])
+        .env("WASM_FA_QUIET_CHILD", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    for stream in [output.stdout, output.stderr].iter() {
+        let text = String::from_utf8_lossy(stream);
+        let begin = text.find(BEGIN).unwrap() + BEGIN.len();
+        let end = text.find(END).unwrap();
+        assert_eq!(text[begin..end].trim(), "");
+    }
+}
+
+#[test]
+fn product_limit() {
+    let by_101 = DenseDFA::divisible_by(101, 2);
+    let by_103 = DenseDFA::divisible_by(103, 2);
+    let and = |left: bool, right: bool| left && right;
+    assert_eq!(
+        by_101.product_with_limit(&by_103, and, 1000).err(),
+        Some(ProductError::ProductTooLarge { reached: 1001 })
+    );
+    // 上限足够大的时候正常构造
+    let product = by_101.product_with_limit(&by_103, and, 20000).unwrap();
+    assert_eq!(product.number_of_states(), 101 * 103 + 1);
+    assert!(product.accepts(&format!("{:b}", 101 * 103 * 3)));
+}
+
+#[test]
+fn union_and_intersection() {
+    let ends_in_1 = wasm_fa::re_to_dfa("(0|1)*1").unwrap();
+    let ends_in_0 = wasm_fa::re_to_dfa("(0|1)*0").unwrap();
+    let nonempty = ends_in_1.union(&ends_in_0).unwrap();
+    for input in binary_strings(6) {
+        assert_eq!(nonempty.accepts(&input), !input.is_empty(), "{:?}", input);
+    }
+
+    let contains_01 = wasm_fa::re_to_dfa("(0|1)*01(0|1)*").unwrap();
+    let even = wasm_fa::re_to_dfa("((0|1)(0|1))*").unwrap();
+    let both = contains_01.intersection_strict(&even).unwrap();
+    for input in binary_strings(7) {
+        assert_eq!(
+            both.accepts(&input),
+            input.contains("01") && input.len() % 2 == 0,
+            "{:?}",
+            input
+        );
+    }
+
+    assert!(both.equivalent(&contains_01.intersect(&even).unwrap()));
+    let strict = ends_in_1.union_strict(&ends_in_0).unwrap();
+    assert!(strict.equivalent(&nonempty));
+
+    // 带_strict后缀的运算不会自动扩展字母表
+    let zeros = DenseDFA::from_table(vec![b'0'], &[vec![0], vec![1]], 1, &[1]);
+    let mismatch = Some(ProductError::Alphabet(AlphabetError::Mismatch {
+        left: vec![b'0'],
+        right: vec![b'0', b'1'],
+    }));
+    assert_eq!(zeros.intersection_strict(&even).err(), mismatch);
+    assert_eq!(zeros.union_strict(&even).err(), mismatch);
+    assert!(zeros.intersect(&even).is_ok());
+    assert!(zeros.union(&even).is_ok());
+}
+
+#[test]
+fn difference_and_symmetric_difference() {
+    let any_one = wasm_fa::re_to_dfa("(0|1)*1(0|1)*").unwrap();
+    let ends_in_1 = wasm_fa::re_to_dfa("(0|1)*1").unwrap();
+    let difference = any_one.difference(&ends_in_1).unwrap();
+    for input in binary_strings(6) {
+        assert_eq!(
+            difference.accepts(&input),
+            input.contains('1') && input.ends_with('0'),
+            "{:?}",
+            input
+        );
+    }
+    assert_eq!(
+        ends_in_1.difference(&any_one).unwrap().shortest_accepted(),
+        None
+    );
+
+    // 和自己的极小化的对称差为空
+    let dfa = redundant_dfa();
+    let symmetric = dfa.symmetric_difference(&dfa.minimize()).unwrap();
+    assert_eq!(symmetric.shortest_accepted(), None);
+    let symmetric = any_one.symmetric_difference(&ends_in_1).unwrap();
+    assert_eq!(symmetric.shortest_accepted(), Some(b"10".to_vec()));
+}
+
+#[test]
+fn is_equivalent_checks_languages() {
+    let minimal = wasm_fa::re_to_dfa("(0|1)*01").unwrap();
+    let subset = wasm_fa::nfa::Builder::new()
+        .build_nfa_from_re("(0|1)*01")
+        .unwrap()
+        .determinize();
+    // 手写的DFA：1号状态是开始状态，2号读到了0，3号读到了01
+    let by_hand = DenseDFA::from_table(
+        vec![b'1', b'0'],
+        &[vec![0, 0], vec![1, 2], vec![3, 2], vec![1, 2]],
+        1,
+        &[3],
+    );
+    assert_eq!(minimal.is_equivalent(&subset), Ok(true));
+    assert_eq!(minimal.is_equivalent(&by_hand), Ok(true));
+    assert_eq!(by_hand.is_equivalent(&subset), Ok(true));
+
+    assert_eq!(minimal.is_equivalent(&minimal.complement()), Ok(false));
+    assert_eq!(
+        by_hand.is_equivalent(&wasm_fa::re_to_dfa("(0|1)*10").unwrap()),
+        Ok(false)
+    );
+}
+
+#[test]
+fn is_equivalent_requires_same_alphabet() {
+    let zeros = DenseDFA::from_table(vec![b'0'], &[vec![0], vec![1]], 1, &[1]);
+    let binary = wasm_fa::re_to_dfa("0*").unwrap();
+    assert_eq!(
+        zeros.is_equivalent(&binary),
+        Err(ProductError::Alphabet(AlphabetError::Mismatch {
+            left: vec![b'0'],
+            right: vec![b'0', b'1'],
+        }))
+    );
+    // equivalent会自动扩展字母表
+    assert!(zeros.equivalent(&binary));
+}
+
+#[test]
+fn product_widens_alphabets() {
+    // {0,1}上以1结尾的字符串，和{0,1,2}上含有至少一个2或者以1结尾的字符串
+    let binary = wasm_fa::re_to_dfa("(0|1)*1").unwrap();
+    let ternary = wasm_fa::re_to_dfa_with_alphabet("(0|1)*1", b"012")
+        .unwrap()
+        .with_extended_alphabet(b"2");
+    let with_two = DenseDFA::from_table(
+        vec![b'0', b'1', b'2'],
+        &[vec![0, 0, 0], vec![1, 1, 2], vec![2, 2, 2]],
+        1,
+        &[2],
+    );
+    let either = ternary.product_with(&with_two, |a, b| a || b).unwrap();
+
+    let both = binary.intersect(&either).unwrap();
+    let mut alphabet = both.alphabet().clone();
+    alphabet.sort_unstable();
+    assert_eq!(alphabet, b"012".to_vec());
+    assert!(both.accepts("0101"));
+    // 含有2的字符串被{0,1}那一边拒绝
+    assert!(!both.accepts("21"));
+    assert!(!both.accepts("0121"));
+    assert!(either.accepts("0120"));
+
+    let union = binary.product_with(&with_two, |a, b| a || b).unwrap();
+    assert!(union.accepts("20"));
+    assert!(union.accepts("01"));
+    assert!(!union.accepts("10"));
+}
+
+#[test]
+fn factor_and_suffix_closed() {
+    let zeros = wasm_fa::re_to_dfa("0*").unwrap();
+    assert!(zeros.is_factor_closed());
+    assert!(zeros.is_suffix_closed());
+
+    let exact = wasm_fa::re_to_dfa("010").unwrap();
+    assert!(!exact.is_factor_closed());
+    assert!(!exact.is_suffix_closed());
+
+    // 不含11的字符串是因子封闭的
+    assert!(wasm_fa::re_to_dfa("(0|10)*(1|())")
+        .unwrap()
+        .is_factor_closed());
+
+    // 以1结尾的字符串（加上空串）是后缀封闭但不是前缀封闭的
+    let ends_with_1 = wasm_fa::re_to_dfa("((0|1)*1)|()").unwrap();
+    assert!(ends_with_1.is_suffix_closed());
+    assert!(!ends_with_1.is_prefix_closed());
+    assert!(!ends_with_1.is_factor_closed());
+
+    // 前缀封闭但不是后缀封闭
+    let prefixes = wasm_fa::re_to_dfa("(1(0|1)*)|()").unwrap();
+    assert!(prefixes.is_prefix_closed());
+    assert!(!prefixes.is_suffix_closed());
+}
+
+#[cfg(feature = "random")]
+#[test]
+fn minimize_random_is_idempotent() {
+    let mut rng = oorandom::Rand64::new(20240501);
+    for round in 0..200 {
+        let num_states = 1 + round % 12;
+        let random = DenseDFA::random(num_states, b"01", 0.3, &mut rng);
+        assert_eq!(random.number_of_states() as usize, num_states + 1);
+
+        let minimized = random.minimize();
+        assert!(minimized.is_minimal(), "round {}", round);
+        assert!(minimized.equivalent(&random));
+        assert!(agree_on_binary_strings(&random, &minimized, 6));
+    }
+}
+
+#[test]
+fn minimize_output_is_minimal() {
+    // 长度是6的倍数的串，用一个12个状态的环表示，每个状态都和环上相隔6的状态不可区分。
+    // 状态之间的区分要沿着环传播好几轮，递归的`distinguish`容易在这里出错。
+    let mut table = vec![vec![0, 0]];
+    for state in 1..=12 {
+        let next = state % 12 + 1;
+        table.push(vec![next, next]);
+    }
+    let dfa = DenseDFA::from_table(vec![b'0', b'1'], &table, 1, &[1, 7]);
+    assert!(!dfa.is_minimal());
+
+    let minimized = dfa.minimize();
+    assert!(minimized.is_minimal());
+    assert!(minimized.is_minimal());
+    assert_eq!(minimized.number_of_states(), 7);
+    assert!(agree_on_binary_strings(&dfa, &minimized, 13));
+}
+
+#[test]
+fn trait_accepts_agrees_across_representations() {
+    use wasm_fa::dfa::DFA01;
+    use wasm_fa::nfa::Builder;
+
+    for re in ["01*", "(0|1)*11", "0(10)*|1"] {
+        let nfa = Builder::new().build_nfa_from_re(re).unwrap();
+        let nfa = Builder::new().build_non_epsilon_nfa(&nfa).unwrap();
+        let sparse = DFA01::build_dfa_from_nfa(&nfa);
+        let dense = wasm_fa::re_to_dfa(re).unwrap();
+        assert_eq!(sparse.trap_state(), Some(0));
+        assert_eq!(dense.trap_state(), Some(0));
+
+        let mut inputs = binary_strings(6);
+        inputs.extend(["2", "0a", "110x"].iter().map(|s| s.to_string()));
+        for input in &inputs {
+            assert_eq!(
+                CompletedDfa::accepts(&sparse, input),
+                CompletedDfa::accepts(&dense, input),
+                "{} on {:?}",
+                re,
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn dfa01_to_dot_opts() {
+    use wasm_fa::dfa::{DotOptions, DFA01};
+    use wasm_fa::nfa::Builder;
+
+    let nfa = Builder::new().build_nfa_from_re("01*").unwrap();
+    let nfa = Builder::new().build_non_epsilon_nfa(&nfa).unwrap();
+    let dfa = DFA01::build_dfa_from_nfa(&nfa);
+    let dot = dfa.to_dot_opts(&DotOptions {
+        subset_labels: true,
+        ..DotOptions::default()
+    });
+    assert_eq!(
+        dot,
+        "digraph DFA {\n\
+         rankdir=LR;\n\
+         node [shape = doublecircle];\n\
+         5 [label = \"{0,2}\"];\n\
+         node [shape = circle];\n\
+         2 [label = \"{1}\"];\n\
+         start [shape = point];\n\
+         start -> 2;\n\
+         2 -> 5 [label = \"0\"];\n\
+         5 -> 5 [label = \"1\"];\n\
+         }\n"
+    );
+
+    // 显示陷阱状态时，陷阱状态自己的两条边合并成一条
+    let dot = dfa.to_dot_opts(&DotOptions {
+        show_trap: true,
+        ..DotOptions::default()
+    });
+    assert!(dot.contains("0 -> 0 [label = \"0,1\"];\n"));
+    assert!(dot.contains("2 -> 0 [label = \"1\"];\n"));
+    assert!(dot.contains("start -> 2;\n"));
+}
+
+#[test]
+fn language_contains_all_of_length() {
+    let dfa = wasm_fa::re_to_dfa("(0|1)(0|1)(0|1)").unwrap();
+    assert!(dfa.language_contains_all_of_length(3));
+    assert!(!dfa.language_contains_all_of_length(2));
+
+    let dfa = wasm_fa::re_to_dfa("(00|11)").unwrap();
+    assert!(!dfa.language_contains_all_of_length(2));
+
+    // 2^200 超出了u128，走不计数的分支
+    assert!(wasm_fa::re_to_dfa("(0|1)*")
+        .unwrap()
+        .language_contains_all_of_length(200));
+    assert!(!wasm_fa::re_to_dfa("(0|1)*1")
+        .unwrap()
+        .language_contains_all_of_length(200));
+}
+
+#[test]
+fn to_dot_clustered() {
+    // q2和q3不可区分，放在同一个框里；q1和q4各自一个框；陷阱状态不显示。
+    let dfa = redundant_dfa();
+    assert_eq!(
+        dfa.equivalence_classes(),
+        vec![vec![0], vec![1], vec![2, 3], vec![4]]
+    );
+    let dot = dfa.to_dot_clustered();
+    assert_eq!(dot.matches("subgraph cluster_").count(), 3);
+    assert!(dot.contains("label = \"{q2,q3}\";\n2 [shape = circle];\n3 [shape = circle];\n"));
+    assert!(dot.contains("4 [shape = doublecircle];\n"));
+
+    // 极小的DFA每个状态自成一类
+    let minimized = dfa.minimize();
+    assert_eq!(
+        minimized
+            .to_dot_clustered()
+            .matches("subgraph cluster_")
+            .count(),
+        minimized.number_of_states() as usize - 1
+    );
+}
+
+#[test]
+fn accepts_detailed() {
+    let dfa = wasm_fa::re_to_dfa("(01)*").unwrap();
+    assert_eq!(dfa.accepts_detailed(b""), Ok(()));
+    assert_eq!(dfa.accepts_detailed(b"0101"), Ok(()));
+    assert_eq!(
+        dfa.accepts_detailed(b"011"),
+        Err(RejectReason::FellIntoTrap { at: 2, byte: b'1' })
+    );
+    assert_eq!(
+        dfa.accepts_detailed(b"010"),
+        Err(RejectReason::EndedInNonAccepting {
+            final_state: dfa.delta(dfa.start_state(), b'0')
+        })
+    );
+    assert_eq!(
+        dfa.accepts_detailed(b"0a1"),
+        Err(RejectReason::UnknownSymbol { at: 1, byte: b'a' })
+    );
+    // 未知字符出现在陷阱状态之后时，先报告陷阱状态
+    assert_eq!(
+        dfa.accepts_detailed(b"1a"),
+        Err(RejectReason::FellIntoTrap { at: 0, byte: b'1' })
+    );
+}
+
+#[test]
+fn to_dot_opts_html_labels_are_escaped() {
+    use std::collections::HashMap;
+    use wasm_fa::dfa::DotOptions;
+
+    let dfa = redundant_dfa();
+    let mut details = HashMap::new();
+    details.insert(1, "<start> & \"q1\"".to_string());
+    details.insert(2, "{q2,q3}".to_string());
+    let dot = dfa.to_dot_opts(&DotOptions {
+        html_labels: true,
+        details: Some(&details),
+        ..DotOptions::default()
+    });
+    assert!(dot.contains(
+        "1 [label = <1<BR/><FONT POINT-SIZE=\"10\">&lt;start&gt; &amp; &quot;q1&quot;</FONT>>];\n"
+    ));
+    assert!(dot.contains("2 [label = <2<BR/><FONT POINT-SIZE=\"10\">{q2,q3}</FONT>>];\n"));
+    assert!(dot.contains("4 [label = <4>];\n"));
+
+    // 不打开html_labels时details不起作用
+    let plain = dfa.to_dot_opts(&DotOptions {
+        details: Some(&details),
+        ..DotOptions::default()
+    });
+    assert!(!plain.contains("label = <"));
+}
+
+#[test]
+fn minimize_long_association_chain() {
+    // 一条长链 1 -> 2 -> ... -> n，只有n是接受状态。填表时(k, k+1)关联到(k+1, k+2)，
+    // 直到最后(n-1, n)被区分，才沿着整条关联链一路标记回(1, 2)。
+    // 在很小的栈上运行，递归实现的distinguish会栈溢出。
+    let n = 1000;
+    let mut table = vec![vec![0]];
+    for state in 1..n {
+        table.push(vec![state + 1]);
+    }
+    table.push(vec![0]);
+    let dfa = DenseDFA::from_table(vec![b'a'], &table, 1, &[n]);
+
+    let minimal = std::thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(move || dfa.is_minimal())
+        .unwrap()
+        .join()
+        .unwrap();
+    assert!(minimal);
+}
+
+#[test]
+fn minimize_many_associated_pairs() {
+    // 150个状态的链，两个字符都前进一步。除了和接受状态组成的状态对以外，
+    // 其余约一万个状态对都是先进入关联表，之后才沿着关联链被区分的。
+    // 关联链的深度受状态数限制（见上一个测试），这里检查的是关联表很多时显式的栈也能正确处理。
+    let n = 150;
+    let mut table = vec![vec![0, 0]];
+    for state in 1..n {
+        table.push(vec![state + 1, state + 1]);
+    }
+    table.push(vec![0, 0]);
+    let dfa = DenseDFA::from_table(vec![b'a', b'b'], &table, 1, &[n]);
+
+    let minimized = std::thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(move || dfa.minimize())
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(minimized.number_of_states(), n + 1);
+    assert!(minimized.accepts(&"ab".repeat(n as usize / 2)[1..]));
+}
+
+#[test]
+fn accept_states_as_ranges() {
+    // 长度为1到4的0串，1到4号状态都是接受状态
+    let table = vec![vec![0], vec![2], vec![3], vec![4], vec![5], vec![0]];
+    let dfa = DenseDFA::from_table(vec![b'0'], &table, 1, &[2, 3, 4, 5]);
+    assert_eq!(dfa.accept_states_as_ranges(), vec![(2, 5)]);
+
+    let rebuilt = DenseDFA::from_table_with_accept_ranges(vec![b'0'], &table, 1, &[(2, 5)]);
+    assert!(same_structure(&dfa, &rebuilt));
+
+    let dfa = DenseDFA::from_table(vec![b'0'], &table, 1, &[1, 2, 4]);
+    assert_eq!(dfa.accept_states_as_ranges(), vec![(1, 2), (4, 4)]);
+    assert!(DenseDFA::from_table(vec![b'0'], &table, 1, &[])
+        .accept_states_as_ranges()
+        .is_empty());
+}
+
+/// 简单检查XML是否良构：标签正确嵌套，属性值都有引号，文本中没有裸露的`<`和`&`。
+fn is_well_formed_xml(xml: &str) -> bool {
+    let body = match xml.strip_prefix("<?xml") {
+        Some(rest) => match rest.find("?>") {
+            Some(end) => &rest[end + 2..],
+            None => return false,
+        },
+        None => xml,
+    };
+    let mut stack: Vec<String> = Vec::new();
+    let mut rest = body;
+    let mut seen_root = false;
+    while let Some(open) = rest.find('<') {
+        let text = &rest[..open];
+        if text.contains('>') || text.split('&').skip(1).any(|entity| !entity.contains(';')) {
+            return false;
+        }
+        if stack.is_empty() && !text.trim().is_empty() {
+            return false;
+        }
+        let close = match rest[open..].find('>') {
+            Some(close) => open + close,
+            None => return false,
+        };
+        let tag = &rest[open + 1..close];
+        if tag.matches('"').count() % 2 != 0 {
+            return false;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            if stack.pop().as_deref() != Some(name.trim()) {
+                return false;
+            }
+        } else {
+            let name = tag
+                .split_whitespace()
+                .next()
+                .unwrap_or("")
+                .trim_end_matches('/');
+            if name.is_empty() || (stack.is_empty() && seen_root) {
+                return false;
+            }
+            seen_root = true;
+            if !tag.ends_with('/') {
+                stack.push(name.to_string());
+            }
+        }
+        rest = &rest[close + 1..];
+    }
+    stack.is_empty() && seen_root && rest.trim().is_empty()
+}
+
+#[test]
+fn to_graphml() {
+    let dfa = redundant_dfa();
+    let xml = dfa.to_graphml();
+    assert!(is_well_formed_xml(&xml), "{}", xml);
+    assert!(xml.contains("<graph id=\"DFA\" edgedefault=\"directed\">"));
+    // 不显示陷阱状态：4个节点，q1到q4每个状态2条边
+    assert_eq!(xml.matches("<node ").count(), 4);
+    assert_eq!(xml.matches("<edge ").count(), 8);
+    assert!(xml.contains(
+        "<node id=\"q1\"><data key=\"accept\">false</data><data key=\"start\">true</data></node>"
+    ));
+    assert!(xml.contains("<edge source=\"q1\" target=\"q3\"><data key=\"symbol\">1</data></edge>"));
+
+    let xml = dfa.to_graphml_with(true);
+    assert!(is_well_formed_xml(&xml));
+    assert_eq!(xml.matches("<node ").count(), 5);
+    assert_eq!(xml.matches("<edge ").count(), 10);
+
+    // 需要转义的字符
+    let dfa = DenseDFA::from_table(vec![b'<', b'&'], &[vec![0, 0], vec![1, 0]], 1, &[1]);
+    let xml = dfa.to_graphml();
+    assert!(is_well_formed_xml(&xml), "{}", xml);
+    assert!(xml.contains("<data key=\"symbol\">&lt;</data>"));
+    // 控制字符不能出现在XML里
+    let dfa = DenseDFA::from_table(vec![0x01, b'\n'], &[vec![0, 0], vec![1, 1]], 1, &[1]);
+    let xml = dfa.to_graphml();
+    assert!(is_well_formed_xml(&xml), "{}", xml);
+    assert!(xml.contains("<data key=\"symbol\">\\x01</data>"));
+    assert!(xml.contains("<data key=\"symbol\">\\x0a</data>"));
+    assert!(!xml.contains('\u{1}'));
+    assert!(!is_well_formed_xml("<graphml><graph></graphml></graph>"));
+}
+
+#[test]
+fn language_difference_examples() {
+    // 0(10)*1 就是 (01)+，和 (01)* 只差一个空串
+    let left = wasm_fa::re_to_dfa("(01)*").unwrap();
+    let right = wasm_fa::re_to_dfa("0(10)*1").unwrap();
+    assert_eq!(
+        left.language_difference_examples(&right, 5),
+        Ok(vec![b"".to_vec()])
+    );
+    assert!(left
+        .language_difference_examples(&left, 5)
+        .unwrap()
+        .is_empty());
+
+    let ends_with_1 = wasm_fa::re_to_dfa("(0|1)*1").unwrap();
+    let starts_with_1 = wasm_fa::re_to_dfa("1(0|1)*").unwrap();
+    let examples: Vec<String> = ends_with_1
+        .language_difference_examples(&starts_with_1, 6)
+        .unwrap()
+        .into_iter()
+        .map(|word| String::from_utf8(word).unwrap())
+        .collect();
+    assert_eq!(examples, vec!["01", "10", "001", "011", "100", "110"]);
+    for example in &examples {
+        assert_ne!(ends_with_1.accepts(example), starts_with_1.accepts(example));
+    }
+
+    // 积DFA超过上限时返回错误，而不是panic
+    let by_101 = DenseDFA::divisible_by(101, 2);
+    let by_103 = DenseDFA::divisible_by(103, 2);
+    assert_eq!(
+        by_101.language_difference_examples_with_limit(&by_103, 3, 1000),
+        Err(ProductError::ProductTooLarge { reached: 1001 })
+    );
+    let examples = by_101
+        .language_difference_examples_with_limit(&by_103, 3, 20000)
+        .unwrap();
+    assert_eq!(examples.len(), 3);
+}
+
+#[test]
+fn enumerate_accepted() {
+    let dfa = wasm_fa::re_to_dfa("0|11|010").unwrap();
+    let words: Vec<String> = dfa
+        .enumerate_accepted(10)
+        .into_iter()
+        .map(|word| String::from_utf8(word).unwrap())
+        .collect();
+    assert_eq!(words, vec!["0", "11", "010"]);
+
+    let dfa = wasm_fa::re_to_dfa("(00)*1").unwrap();
+    assert_eq!(
+        dfa.enumerate_accepted(3),
+        vec![b"1".to_vec(), b"001".to_vec(), b"00001".to_vec()]
+    );
+}
+
+#[test]
+fn binary_fast_path_agrees_with_generic_path() {
+    // 字母表多一个用不到的字符，就只能走通用路径
+    let binary = wasm_fa::re_to_dfa("(0|1)*1(0|1)(0|1)").unwrap();
+    let generic = binary.with_extended_alphabet(b"2");
+    // 同一个语言，字母表顺序是"10"
+    let flipped = DenseDFA::from_table(
+        vec![b'1', b'0'],
+        &[vec![0, 0], vec![2, 1], vec![2, 1]],
+        1,
+        &[2],
+    );
+
+    let mut inputs = binary_strings(8);
+    for len in [500, 1001, 4003] {
+        let input: String = (0..len)
+            .map(|i: u32| if i.count_ones() % 3 == 1 { '1' } else { '0' })
+            .collect();
+        inputs.push(format!("{}x", input));
+        inputs.push(format!("x{}", input));
+        inputs.push(input);
+    }
+    for input in &inputs {
+        assert_eq!(binary.accepts(input), generic.accepts(input), "{:?}", input);
+        assert_eq!(
+            flipped.accepts(input),
+            input.ends_with('1') && !input.contains('x'),
+            "{:?}",
+            input
+        );
+    }
+}
+
+#[test]
+fn from_truth_table() {
+    // 3位异或：1的个数是奇数的3位串。16个状态的树被压缩成按（已读位数, 奇偶性）区分的状态，
+    // 再加上陷阱状态：开始状态、第1层2个、第2层2个、接受状态，一共7个状态。
+    // 因为语言只含长度为3的串，所以不会像不限长度的奇偶校验那样只有2个状态。
+    let odd: Vec<&[u8]> = vec![b"001", b"010", b"100", b"111"];
+    let dfa = DenseDFA::from_truth_table(3, &odd);
+    assert_eq!(dfa.number_of_states(), 7);
+    for input in binary_strings(5) {
+        let expected = input.len() == 3 && input.matches('1').count() % 2 == 1;
+        assert_eq!(dfa.accepts(&input), expected, "{:?}", input);
+    }
+
+    // 所有3位串：只按长度区分
+    let all: Vec<&[u8]> = vec![
+        b"000", b"001", b"010", b"011", b"100", b"101", b"110", b"111",
+    ];
+    assert_eq!(DenseDFA::from_truth_table(3, &all).number_of_states(), 5);
+    assert!(DenseDFA::from_truth_table(3, &[])
+        .shortest_accepted()
+        .is_none());
+}
+
+#[cfg(feature = "num-bigint")]
+#[test]
+fn count_accepted_of_length_big() {
+    use num_bigint::BigUint;
+
+    let dfa = wasm_fa::re_to_dfa("(0|1)*1(0|1)").unwrap();
+    for n in 0..20 {
+        assert_eq!(
+            dfa.count_accepted_of_length_big(n),
+            BigUint::from(dfa.count_accepted_of_length(n))
+        );
+    }
+
+    // 全体01串：2^200个，u128的版本已经饱和了
+    let all = wasm_fa::re_to_dfa("(0|1)*").unwrap();
+    assert_eq!(all.count_accepted_of_length(200), u128::MAX);
+    assert_eq!(
+        all.count_accepted_of_length_big(200),
+        BigUint::from(1u32) << 200
+    );
+    // 倒数第二位是1的串恰好占一半
+    assert_eq!(
+        dfa.count_accepted_of_length_big(200),
+        BigUint::from(1u32) << 199
+    );
+}
+
+#[test]
+fn minimize_opts_keep_trap() {
+    // 以0开头的串：部分极小DFA有开始状态和接受状态两个状态，完全极小DFA还有一个死状态
+    let dfa = wasm_fa::re_to_dfa("0(0|1)*").unwrap();
+    let partial = dfa.minimize_opts(false);
+    let complete = dfa.minimize_opts(true);
+    assert_eq!(partial.number_of_states(), 3);
+    assert_eq!(complete.number_of_states(), 4);
+    assert!(agree_on_binary_strings(&partial, &complete, 6));
+
+    // 死状态在状态图中是一个普通的、有自环的状态，0号状态不可达
+    let dead = complete.delta(complete.start_state(), b'1');
+    assert_ne!(dead, 0);
+    assert!(complete
+        .to_dot()
+        .contains(&format!("{} -> {} [label = \"0,1\"];", dead, dead)));
+    assert!(!partial.to_dot().contains("[label = \"1\"]"));
+    // 再极小化一次，死状态又并入了0号状态
+    let again = complete.minimize();
+    assert_eq!(again.number_of_states(), partial.number_of_states());
+    assert!(again.equivalent(&partial));
+
+    // 不需要死状态的语言，两种结果一样
+    let all = wasm_fa::re_to_dfa("(0|1)*").unwrap();
+    assert!(same_structure(
+        &all.minimize_opts(false),
+        &all.minimize_opts(true)
+    ));
+}
+
+#[test]
+fn is_disjoint_and_common_witness() {
+    let zeros = wasm_fa::re_to_dfa("0*").unwrap();
+    let ones = wasm_fa::re_to_dfa("1*").unwrap();
+    assert!(!zeros.is_disjoint(&ones));
+    assert_eq!(zeros.common_witness(&ones), Some(Vec::new()));
+
+    let zeros = wasm_fa::re_to_dfa("00*").unwrap();
+    let ones = wasm_fa::re_to_dfa("11*").unwrap();
+    assert!(zeros.is_disjoint(&ones));
+    assert_eq!(zeros.common_witness(&ones), None);
+
+    // 最短的公共字符串中取字典序最小的
+    let ends_with_1 = wasm_fa::re_to_dfa("(0|1)*1").unwrap();
+    let has_00 = wasm_fa::re_to_dfa("(0|1)*00(0|1)*").unwrap();
+    assert_eq!(ends_with_1.common_witness(&has_00), Some(b"001".to_vec()));
+
+    // 字母表不同时只用共有的字符
+    let abc = DenseDFA::from_table(
+        vec![b'0', b'a'],
+        &[vec![0, 0], vec![2, 1], vec![0, 0]],
+        1,
+        &[2],
+    );
+    assert_eq!(abc.common_witness(&ends_with_1), None);
+    assert_eq!(
+        abc.common_witness(&wasm_fa::re_to_dfa("0").unwrap()),
+        Some(b"0".to_vec())
+    );
+}
+
+#[test]
+fn complement_over_alphabet() {
+    let zeros = DenseDFA::from_table(vec![b'0'], &[vec![0], vec![1]], 1, &[1]);
+
+    // 字母表{0}上，0*就是全体字符串，补集是空集
+    let over_0 = zeros.complement();
+    assert_eq!(over_0.shortest_accepted(), None);
+    assert!(same_structure(&over_0, &zeros.complement_over(b"0")));
+
+    // 字母表{0,1}上，补集是所有含有1的串
+    let over_01 = zeros.complement_over(b"01");
+    for input in binary_strings(5) {
+        assert_eq!(over_01.accepts(&input), input.contains('1'), "{:?}", input);
+    }
+
+    // 补两次得到原来的语言
+    let dfa = wasm_fa::re_to_dfa("0(0|1)*1").unwrap();
+    assert!(dfa.complement().complement().equivalent(&dfa));
+    for input in binary_strings(5) {
+        assert_ne!(dfa.complement().accepts(&input), dfa.accepts(&input));
+    }
+}
+
+#[test]
+fn complement_of_ends_with_one() {
+    let complement = wasm_fa::re_to_dfa("(0|1)*1").unwrap().complement();
+    for input in binary_strings(6) {
+        assert_eq!(
+            complement.accepts(&input),
+            input.is_empty() || input.ends_with('0'),
+            "{:?}",
+            input
+        );
+    }
+
+    // 0(0|1)*里读到1就进入死状态，补集里它变成了会自环的接受状态
+    let complement = wasm_fa::re_to_dfa("0(0|1)*").unwrap().complement();
+    assert!(complement.accepts("1"));
+    assert!(complement.accepts("1010"));
+    assert!(!complement.accepts("01"));
+    assert!(complement
+        .accept_states_sorted()
+        .iter()
+        .all(|state| *state != 0));
+}
+
+#[test]
+fn shortest_string_reaching() {
+    // 一条链：状态k读1到k+1，读0回到1号状态，所以到达状态k需要连续k-1个1
+    let n = 6;
+    let mut table = vec![vec![0, 0]];
+    for state in 1..n {
+        table.push(vec![1, state + 1]);
+    }
+    table.push(vec![1, 0]);
+    let dfa = DenseDFA::from_table(vec![b'0', b'1'], &table, 1, &[n]);
+    for k in 1..=n {
+        assert_eq!(
+            dfa.shortest_string_reaching(k),
+            Some(vec![b'1'; k as usize - 1])
+        );
+    }
+    // 陷阱状态要读满n个1才能到达
+    assert_eq!(
+        dfa.shortest_string_reaching(0),
+        Some(vec![b'1'; n as usize])
+    );
+    assert_eq!(dfa.shortest_string_reaching(100), None);
+
+    // 不可达的状态
+    let dfa = DenseDFA::from_table(vec![b'0'], &[vec![0], vec![1], vec![1]], 1, &[2]);
+    assert_eq!(dfa.shortest_string_reaching(2), None);
+    assert_eq!(dfa.shortest_string_reaching(0), None);
+
+    // 长度相同时取字母表顺序最小的
+    let dfa = wasm_fa::re_to_dfa("(0|1)(0|1)").unwrap();
+    let accept = dfa.accept_states_sorted()[0];
+    assert_eq!(dfa.shortest_string_reaching(accept), Some(b"00".to_vec()));
+}
+
+#[test]
+fn minimize_merges_start_state() {
+    // 开始状态1和状态2不可区分，合并之后的状态必须仍然是开始状态，并且仍然是接受状态。
+    // 3号是死状态，并入陷阱状态。语言是0*。
+    let dfa = DenseDFA::from_table(
+        vec![b'0', b'1'],
+        &[vec![0, 0], vec![2, 3], vec![1, 3], vec![3, 3]],
+        1,
+        &[1, 2],
+    );
+    let (minimized, groups) = dfa.minimize_and_report();
+    let start = minimized.start_state() as usize;
+    assert_eq!(groups[start], vec![1, 2]);
+    assert_eq!(groups[0], vec![0, 3]);
+    assert!(minimized.accepts_empty_string());
+    assert_eq!(minimized.accept_states_sorted(), vec![start as u128]);
+    assert!(agree_on_binary_strings(&dfa, &minimized, 6));
+}
+
+#[test]
+fn json_compact_round_trip() {
+    let dfa = redundant_dfa();
+    let json = dfa.to_json_compact();
+    assert_eq!(
+        json,
+        "{\"alphabet\":[\"0\",\"1\"],\"states\":5,\"start\":1,\"accept\":[4],\
+         \"transitions\":[[1,\"0\",2],[1,\"1\",3],[2,\"0\",4],[2,\"1\",4],\
+         [3,\"0\",4],[3,\"1\",4],[4,\"0\",4],[4,\"1\",4]]}"
+    );
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["transitions"].as_array().unwrap().len(), 8);
+    assert!(same_structure(
+        &DenseDFA::from_json_compact(&json).unwrap(),
+        &dfa
+    ));
+
+    // 需要转义的字符和带空白的输入
+    let dfa = DenseDFA::from_table(vec![b'"', 0xe9], &[vec![0, 0], vec![1, 0]], 1, &[1]);
+    let json = dfa.to_json_compact();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["alphabet"][1], "\u{e9}");
+    let pretty = serde_json::to_string_pretty(&value).unwrap();
+    assert!(same_structure(
+        &DenseDFA::from_json_compact(&pretty).unwrap(),
+        &dfa
+    ));
+
+    assert!(DenseDFA::from_json_compact("{\"alphabet\":[\"0\"]}").is_err());
+    assert!(DenseDFA::from_json_compact(
+        "{\"alphabet\":[\"0\"],\"states\":2,\"start\":1,\"accept\":[],\"transitions\":[[1,\"0\",2]]}"
+    )
+    .is_err());
+}
+
+#[test]
+fn json_compact_rejects_trap_state_and_huge_sizes() {
+    let load = |accept: &str, transitions: &str, states: &str| {
+        DenseDFA::from_json_compact(&format!(
+            "{{\"alphabet\":[\"0\"],\"states\":{},\"start\":1,\"accept\":[{}],\"transitions\":[{}]}}",
+            states, accept, transitions
+        ))
+    };
+    assert!(load("1", "[1,\"0\",1]", "2").is_ok());
+    // 0号状态是陷阱状态，不能是接受状态，也不能有转移
+    assert!(load("0", "[1,\"0\",0]", "2").is_err());
+    assert!(load("1", "[0,\"0\",1]", "2").is_err());
+    // 太大的状态数返回错误，而不是在分配转移表时耗尽内存
+    let error = load("1", "", "340282366920938463463374607431768211455")
+        .err()
+        .unwrap();
+    assert!(error.contains("too many states"), "{}", error);
+}
+
+#[test]
+fn partial_dfa_rejects_undefined_transitions() {
+    // 0号状态是一个普通的接受状态：0 -a-> 1 -b-> 0，语言是(ab)*。1号状态读a没有定义。
+    let dfa = DenseDFA::from_partial_table(
+        vec![b'a', b'b'],
+        &[vec![Some(1), None], vec![None, Some(0)]],
+        0,
+        &[0],
+    );
+    assert!(dfa.is_partial());
+    assert!(!dfa.is_defined(1, b'a'));
+    assert_eq!(dfa.trap_state(), None);
+
+    assert!(dfa.accepts(""));
+    assert!(dfa.accepts("abab"));
+    // 1号状态读a没有定义，必须拒绝，而不是跳到0号接受状态
+    assert!(!dfa.accepts("aa"));
+    assert!(!dfa.accepts("b"));
+    assert!(!CompletedDfa::accepts(&dfa, "aa"));
+    assert_eq!(
+        dfa.accepts_all_of(&["ab", "aa", "ba"]),
+        vec![true, false, false]
+    );
+    assert!(!dfa.accepts_with_partiality(b"aa", Partiality::UndefinedRejects));
+
+    // 陷阱语义：没有定义的转移到0号状态
+    assert!(dfa.accepts_with_partiality(b"aa", Partiality::UndefinedIsTrap));
+    assert!(dfa.accepts_with_partiality(b"b", Partiality::UndefinedIsTrap));
+
+    // 完全DFA两种语义相同
+    let complete = wasm_fa::re_to_dfa("(01)*").unwrap();
+    assert!(!complete.is_partial());
+    for input in binary_strings(5) {
+        assert_eq!(
+            complete.accepts_with_partiality(input.as_bytes(), Partiality::UndefinedRejects),
+            complete.accepts_with_partiality(input.as_bytes(), Partiality::UndefinedIsTrap)
+        );
+    }
+}
+
+#[test]
+fn partial_dfa_keeps_its_language() {
+    // 和上一个测试相同的部分DFA，语言是(ab)*，0号状态是接受状态
+    let dfa = DenseDFA::from_partial_table(
+        vec![b'a', b'b'],
+        &[vec![Some(1), None], vec![None, Some(0)]],
+        0,
+        &[0],
+    );
+    assert_eq!(dfa.accepts_detailed(b"ab"), Ok(()));
+    assert_eq!(
+        dfa.accepts_detailed(b"aa"),
+        Err(RejectReason::FellIntoTrap { at: 1, byte: b'a' })
+    );
+    assert!(dfa.accepts_reversed(b"ba"));
+    assert!(!dfa.accepts_reversed(b"aa"));
+    assert_eq!(dfa.longest_accepting_prefix(b"abaa"), Some(2));
+
+    // 转换成完全DFA之后，0号状态是陷阱状态，语言不变
+    let complete = dfa.complete_partial();
+    assert!(!complete.is_partial());
+    for input in ["", "ab", "abab", "a", "aa", "b", "aba"].iter() {
+        assert_eq!(complete.accepts(input), dfa.accepts(input), "{:?}", input);
+        for converted in [
+            dfa.minimize(),
+            dfa.minimize_hopcroft(),
+            dfa.normalize(),
+            dfa.union(&dfa).unwrap(),
+        ]
+        .iter()
+        {
+            assert_eq!(converted.accepts(input), dfa.accepts(input), "{:?}", input);
+        }
+        assert_ne!(
+            dfa.complement().accepts(input),
+            dfa.accepts(input),
+            "{:?}",
+            input
+        );
+    }
+    assert!(dfa.equivalent(&wasm_fa::re_to_dfa_with_alphabet("(ab)*", b"ab").unwrap()));
+}
+
+#[test]
+fn partial_dfa_operations_skip_undefined_transitions() {
+    // 还是(ab)*的部分DFA。没有定义的转移在表里记成了0，但0号状态是接受状态，不能当成陷阱状态。
+    let dfa = DenseDFA::from_partial_table(
+        vec![b'a', b'b'],
+        &[vec![Some(1), None], vec![None, Some(0)]],
+        0,
+        &[0],
+    );
+    let expected = wasm_fa::re_to_dfa_with_alphabet("(ab)*", b"ab").unwrap();
+
+    // 分析
+    for n in 0..6 {
+        assert_eq!(
+            dfa.count_accepted_of_length(n),
+            expected.count_accepted_of_length(n),
+            "{}",
+            n
+        );
+    }
+    assert_eq!(dfa.count_accepted_of_length(2), 1);
+    assert_eq!(
+        dfa.enumerate_accepted(3),
+        vec![b"".to_vec(), b"ab".to_vec(), b"abab".to_vec()]
+    );
+    assert_eq!(dfa.shortest_string_reaching(1), Some(b"a".to_vec()));
+    assert!(dfa.language_contains_all_of_length(0));
+    assert!(!dfa.language_contains_all_of_length(2));
+    assert!(!dfa.is_prefix_closed());
+    assert!(!dfa.is_suffix_closed());
+    assert!((dfa.growth_rate() - 1.0).abs() < 1e-6);
+    assert_eq!(dfa.edit_distance_to_language(b"aa"), 1);
+    assert_eq!(dfa.nearest_accepted(b"aa"), Some(b"ab".to_vec()));
+    assert_eq!(dfa.equivalence_classes(), vec![vec![0], vec![1]]);
+    assert_eq!(dfa.out_degree(0), 1);
+    assert_eq!(dfa.out_degree(1), 1);
+    assert_eq!(dfa.uncovered_transitions(&["a"]), vec![(1, b'b')]);
+    assert_eq!(dfa.transition_coverage(&["ab"]), 1.0);
+
+    // 变换之后的语言不变
+    let converted = [
+        dfa.trim(),
+        dfa.fold_identical_states(),
+        dfa.materialize_trap(),
+        dfa.canonicalize(),
+        dfa.minimize_by_accept_labels(&std::collections::HashMap::new()),
+        dfa.minimize_and_report().0,
+        dfa.quotient_by(&[vec![0], vec![1]]).unwrap(),
+        dfa.with_extended_alphabet(b"c")
+            .restrict_to_alphabet(b"ab")
+            .unwrap(),
+        wasm_fa::re_to_dfa_with_alphabet(&dfa.to_regex().unwrap(), b"ab").unwrap(),
+        DenseDFA::from_json_compact(&dfa.to_json_compact()).unwrap(),
+    ];
+    for (index, result) in converted.iter().enumerate() {
+        assert!(result.equivalent(&expected), "{}", index);
+    }
+    assert!(dfa.trim().accepts("ab"));
+    assert!(dfa.star().accepts("abab"));
+    assert!(dfa.star().equivalent(&expected));
+    assert!(dfa.concat(&dfa).equivalent(&expected));
+    assert_eq!(dfa.common_witness(&dfa), Some(Vec::new()));
+    assert!(!dfa.is_disjoint(&expected));
+
+    // 改变字母表之后仍然是部分DFA
+    let extended = dfa.with_extended_alphabet(b"c");
+    assert!(extended.is_partial());
+    assert!(extended.accepts("abab"));
+    assert!(!extended.accepts("aa"));
+    assert!(!extended.accepts("abc"));
+    let restricted = dfa.restrict_to_alphabet(b"a").unwrap();
+    assert!(restricted.is_partial());
+    assert!(restricted.accepts(""));
+    assert!(!restricted.accepts("a"));
+
+    // 输出中没有定义的转移不出现，0号状态是普通状态
+    let dot = dfa.to_dot();
+    assert!(dot.contains("0 -> 1 [label = \"a\"];"), "{}", dot);
+    assert!(dot.contains("1 -> 0 [label = \"b\"];"), "{}", dot);
+    assert_eq!(dot.matches("->").count(), 2, "{}", dot);
+    assert_eq!(dfa.to_rg(), "S -> q1 | ε\nq1 -> aq2 \nq2 -> b | bq1 \n");
+}
+
+#[test]
+fn iter_accepted_with_state_paths() {
+    let dfa = wasm_fa::re_to_dfa("(01)*").unwrap();
+    let start = dfa.start_state();
+    let middle = dfa.delta(start, b'0');
+    let first: Vec<(String, Vec<u128>)> = dfa.iter_accepted_with_state_paths().take(3).collect();
+    assert_eq!(
+        first,
+        vec![
+            (String::new(), vec![start]),
+            ("01".to_string(), vec![start, middle, start]),
+            (
+                "0101".to_string(),
+                vec![start, middle, start, middle, start]
+            ),
+        ]
+    );
+
+    // 每条路径都是真实的运行路径，并且只经过能到达接受状态的状态
+    let dfa = wasm_fa::re_to_dfa("(0|1)*1(0|1)").unwrap();
+    for (word, path) in dfa.iter_accepted_with_state_paths().take(20) {
+        assert_eq!(path.len(), word.len() + 1);
+        assert!(dfa.accepts(&word));
+        for (index, input) in word.bytes().enumerate() {
+            assert_eq!(dfa.delta(path[index], input), path[index + 1]);
+        }
+        assert!(!path.contains(&0));
+    }
+
+    // 有限语言的迭代器会结束
+    assert_eq!(
+        wasm_fa::re_to_dfa("0|11")
+            .unwrap()
+            .iter_accepted_with_state_paths()
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn minimize_large_redundant_dfa() {
+    // 被m整除的二进制数，每个余数有两份等价的状态，极小化后两份合并
+    let m: u128 = 301;
+    let mut table = vec![vec![0, 0]];
+    for k in 0..2 * m {
+        let (residue, copy) = (k % m, k / m);
+        table.push(
+            (0..2)
+                .map(|bit| (2 * residue + bit) % m + m * (copy ^ bit) + 1)
+                .collect(),
+        );
+    }
+    let dfa = DenseDFA::from_table(b"01".to_vec(), &table, 1, &[1, m + 1]);
+    let minimized = dfa.minimize();
+    assert_eq!(minimized.number_of_states(), m + 1);
+    assert!(minimized.is_minimal());
+    assert!(minimized.equivalent(&dfa));
+    assert!(minimized.accepts(&format!("{:b}", 7 * m)));
+    assert!(!minimized.accepts(&format!("{:b}", 7 * m + 2)));
+}
+
+#[test]
+fn to_dot_opts_rank_by_distance() {
+    use wasm_fa::dfa::DotOptions;
+
+    // 有限语言 {0, 01, 10, 11}：开始状态、读了一个字符、读了两个字符，正好三层
+    let dfa = DenseDFA::from_table(
+        b"01".to_vec(),
+        &[vec![0, 0], vec![2, 3], vec![0, 4], vec![4, 4], vec![0, 0]],
+        1,
+        &[2, 4],
+    );
+    let dot = dfa.to_dot_opts(&DotOptions {
+        rank_by_distance: true,
+        ..DotOptions::default()
+    });
+    assert!(dot.contains("{ rank=same; 1; }\n{ rank=same; 2; 3; }\n{ rank=same; 4; }\n"));
+    assert!(!dot.contains("rank=same; 0;"));
+
+    let with_trap = dfa.to_dot_opts(&DotOptions {
+        rank_by_distance: true,
+        show_trap: true,
+        ..DotOptions::default()
+    });
+    assert!(with_trap.contains("{ rank=same; 0; 4; }\n"));
+
+    // 默认不分层
+    assert!(!dfa
+        .to_dot_opts(&DotOptions::default())
+        .contains("rank=same"));
+}
+
+#[test]
+fn language_symbols_used() {
+    use std::collections::HashSet;
+
+    let zeros = wasm_fa::re_to_dfa("0*").unwrap();
+    assert_eq!(zeros.alphabet(), &b"01".to_vec());
+    assert_eq!(
+        zeros.language_symbols_used(),
+        [b'0'].iter().cloned().collect()
+    );
+
+    // 扩展出来的字符只通向陷阱状态
+    let widened = wasm_fa::re_to_dfa("(0|1)*1")
+        .unwrap()
+        .with_extended_alphabet(b"2");
+    let expected: HashSet<u8> = b"01".iter().cloned().collect();
+    assert_eq!(widened.language_symbols_used(), expected);
+
+    // 空语言没有用到任何字符
+    let empty = DenseDFA::from_table(b"01".to_vec(), &[vec![0, 0], vec![1, 1]], 1, &[]);
+    assert!(empty.language_symbols_used().is_empty());
+}
+
+#[test]
+fn edit_accept_and_start_states() {
+    // 1 -0-> 2 -1-> 3，一开始没有接受状态
+    let mut dfa = DenseDFA::from_table(
+        b"01".to_vec(),
+        &[vec![0, 0], vec![2, 0], vec![0, 3], vec![0, 0]],
+        1,
+        &[],
+    );
+    assert!(!dfa.accepts("01"));
+    dfa.mark_accept(3).unwrap();
+    assert!(dfa.accepts("01"));
+    dfa.mark_accept(2).unwrap();
+    assert!(dfa.accepts("0"));
+    dfa.unmark_accept(3).unwrap();
+    assert!(!dfa.accepts("01"));
+    dfa.set_start(2).unwrap();
+    assert!(dfa.accepts(""));
+    assert!(!dfa.accepts("0"));
+
+    let out_of_range = EditError::StateOutOfRange {
+        id: 4,
+        number_of_states: 4,
+    };
+    assert_eq!(dfa.mark_accept(4), Err(out_of_range.clone()));
+    assert_eq!(dfa.unmark_accept(4), Err(out_of_range.clone()));
+    assert_eq!(dfa.set_start(4), Err(out_of_range));
+    assert_eq!(dfa.mark_accept(0), Err(EditError::TrapState));
+}
+
+#[test]
+fn add_transition_checked() {
+    let mut dfa = DenseDFA::from_table(
+        b"01".to_vec(),
+        &[vec![0, 0], vec![2, 0], vec![0, 0]],
+        1,
+        &[2],
+    );
+    assert!(!dfa.accepts("01"));
+    dfa.add_transition_checked(2, b'1', 2).unwrap();
+    assert!(dfa.accepts("01"));
+    assert!(dfa.accepts("0111"));
+    // 入表也要跟着更新
+    assert!(dfa.symbols_reaching(2).contains(&(2, b'1')));
+    assert!(!dfa.symbols_reaching(0).contains(&(2, b'1')));
+
+    // 设置成相同的状态不算覆盖
+    assert_eq!(dfa.add_transition_checked(1, b'0', 2), Ok(()));
+    assert_eq!(
+        dfa.add_transition_checked(1, b'0', 1),
+        Err(EditError::CellOccupied {
+            from: 1,
+            input: b'0',
+            existing: 2
+        })
+    );
+    assert_eq!(
+        dfa.add_transition_checked(1, b'2', 2),
+        Err(EditError::UnknownSymbol(b'2'))
+    );
+    assert_eq!(
+        dfa.add_transition_checked(1, b'1', 3),
+        Err(EditError::StateOutOfRange {
+            id: 3,
+            number_of_states: 3
+        })
+    );
+    assert_eq!(
+        dfa.add_transition_checked(0, b'1', 1),
+        Err(EditError::TrapState)
+    );
+
+    // 部分DFA中没有定义的转移可以设置，设置之后就有定义了
+    let mut partial = DenseDFA::from_partial_table(
+        b"01".to_vec(),
+        &[vec![Some(1), None], vec![None, None]],
+        0,
+        &[1],
+    );
+    assert!(!partial.accepts("01"));
+    partial.add_transition_checked(1, b'1', 1).unwrap();
+    assert!(partial.is_defined(1, b'1'));
+    assert!(partial.accepts("01"));
+    partial.mark_accept(0).unwrap();
+    assert!(partial.accepts(""));
+}
+
+#[test]
+fn to_dot_animation_frames() {
+    let dfa = wasm_fa::re_to_dfa("(0|1)*1").unwrap();
+    let frames = dfa.to_dot_animation_frames(b"101");
+    assert_eq!(frames.len(), 4);
+    let start = dfa.start_state();
+    let after_1 = dfa.delta(start, b'1');
+    assert!(frames[0].contains(&format!(
+        "{} [style = filled, fillcolor = yellow];\n",
+        start
+    )));
+    assert!(frames[0].contains(&format!("{} -> {} [label = ", start, after_1)));
+    assert!(frames[0].contains("color = red, penwidth = 2];\n"));
+    assert!(!frames[0].contains("accepted"));
+    assert!(frames[3].contains("label = \"accepted\";\n"));
+    assert!(frames[3].contains(&format!(
+        "{} [style = filled, fillcolor = green];\n",
+        after_1
+    )));
+    assert!(!frames[3].contains("penwidth"));
+
+    let rejected = dfa.to_dot_animation_frames(b"10");
+    assert_eq!(rejected.len(), 3);
+    assert!(rejected[2].contains("label = \"rejected\";\n"));
+    assert!(rejected[2].contains("fillcolor = red"));
+
+    // 掉进陷阱状态：陷阱状态和走进去的边显示出来，之后一直停在陷阱状态
+    let dfa = wasm_fa::re_to_dfa("01*").unwrap();
+    let frames = dfa.to_dot_animation_frames(b"001");
+    assert_eq!(frames.len(), 4);
+    let q = dfa.delta(dfa.start_state(), b'0');
+    assert!(frames[1].contains(&format!(
+        "{} -> 0 [label = \"0\", color = red, penwidth = 2];\n",
+        q
+    )));
+    assert!(frames[2].contains("0 [style = filled, fillcolor = yellow];\n"));
+    // 陷阱状态的自环也画出来，在陷阱状态里读字符时高亮它
+    assert!(frames[1].contains("0 -> 0 [label = \"0,1\"];\n"));
+    assert!(frames[2].contains("0 -> 0 [label = \"0,1\", color = red, penwidth = 2];\n"));
+    assert!(frames[3].contains("0 [style = filled, fillcolor = red];\n"));
+    assert!(frames[3].contains("label = \"rejected\";\n"));
+    // 没有走到陷阱状态时不显示它
+    assert!(!dfa.to_dot_animation_frames(b"01")[0].contains("0;\n"));
+    assert!(!dfa.to_dot_animation_frames(b"01")[0].contains("0 -> 0"));
+
+    // 空串只有一帧
+    assert_eq!(dfa.to_dot_animation_frames(b"").len(), 1);
+}
+
+#[test]
+fn right_language_fingerprint() {
+    let dfa = redundant_dfa();
+    // q2和q3等价，其余状态两两不等价
+    assert_eq!(dfa.congruence_classes_by_future(), vec![0, 1, 2, 2, 4]);
+    assert_eq!(
+        dfa.right_language_fingerprint(2),
+        dfa.right_language_fingerprint(3)
+    );
+    for (state1, state2) in [(0, 1), (1, 2), (2, 4), (0, 4)] {
+        assert_ne!(
+            dfa.right_language_fingerprint(state1),
+            dfa.right_language_fingerprint(state2)
+        );
+    }
+}
+
+#[test]
+fn minimize_merges_dead_states_into_trap() {
+    // 2号状态可达、不是接受状态，并且只能转移到自己，是一个死循环，和0号陷阱状态不可区分
+    let dfa = DenseDFA::from_table(
+        b"01".to_vec(),
+        &[vec![0, 0], vec![2, 3], vec![2, 2], vec![3, 3]],
+        1,
+        &[3],
+    );
+    let minimized = dfa.minimize();
+    assert_eq!(minimized.number_of_states(), 3);
+    assert_ne!(minimized.start_state(), 0);
+    assert_eq!(minimized.delta(0, b'0'), 0);
+    assert_eq!(minimized.delta(0, b'1'), 0);
+    assert_eq!(minimized.delta(minimized.start_state(), b'0'), 0);
+    assert!(minimized.accepts("10"));
+    assert!(!minimized.accepts("01"));
+    assert!(minimized.equivalent(&dfa));
+}
+
+#[test]
+fn minimization_dot_pair() {
+    fn assert_well_formed(dot: &str) {
+        assert!(dot.starts_with("digraph DFA {\n"), "{}", dot);
+        assert!(dot.ends_with("}\n"), "{}", dot);
+        let mut depth = 0i32;
+        for line in dot.lines() {
+            depth += line.matches('{').count() as i32 - line.matches('}').count() as i32;
+            assert!(depth >= 0, "{}", dot);
+            assert!(
+                line.ends_with(';') || line.ends_with('{') || line == "}",
+                "{:?} in\n{}",
+                line,
+                dot
+            );
+        }
+        assert_eq!(depth, 0, "{}", dot);
+    }
+
+    let (before, after) = redundant_dfa().minimization_dot_pair();
+    assert_well_formed(&before);
+    assert_well_formed(&after);
+    // 框的标签和合并之后的状态的标签相同
+    assert!(before.contains("label = \"{q2,q3}\";\n"));
+    assert!(after.contains("[label = \"{q2,q3}\"];\n"));
+    assert!(after.contains("[label = \"{q1}\"];\n"));
+    assert!(after.contains("[label = \"{q4}\"];\n"));
+    assert_eq!(after.matches("[label = \"{").count(), 3);
+}
+
+#[test]
+fn reject_reason_displays_non_ascii_bytes() {
+    let dfa = wasm_fa::re_to_dfa("0*").unwrap();
+    let reason = dfa.accepts_detailed(&[b'0', 0xE9]).unwrap_err();
+    assert_eq!(reason, RejectReason::UnknownSymbol { at: 1, byte: 0xE9 });
+    #[cfg(feature = "bstr")]
+    let expected = "symbol '\u{FFFD}' at 1 is not in the alphabet";
+    #[cfg(not(feature = "bstr"))]
+    let expected = "symbol '\\xE9' at 1 is not in the alphabet";
+    assert_eq!(reason.to_string(), expected);
+
+    // 可打印的ASCII字符原样显示
+    let reason = dfa.accepts_detailed(b"0a").unwrap_err();
+    assert_eq!(reason.to_string(), "symbol 'a' at 1 is not in the alphabet");
+}
+
+#[test]
+fn minimize_by_accept_labels() {
+    use std::collections::HashMap;
+
+    // 记号 "a" 和 "b"：两个接受状态除了标签之外完全等价，4号状态不可达
+    let dfa = DenseDFA::from_table(
+        b"ab".to_vec(),
+        &[vec![0, 0], vec![2, 3], vec![0, 0], vec![0, 0], vec![2, 3]],
+        1,
+        &[2, 3],
+    );
+    assert_eq!(dfa.minimize().number_of_states(), 3);
+
+    let labels: HashMap<u128, u32> = [(2, 7), (3, 8)].iter().cloned().collect();
+    let typed = dfa.minimize_by_accept_labels(&labels);
+    assert_eq!(typed.number_of_states(), 4);
+    assert!(typed.equivalent(&dfa));
+    assert_ne!(
+        typed.delta(typed.start_state(), b'a'),
+        typed.delta(typed.start_state(), b'b')
+    );
+
+    // 标签相同时和普通的极小化一样
+    let same: HashMap<u128, u32> = [(2, 7), (3, 7)].iter().cloned().collect();
+    assert_eq!(dfa.minimize_by_accept_labels(&same).number_of_states(), 3);
+    assert_eq!(
+        dfa.minimize_by_accept_labels(&HashMap::new())
+            .number_of_states(),
+        3
+    );
+}
+
+#[test]
+fn accepts_bytes() {
+    let dfa = wasm_fa::re_to_dfa("(0|1)*1").unwrap();
+    assert!(dfa.accepts_bytes(b"01"));
+    assert!(!dfa.accepts_bytes(b"10"));
+    // 字母表之外的字符直接拒绝，不会panic
+    assert!(!dfa.accepts("012"));
+    assert!(!dfa.accepts_bytes(b"0\xff1"));
+
+    // 字母表中有非ASCII字节，输入不是合法的UTF-8
+    let dfa = DenseDFA::from_table(
+        vec![0xff, 0xfe],
+        &[vec![0, 0], vec![2, 0], vec![2, 2]],
+        1,
+        &[2],
+    );
+    assert!(dfa.accepts_bytes(&[0xff]));
+    assert!(dfa.accepts_bytes(&[0xff, 0xfe, 0xff]));
+    assert!(!dfa.accepts_bytes(&[0xfe]));
+    assert!(!dfa.accepts_bytes(&[]));
+}
+
+#[test]
+fn accepts_with_trace() {
+    let dfa = wasm_fa::re_to_dfa("01*").unwrap();
+    let start = dfa.start_state();
+    let q = dfa.delta(start, b'0');
+
+    assert_eq!(dfa.accepts_with_trace("011"), (true, vec![start, q, q, q]));
+    assert_eq!(dfa.accepts_with_trace(""), (false, vec![start]));
+    // 进入陷阱状态后停止
+    assert_eq!(dfa.accepts_with_trace("0011"), (false, vec![start, q, 0]));
+    assert_eq!(dfa.accepts_with_trace("1"), (false, vec![start, 0]));
+    // 字母表之外的字符：停在最后一个合法的状态
+    assert_eq!(dfa.accepts_with_trace("0a1"), (false, vec![start, q]));
+
+    for input in binary_strings(6) {
+        assert_eq!(dfa.accepts_with_trace(&input).0, dfa.accepts(&input));
+    }
+}
+
+#[test]
+fn build_dfa_from_nfa_over_ab() {
+    use wasm_fa::dfa::DFA01;
+    use wasm_fa::nfa::Builder;
+
+    let nfa = Builder::new().build_nfa_from_re("(a|b)*a").unwrap();
+    let nfa = Builder::new().build_non_epsilon_nfa(&nfa).unwrap();
+    let sparse = DFA01::build_dfa_from_nfa(&nfa);
+    assert_eq!(sparse.alphabet(), &b"ab".to_vec());
+    assert!(sparse.accepts("aba"));
+    assert!(!sparse.accepts("ab"));
+
+    let dfa = wasm_fa::re_to_dfa("(a|b)*a").unwrap();
+    assert_eq!(dfa.alphabet(), &b"ab".to_vec());
+    // 陷阱状态、以a结尾、不以a结尾
+    assert_eq!(dfa.number_of_states(), 3);
+    let mut inputs = vec![String::new()];
+    for _ in 0..5 {
+        inputs = inputs
+            .iter()
+            .flat_map(|s| vec![format!("{}a", s), format!("{}b", s)])
+            .chain(inputs.iter().cloned())
+            .collect();
+        inputs.sort();
+        inputs.dedup();
+    }
+    for input in &inputs {
+        assert_eq!(dfa.accepts(input), input.ends_with('a'), "{:?}", input);
+    }
+
+    // 字母表是{0,1}的子集时仍然是{0,1}
+    assert_eq!(
+        wasm_fa::re_to_dfa("0*").unwrap().alphabet(),
+        &b"01".to_vec()
+    );
+}
+
+#[test]
+fn re_to_dfa_reports_errors() {
+    let error = wasm_fa::re_to_dfa("(01").err().unwrap();
+    assert!(error.contains("(01"), "{}", error);
+    assert!(wasm_fa::re_to_dfa("^0$").is_err());
+    // 嵌套的重复展开之后太大，也只是返回错误
+    let error = wasm_fa::re_to_dfa("(a{1000}){1000}").err().unwrap();
+    assert!(error.contains("too large"), "{}", error);
+
+    assert!(!wasm_fa::get_ans("0|1").starts_with("error"));
+    // 字母表不是{0,1}时也返回正常的答案：表格@正则文法@dot
+    let answer = wasm_fa::get_ans("(a|b)*a");
+    let parts: Vec<&str> = answer.split('@').collect();
+    assert_eq!(parts.len(), 3, "{}", answer);
+    assert!(parts[0].starts_with("\ta\tb\n"), "{}", answer);
+    assert!(parts[1].starts_with("S -> q"), "{}", answer);
+    assert!(parts[2].starts_with("digraph"), "{}", answer);
+    assert!(wasm_fa::get_ans("0)").starts_with("error: "));
+    assert!(wasm_fa::get_dfa_json("0)").starts_with("error: "));
+    assert!(wasm_fa::get_epsilon_free_nfa_dot("(a").starts_with("error: "));
+    assert!(wasm_fa::get_epsilon_free_nfa_dot("(a)").starts_with("digraph"));
+    // 只接受空串的正则表达式，开始状态没有非空转移，也要正常输出
+    for re in ["()", "a{0}", "(a{0})*"] {
+        let dot = wasm_fa::get_epsilon_free_nfa_dot(re);
+        assert!(dot.starts_with("digraph"), "{}: {}", re, dot);
+        assert!(!dot.contains('ε'), "{}: {}", re, dot);
+    }
+}
+
+#[test]
+fn minimize_already_minimal_is_isomorphic() {
+    let dfa = wasm_fa::re_to_dfa("(0|1)*01").unwrap();
+    assert!(dfa.is_minimal());
+    let again = dfa.minimize();
+    assert_eq!(again.number_of_states(), dfa.number_of_states());
+    assert_eq!(
+        again.canonicalize().to_json_compact(),
+        dfa.canonicalize().to_json_compact()
+    );
+}
+
+#[test]
+fn minimize_hopcroft_agrees_with_minimize() {
+    for re in [
+        "(0|1)*01",
+        "0*10*",
+        "(00|11)*(01|10)((00|11)*(01|10)(00|11)*(01|10))*(00|11)*",
+        "0(0|1)*",
+        "1*",
+        "(0|1)(0|1)(0|1)",
+    ]
+    .iter()
+    {
+        let dfa = wasm_fa::nfa::Builder::new()
+            .build_nfa_from_re(re)
+            .unwrap()
+            .determinize();
+        let hopcroft = dfa.minimize_hopcroft();
+        let minimized = dfa.minimize();
+        assert_eq!(
+            hopcroft.number_of_states(),
+            minimized.number_of_states(),
+            "{}",
+            re
+        );
+        assert!(hopcroft.equivalent(&minimized), "{}", re);
+        assert!(hopcroft.is_minimal(), "{}", re);
+    }
+
+    let dfa = redundant_dfa();
+    assert_eq!(
+        dfa.minimize_hopcroft().canonicalize().to_json_compact(),
+        dfa.minimize().canonicalize().to_json_compact()
+    );
+}
+
+#[cfg(feature = "random")]
+#[test]
+fn minimize_hopcroft_on_random_dfas() {
+    let mut rng = oorandom::Rand64::new(20240760);
+    for round in 0..200 {
+        let random = DenseDFA::random(1 + round % 15, b"012", 0.3, &mut rng);
+        let hopcroft = random.minimize_hopcroft();
+        let minimized = random.minimize();
+        assert_eq!(
+            hopcroft.number_of_states(),
+            minimized.number_of_states(),
+            "round {}",
+            round
+        );
+        assert!(hopcroft.equivalent(&random), "round {}", round);
+    }
+}
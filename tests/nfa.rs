@@ -0,0 +1,412 @@
+//! Test suite for the NFA construction.
+
+use wasm_fa::dfa::DenseDFA;
+use wasm_fa::nfa::{BuildError, Builder, NfaDotOptions, State, NFA};
+
+#[test]
+fn class_is_clamped_to_alphabet() {
+    let nfa = Builder::with_alphabet(b"01")
+        .build_nfa_from_re("[0-9]")
+        .unwrap();
+
+    let mut symbols: Vec<u8> = nfa.alphabet().iter().cloned().collect();
+    symbols.sort();
+    assert_eq!(symbols, vec![b'0', b'1']);
+
+    let number_of_trans: usize = nfa
+        .get_states_iter()
+        .map(|state| match state {
+            State::NonEpsilon(trans) => trans.iter().count(),
+            _ => 0,
+        })
+        .sum();
+    assert_eq!(number_of_trans, 2);
+}
+
+#[test]
+fn wide_class_is_clamped_by_default() {
+    let number_of_trans = |nfa: &NFA| -> usize {
+        nfa.get_states_iter()
+            .map(|state| match state {
+                State::NonEpsilon(trans) => trans.iter().count(),
+                _ => 0,
+            })
+            .sum()
+    };
+    let sorted = |nfa: &NFA| -> Vec<u8> {
+        let mut symbols: Vec<u8> = nfa.alphabet().iter().cloned().collect();
+        symbols.sort_unstable();
+        symbols
+    };
+
+    // 宽字符类只展开成0、1和正则表达式里出现的字符
+    let nfa = Builder::new()
+        .build_nfa_from_re("(0|1)[\\x00-\\xff]")
+        .unwrap();
+    assert_eq!(sorted(&nfa), b"01".to_vec());
+    assert_eq!(number_of_trans(&nfa), 4);
+    let nfa = Builder::new().build_nfa_from_re("a.").unwrap();
+    assert_eq!(sorted(&nfa), b"01a".to_vec());
+    let nfa = Builder::new().build_nfa_from_re(".*").unwrap();
+    assert_eq!(sorted(&nfa), b"01".to_vec());
+
+    // 窄字符类照常展开
+    let nfa = Builder::new().build_nfa_from_re("[0-9]").unwrap();
+    assert_eq!(number_of_trans(&nfa), 10);
+
+    let dfa = wasm_fa::re_to_dfa("0[^0]*").unwrap();
+    assert!(dfa.accepts("0111"));
+    assert!(!dfa.accepts("010"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    let nfa = Builder::new().build_nfa_from_re("(0|1)*01").unwrap();
+    let json = serde_json::to_string(&nfa).unwrap();
+    let loaded: wasm_fa::nfa::NFA = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.get_states_iter().len(), nfa.get_states_iter().len());
+    assert_eq!(loaded.start_state, nfa.start_state);
+    assert_eq!(loaded.accept_states, nfa.accept_states);
+    assert_eq!(loaded.alphabet(), nfa.alphabet());
+
+    let broken =
+        r#"{"states":[{"Epsilon":[5]}],"alphabet":[],"start_state":0,"accept_states":[0]}"#;
+    assert!(serde_json::from_str::<wasm_fa::nfa::NFA>(broken).is_err());
+}
+
+#[test]
+fn getters() {
+    let nfa = Builder::new().build_nfa_from_re("0(1|0)*").unwrap();
+    assert_eq!(nfa.number_of_states(), nfa.get_states_iter().len());
+    assert_eq!(nfa.start_state(), nfa.start_state);
+    assert_eq!(nfa.accept_states(), &nfa.accept_states[..]);
+    assert_eq!(nfa.accept_states().len(), 1);
+}
+
+#[test]
+fn from_edge_list_splits_mixed_states() {
+    // 0既有空转移又有非空转移，2是有非空转移的接受状态。语言是 0*10*
+    let edges = [
+        (0, Some(b'0'), 0),
+        (0, None, 1),
+        (1, Some(b'1'), 2),
+        (2, Some(b'0'), 2),
+    ];
+    let nfa = NFA::from_edge_list(&edges, 0, &[2]).unwrap();
+    // 3个原状态 + 拆分0和2得到的2个状态 + 1个新的接收状态
+    assert_eq!(nfa.number_of_states(), 6);
+    assert_eq!(nfa.start_state(), Some(0));
+    assert_eq!(nfa.accept_states().len(), 1);
+
+    let dfa = DenseDFA::build_from_nfa(&nfa);
+    let expected = wasm_fa::re_to_dfa("0*10*").unwrap();
+    for input in ["1", "01", "0010", "100", "", "0", "11", "0101"].iter() {
+        assert_eq!(dfa.accepts(input), expected.accepts(input), "{:?}", input);
+    }
+
+    // 边表中原有的边都还在，拆分只是多加了一些空转移
+    let exported = nfa.to_edge_list();
+    assert!(exported.contains(&(0, None, 1)));
+    assert!(exported.contains(&(1, Some(b'1'), 2)));
+}
+
+#[test]
+fn from_edge_list_rejects_huge_ids() {
+    assert_eq!(
+        NFA::from_edge_list(&[(0, None, u32::MAX)], 0, &[]).unwrap_err(),
+        BuildError::StateIdOverflow(u32::MAX)
+    );
+}
+
+#[test]
+fn remove_epsilon_remaps_start_and_accept_states() {
+    // 0 -0-> 3 不可达，1 -ε-> 2 -1-> 3(接受)，开始状态是1。
+    // 删除不可达的0号状态之后，开始状态和接受状态的编号都要减一。
+    let mut nfa = NFA::init_empty();
+    let unreachable = nfa.add_non_epsilon_state();
+    let start = nfa.add_epsilon_state();
+    let middle = nfa.add_non_epsilon_state();
+    let accept = nfa.add_final_state();
+    nfa.add_transition(unreachable, b'0', accept);
+    nfa.add_epsilon_transition(start, middle);
+    nfa.add_transition(middle, b'1', accept);
+    nfa.set_start_state(start);
+    nfa.set_accept_state(accept);
+
+    let nfa = nfa.remove_epsilon().unwrap();
+    let number_of_states = nfa.number_of_states() as u32;
+    assert!(nfa.start_state().unwrap() < number_of_states);
+    assert!(nfa.accept_states().iter().all(|id| *id < number_of_states));
+
+    let dfa = nfa.determinize();
+    assert!(dfa.accepts("1"));
+    assert!(!dfa.accepts("0"));
+    assert!(!dfa.accepts(""));
+}
+
+#[test]
+fn remove_epsilon_keeps_a_start_state_without_transitions() {
+    // 开始状态没有非空转移，但能通过空转移到达接受状态，它自己就变成接受状态
+    for re in ["()", "a{0}", "()|a{0}"] {
+        let nfa = Builder::new().build_nfa_from_re(re).unwrap();
+        let epsilon_free = nfa.remove_epsilon().unwrap();
+        let start = epsilon_free.start_state().unwrap();
+        assert!(start < epsilon_free.number_of_states() as u32, "{}", re);
+        assert!(epsilon_free.accept_states().contains(&start), "{}", re);
+        assert!(nfa.epsilon_free_dot().unwrap().starts_with("digraph"));
+
+        let dfa = epsilon_free.determinize();
+        assert!(dfa.accepts(""), "{}", re);
+        assert!(!dfa.accepts("a"), "{}", re);
+    }
+
+    // 字符类和字母表没有交集，开始状态也到不了接受状态，语言是空的
+    let nfa = Builder::with_alphabet(b"01")
+        .build_nfa_from_re("[a-c]")
+        .unwrap();
+    assert!(nfa.epsilon_free_dot().unwrap().starts_with("digraph"));
+    let dfa = nfa.remove_epsilon().unwrap().determinize();
+    assert!(!dfa.accepts(""));
+    assert!(!dfa.accepts("0"));
+    assert!(!dfa.accepts("a"));
+}
+
+#[test]
+fn prune_fail_states() {
+    // 0 -ε-> 1(fail), 0 -ε-> 3, 3 -0-> 1(fail), 3 -1-> 2(接受), 4(fail)
+    let mut nfa = NFA::init_empty();
+    let start = nfa.add_epsilon_state();
+    let fail = nfa.add_fail_state();
+    let accept = nfa.add_final_state();
+    let middle = nfa.add_non_epsilon_state();
+    nfa.add_fail_state();
+    nfa.add_epsilon_transition(start, fail);
+    nfa.add_epsilon_transition(start, middle);
+    nfa.add_transition(middle, b'0', fail);
+    nfa.add_transition(middle, b'1', accept);
+    nfa.set_start_state(start);
+    nfa.set_accept_state(accept);
+
+    nfa.prune_fail_states();
+    assert_eq!(nfa.number_of_states(), 3);
+    assert!(nfa
+        .get_states_iter()
+        .all(|state| !matches!(state, State::Fail)));
+    assert_eq!(nfa.start_state(), Some(0));
+    assert_eq!(nfa.accept_states(), &[1]);
+    assert_eq!(nfa.to_edge_list(), vec![(0, None, 2), (2, Some(b'1'), 1)]);
+
+    let dfa = DenseDFA::build_from_nfa(&nfa);
+    assert!(dfa.accepts("1"));
+    assert!(!dfa.accepts("0"));
+    assert!(!dfa.accepts(""));
+}
+
+#[test]
+fn remove_epsilon() {
+    let nfa = Builder::new().build_nfa_from_re("(0|1)*01").unwrap();
+    assert!(nfa
+        .get_states_iter()
+        .any(|state| matches!(state, State::Epsilon(_))));
+
+    let epsilon_free = nfa.remove_epsilon().unwrap();
+    assert!(epsilon_free
+        .get_states_iter()
+        .all(|state| !matches!(state, State::Epsilon(_))));
+    assert!(epsilon_free
+        .to_edge_list()
+        .iter()
+        .all(|(_, input, _)| input.is_some()));
+
+    let dot = nfa.epsilon_free_dot().unwrap();
+    assert_eq!(dot, epsilon_free.to_dot());
+    assert!(!dot.contains('ε'));
+}
+
+#[test]
+fn determinize_over_abc() {
+    use wasm_fa::dfa::CompletedDfa;
+
+    // (a|b)*c(a|c)*：恰好有一个c之前只有a和b，之后只有a和c
+    let nfa = Builder::with_alphabet(b"abc")
+        .build_nfa_from_re("(a|b)*c(a|c)*")
+        .unwrap();
+    let dfa = nfa.determinize();
+    assert_eq!(dfa.alphabet(), &vec![b'a', b'b', b'c']);
+
+    let expected = |input: &str| match input.find('c') {
+        Some(index) => !input[index + 1..].contains('b'),
+        None => false,
+    };
+    let mut inputs = vec![String::new()];
+    for _ in 0..5 {
+        inputs = inputs
+            .iter()
+            .flat_map(|s| ["a", "b", "c"].iter().map(move |c| format!("{}{}", s, c)))
+            .chain(inputs.iter().cloned())
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .collect();
+    }
+    for input in &inputs {
+        assert_eq!(dfa.accepts(input), expected(input), "{:?}", input);
+    }
+
+    // 陷阱状态、读到c之前、读到c之后，共3个状态
+    assert_eq!(dfa.minimize().number_of_states(), 3);
+}
+
+#[test]
+fn determinize_is_reproducible() {
+    // 两个NFA各自的HashSet字母表遍历顺序可以不同，确定化的结果仍然要完全一样
+    let build = || {
+        Builder::with_alphabet(b"zyxabc")
+            .build_nfa_from_re("(a|x)*(b|y)(z|c)*")
+            .unwrap()
+    };
+    let (first, second) = (build(), build());
+    assert_eq!(first.sorted_alphabet(), b"abcxyz".to_vec());
+    assert_eq!(
+        first.determinize().to_json_compact(),
+        first.determinize().to_json_compact()
+    );
+    assert_eq!(
+        first.determinize().to_json_compact(),
+        second.determinize().to_json_compact()
+    );
+}
+
+#[test]
+fn to_dot_merges_class_edges() {
+    let nfa = Builder::with_alphabet(b"0123")
+        .build_nfa_from_re("[0-2]3")
+        .unwrap();
+    assert_eq!(
+        nfa.to_dot(),
+        "digraph {\n\
+         rankdir=LR;\n\
+         node [shape = doublecircle];\n\
+         0;\n\
+         node [shape = circle];\n\
+         1 -> 3 [label=\"ε\"];\n\
+         2 -> 0 [label=\"ε\"];\n\
+         3 -> 5 [label=\"ε\"];\n\
+         4 -> 7 [label=\"ε\"];\n\
+         5 -> 4 [label=\"0,1,2\"];\n\
+         6 -> 2 [label=\"ε\"];\n\
+         7 -> 6 [label=\"3\"];\n\
+         }"
+    );
+
+    // 同一个字符到两个状态时还是两条边，加入顺序不影响输出
+    let mut nfa = NFA::init_empty();
+    let from = nfa.add_non_epsilon_state();
+    let left = nfa.add_final_state();
+    let right = nfa.add_final_state();
+    nfa.set_start_state(from);
+    nfa.add_transition(from, b'1', right);
+    nfa.add_transition(from, b'0', right);
+    nfa.add_transition(from, b'0', left);
+    nfa.add_transition(from, b'0', left);
+    let dot = nfa.to_dot();
+    assert!(dot.contains("0 -> 1 [label=\"0\"];\n0 -> 2 [label=\"0,1\"];\n"));
+}
+
+#[test]
+fn to_dot_epsilon_styles() {
+    let nfa = Builder::new().build_nfa_from_re("0|1*").unwrap();
+    assert_eq!(nfa.to_dot_opts(&NfaDotOptions::default()), nfa.to_dot());
+
+    let dashed = nfa.to_dot_opts(&NfaDotOptions {
+        dashed_epsilon: true,
+        ..NfaDotOptions::default()
+    });
+    let edges: Vec<&str> = dashed.lines().filter(|line| line.contains("->")).collect();
+    assert!(edges.iter().any(|edge| edge.contains("ε")));
+    for edge in edges {
+        assert_eq!(
+            edge.contains("style=dashed"),
+            edge.contains("ε"),
+            "{}",
+            edge
+        );
+    }
+
+    let skeleton = nfa.to_dot_opts(&NfaDotOptions {
+        hide_epsilon: true,
+        ..NfaDotOptions::default()
+    });
+    assert!(!skeleton.contains("ε"));
+    assert_eq!(skeleton.matches("->").count(), 2);
+}
+
+#[test]
+fn plus_repeats_at_least_once() {
+    let dfa = wasm_fa::re_to_dfa("a+").unwrap();
+    assert!(!dfa.accepts(""));
+    assert!(dfa.accepts("a"));
+    assert!(dfa.accepts("aaa"));
+
+    // 正闭包和 rr* 是同一个语言
+    let plus = wasm_fa::re_to_dfa("(01)+1").unwrap();
+    let expected = wasm_fa::re_to_dfa("01(01)*1").unwrap();
+    for input in ["", "1", "011", "01011", "0101", "0111"].iter() {
+        assert_eq!(plus.accepts(input), expected.accepts(input), "{:?}", input);
+    }
+}
+
+#[test]
+fn question_mark_is_optional() {
+    let dfa = wasm_fa::re_to_dfa("a?").unwrap();
+    assert!(dfa.accepts(""));
+    assert!(dfa.accepts("a"));
+    assert!(!dfa.accepts("aa"));
+
+    let dfa = wasm_fa::re_to_dfa("(01)?1").unwrap();
+    assert!(dfa.accepts("1"));
+    assert!(dfa.accepts("011"));
+    assert!(!dfa.accepts("01011"));
+    assert!(!dfa.accepts("01"));
+}
+
+#[test]
+fn bounded_repetition_is_unrolled() {
+    let dfa = wasm_fa::re_to_dfa("0{2,3}").unwrap();
+    assert!(dfa.accepts("00"));
+    assert!(dfa.accepts("000"));
+    assert!(!dfa.accepts("0"));
+    assert!(!dfa.accepts("0000"));
+
+    let dfa = wasm_fa::re_to_dfa("0{2,}1").unwrap();
+    assert!(!dfa.accepts("01"));
+    assert!(dfa.accepts("001"));
+    assert!(dfa.accepts("000001"));
+
+    let dfa = wasm_fa::re_to_dfa("(01){2}|1{0,1}").unwrap();
+    assert!(dfa.accepts("0101"));
+    assert!(dfa.accepts(""));
+    assert!(dfa.accepts("1"));
+    assert!(!dfa.accepts("01"));
+    assert!(!dfa.accepts("11"));
+
+    // 展开很多份只会让连接变宽，不会让构造过程递归得很深
+    let nfa = Builder::new().build_nfa_from_re("(0|1){300}").unwrap();
+    assert!(nfa.number_of_states() > 300);
+
+    // 超过128个NFA状态也能转化为DFA
+    let dfa = wasm_fa::re_to_dfa("0{200}").unwrap();
+    assert!(dfa.accepts(&"0".repeat(200)));
+    assert!(!dfa.accepts(&"0".repeat(199)));
+    assert!(!dfa.accepts(&"0".repeat(201)));
+    let dfa = wasm_fa::re_to_dfa("(0|1){300}").unwrap();
+    assert!(dfa.accepts(&"01".repeat(150)));
+    assert!(!dfa.accepts(&"01".repeat(149)));
+
+    // 嵌套的重复相乘之后太大时返回错误，而不是展开出一百万份
+    let error = Builder::new()
+        .build_nfa_from_re("(a{1000}){1000}")
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("too large"), "{}", error);
+}
@@ -0,0 +1,85 @@
+//! 用`regex`库作为参照，检查从正则表达式到极小DFA的流程：
+//! `re_to_dfa`的 Thompson构造 -> 子集构造 -> 极小化，
+//! 以及先消除空转移再确定化的 Thompson构造 -> 消除空转移 -> 子集构造 -> 极小化。
+//!
+//! 需要`random` feature：`cargo test --features random --test regex_oracle`。
+#![cfg(feature = "random")]
+
+use oorandom::Rand64;
+
+/// 随机生成01字母表上的正则表达式。
+///
+/// 只使用本crate支持的语法：字符、字符类`[01]`、连接、`|`、`*`、`+`、`?`和`{n,m}`。
+/// 锚点和空的正则表达式都不生成。
+fn random_regex(rng: &mut Rand64, depth: u32) -> String {
+    if depth == 0 {
+        return match rng.rand_range(0..3) {
+            0 => "0".to_string(),
+            1 => "1".to_string(),
+            _ => "[01]".to_string(),
+        };
+    }
+    match rng.rand_range(0..7) {
+        0 => format!(
+            "{}{}",
+            random_regex(rng, depth - 1),
+            random_regex(rng, depth - 1)
+        ),
+        1 => format!(
+            "({}|{})",
+            random_regex(rng, depth - 1),
+            random_regex(rng, depth - 1)
+        ),
+        2 => format!("({})*", random_regex(rng, depth - 1)),
+        3 => format!("({})+", random_regex(rng, depth - 1)),
+        4 => format!("({})?", random_regex(rng, depth - 1)),
+        5 => {
+            let min = rng.rand_range(0..3);
+            let max = min + rng.rand_range(0..3);
+            format!("({}){{{},{}}}", random_regex(rng, depth - 1), min, max)
+        }
+        _ => random_regex(rng, depth - 1),
+    }
+}
+
+fn random_binary_string(rng: &mut Rand64, max_len: u64) -> String {
+    let len = rng.rand_range(0..max_len + 1);
+    (0..len)
+        .map(|_| if rng.rand_range(0..2) == 0 { '0' } else { '1' })
+        .collect()
+}
+
+#[test]
+fn minimized_dfa_agrees_with_regex_crate() {
+    let mut rng = Rand64::new(20240733);
+    for _ in 0..200 {
+        let re = random_regex(&mut rng, 4);
+        let dfa = wasm_fa::re_to_dfa(&re).unwrap();
+        let epsilon_free = wasm_fa::nfa::Builder::new()
+            .build_nfa_from_re(&re)
+            .unwrap()
+            .remove_epsilon()
+            .unwrap()
+            .determinize()
+            .minimize();
+        let oracle = regex::Regex::new(&format!("^({})$", re)).unwrap();
+        for _ in 0..100 {
+            let input = random_binary_string(&mut rng, 12);
+            assert_eq!(
+                dfa.accepts(&input),
+                oracle.is_match(&input),
+                "regex {:?} on input {:?}",
+                re,
+                input
+            );
+            assert_eq!(
+                epsilon_free.accepts(&input),
+                oracle.is_match(&input),
+                "regex {:?} on input {:?} after removing epsilon transitions",
+                re,
+                input
+            );
+        }
+        assert!(dfa.is_minimal(), "regex {:?}", re);
+    }
+}